@@ -0,0 +1,57 @@
+use pdfium_render::prelude::*;
+
+// A comment the document itself carries, as opposed to the highlights PDFer keeps in database.json
+#[derive(Clone)]
+pub struct Comment {
+    pub page: u32,
+    // Note, Text box, Highlight, ...
+    pub kind: &'static str,
+    pub author: String,
+    pub text: String,
+}
+
+// Lists the annotations of the document that have comment text, in page order. Popups repeat the text
+// of the annotation they belong to, and form fields and links aren't comments, so those are left out
+pub fn list_comments(path: &str) -> Result<Vec<Comment>, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let mut comments = Vec::new();
+
+    for (page_index, page) in document.pages().iter().enumerate() {
+        for annotation in page.annotations().iter() {
+            let Some(kind) = kind_name(annotation.annotation_type()) else {
+                continue;
+            };
+            let text = annotation.contents().unwrap_or_default();
+            if text.trim().is_empty() {
+                continue;
+            }
+            comments.push(Comment {
+                page: page_index as u32,
+                kind,
+                author: annotation.creator().unwrap_or_default(),
+                text: text.trim().to_string(),
+            });
+        }
+    }
+    Ok(comments)
+}
+
+fn kind_name(annotation_type: PdfPageAnnotationType) -> Option<&'static str> {
+    match annotation_type {
+        PdfPageAnnotationType::Text => Some("Note"),
+        PdfPageAnnotationType::FreeText => Some("Text box"),
+        PdfPageAnnotationType::Highlight => Some("Highlight"),
+        PdfPageAnnotationType::Underline => Some("Underline"),
+        PdfPageAnnotationType::Squiggly => Some("Squiggly"),
+        PdfPageAnnotationType::Strikeout => Some("Strikeout"),
+        PdfPageAnnotationType::Stamp => Some("Stamp"),
+        PdfPageAnnotationType::Ink => Some("Drawing"),
+        PdfPageAnnotationType::Square | PdfPageAnnotationType::Circle => Some("Shape"),
+        PdfPageAnnotationType::Popup
+        | PdfPageAnnotationType::Widget
+        | PdfPageAnnotationType::XfaWidget
+        | PdfPageAnnotationType::Link => None,
+        _ => Some("Comment"),
+    }
+}
@@ -0,0 +1,181 @@
+use crate::clipboard;
+use crate::interface::FileManager;
+use crate::options::named_options;
+use crate::pages;
+use crate::settings::{store_named_setting, Settings};
+use crate::text;
+use crate::{App, BackendPDF};
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use slint::{ComponentHandle, Model, VecModel};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// How a copied quote credits its source. The styles follow the in-text form of each, close enough to
+// paste into a draft and tidy later
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum CitationStyle {
+        #[default]
+        Apa => "APA",
+        Mla => "MLA",
+        Chicago => "Chicago",
+    }
+}
+
+impl CitationStyle {
+    // The quote with its source. Without an author the title stands in for it, the way the styles
+    // cite anonymous works
+    pub fn format(&self, quote: &str, source: &Source, page: &str) -> String {
+        let year = source.year.as_deref().unwrap_or("n.d.");
+        match (self, source.author.as_deref()) {
+            (CitationStyle::Apa, Some(author)) => {
+                format!("\u{201c}{}\u{201d} ({}, {}, p. {}). {}.", quote, author, year, page, source.title)
+            }
+            (CitationStyle::Apa, None) => format!("\u{201c}{}\u{201d} ({}, {}, p. {}).", quote, source.title, year, page),
+            (CitationStyle::Mla, Some(author)) => {
+                format!("\u{201c}{}\u{201d} ({} {}). {}, {}.", quote, surname(author), page, author, source.title)
+            }
+            (CitationStyle::Mla, None) => format!("\u{201c}{}\u{201d} ({} {}).", quote, source.title, page),
+            (CitationStyle::Chicago, Some(author)) => {
+                format!("\u{201c}{}\u{201d} {}, {} ({}), {}.", quote, author, source.title, year, page)
+            }
+            (CitationStyle::Chicago, None) => format!("\u{201c}{}\u{201d} {} ({}), {}.", quote, source.title, year, page),
+        }
+    }
+}
+
+// What a citation says about the document, from its metadata. A document without a title is cited by
+// its file name
+pub struct Source {
+    pub title: String,
+    pub author: Option<String>,
+    pub year: Option<String>,
+}
+
+impl Source {
+    pub fn of(document: &PdfDocument, path: &str) -> Self {
+        let tag = |tag_type| {
+            document
+                .metadata()
+                .get(tag_type)
+                .map(|tag| tag.value().trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let title = tag(PdfDocumentMetadataTagType::Title).unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map_or(path.to_string(), |stem| stem.to_string_lossy().into_owned())
+        });
+        // PDF dates look like D:20190530120000, the year is all a citation needs
+        let year = tag(PdfDocumentMetadataTagType::CreationDate).and_then(|date| {
+            let digits = date.trim_start_matches("D:");
+            let year = digits.get(..4)?;
+            year.chars().all(|c| c.is_ascii_digit()).then(|| year.to_string())
+        });
+        Self {
+            title,
+            author: tag(PdfDocumentMetadataTagType::Author),
+            year,
+        }
+    }
+}
+
+// Last word of the first listed author, which MLA puts in the parentheses
+fn surname(author: &str) -> &str {
+    let first = author.split([',', ';', '&']).next().unwrap_or(author).trim();
+    first.split_whitespace().last().unwrap_or(first)
+}
+
+// Registers the callbacks for copying text with a citation and for picking the citation style
+pub fn bind(app: &App, settings: &Arc<Mutex<Settings>>, file_manager: &Arc<Mutex<FileManager>>) {
+    /*  CALLBACK:
+        Copies the selected text of the current page with a citation of the document, in the
+        citation style, for pasting into notes or a paper. The title, author and year come from the
+        document's metadata, the file name stands in for a missing title
+
+        # Arguments
+        * 'boxes' - the page's character boxes, as get-text-boxes returned them
+        * 'x0', 'y0', 'x1', 'y1' - corners of the selection in pixels of the displayed image
+
+        # Return
+        * What was copied, or why nothing could be
+    */
+    app.global::<BackendPDF>().on_copy_with_citation({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move |boxes, x0, y0, x1, y1| {
+            let Some((first, last)) = text::selected_range(&boxes, x0, y0, x1, y1) else {
+                return "There is no text in the selection".into();
+            };
+            let mut chars: Vec<(i32, slint::SharedString)> = boxes
+                .iter()
+                .filter(|text_box| (first as i32..=last as i32).contains(&text_box.index))
+                .map(|text_box| (text_box.index, text_box.char))
+                .collect();
+            chars.sort_by_key(|(index, _)| *index);
+            let text: String = chars.iter().map(|(_, char)| char.as_str()).collect();
+            let quote = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if quote.is_empty() {
+                return "There is no text in the selection".into();
+            }
+
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(file_path) = file_manager.get_cur_path() else {
+                return "There is no document to cite".into();
+            };
+            let current_page = file_manager.get_cur_file_info().get_cur_page();
+            drop(file_manager);
+            let pdfium = Pdfium::default();
+            let document = match pdfium.load_pdf_from_file(file_path.as_str(), None) {
+                Ok(document) => document,
+                Err(e) => {
+                    eprintln!("Error reading {} for a citation: {}", file_path, e);
+                    return format!("Couldn't read the document: {}", e).into();
+                }
+            };
+            // the printed page number is what a reader looks up, e.g. "xii" in the front matter
+            let page = pages::page_label(&document, current_page).unwrap_or_else(|| (current_page + 1).to_string());
+            let source = Source::of(&document, file_path.as_str());
+            let style = cloned_settings.lock().unwrap().get_citation_style();
+            match clipboard::copy_text(style.format(quote.as_str(), &source, page.as_str()).as_str()) {
+                Ok(_) => format!("Selection copied with a {} citation", style.name()).into(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    e.into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the citation styles a selection can be copied with
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of citation style names
+    */
+    app.global::<BackendPDF>().on_get_citation_styles(|| {
+        let names: Vec<slint::SharedString> = CitationStyle::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves the style of citations copied with a selection
+
+        # Arguments
+        * 'name' - citation style name: APA, MLA or Chicago
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_citation_style(store_named_setting(
+        settings,
+        "citation style",
+        CitationStyle::from_name,
+        Settings::set_citation_style,
+    ));
+}
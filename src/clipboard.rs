@@ -0,0 +1,62 @@
+use arboard::{Clipboard, ImageData};
+use std::borrow::Cow;
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// On X11 and Wayland the copied content is served by the clipboard object and goes away with it, so
+// the clipboard is opened once and kept for the whole session
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+// Runs action on the session's clipboard, opening it on first use. Opening is tried again next time
+// if it fails, e.g. before the display server is up
+fn with_clipboard<T>(action: impl FnOnce(&mut Clipboard) -> Result<T, String>) -> Result<T, String> {
+    let mut clipboard = CLIPBOARD.lock().unwrap();
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().map_err(|e| format!("The clipboard isn't available: {}", e))?);
+    }
+    action(clipboard.as_mut().unwrap())
+}
+
+// Writes the image on the clipboard to a new PNG in dir and returns its path. Err if the clipboard
+// holds no image or the file can't be written
+pub fn save_clipboard_image(dir: &Path) -> Result<PathBuf, String> {
+    let data = with_clipboard(|clipboard| {
+        clipboard
+            .get_image()
+            .map_err(|_| "No image on the clipboard".to_string())
+    })?;
+    let image = RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+        .ok_or_else(|| "Clipboard image has an unexpected size".to_string())?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+    let path = dir.join(format!("pasted-{}.png", stamp));
+    image.save(&path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+// Puts the image on the clipboard for pasting into other apps. Err if there is no clipboard to
+// write to, e.g. no display server or another app holding it
+pub fn copy_image(image: &RgbaImage) -> Result<(), String> {
+    with_clipboard(|clipboard| {
+        clipboard
+            .set_image(ImageData {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                bytes: Cow::Borrowed(image.as_raw()),
+            })
+            .map_err(|e| format!("Couldn't copy to the clipboard: {}", e))
+    })
+}
+
+// Puts the text on the clipboard. Err if there is no clipboard to write to
+pub fn copy_text(text: &str) -> Result<(), String> {
+    with_clipboard(|clipboard| {
+        clipboard
+            .set_text(text)
+            .map_err(|e| format!("Couldn't copy to the clipboard: {}", e))
+    })
+}
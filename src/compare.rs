@@ -0,0 +1,361 @@
+use crate::interface::{self, FileManager};
+use crate::pages;
+use crate::render::{self, RenderQuality};
+use crate::settings::Settings;
+use crate::{App, BackendCompare};
+use pdfium_render::prelude::*;
+use slint::ComponentHandle;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+// How far apart two pixels' channels must be to count toward the changed share of a page, so
+// anti-aliasing noise between two renders of the same text doesn't
+const CHANGED_THRESHOLD: u8 = 48;
+// Strength of the heatmap over the pages, 255 showing every difference fully red
+const HEATMAP_GAIN: u32 = 3;
+
+// Two documents side by side, each at its own page. With sync on both sides turn pages together and
+// scroll as one
+pub struct Comparison {
+    pub paths: [String; 2],
+    pub counts: [u32; 2],
+    pub pages: [u32; 2],
+    pub synced: bool,
+    // share of the shown pages' pixels that changed, from the last heatmap
+    pub changed: Option<f32>,
+}
+
+impl Comparison {
+    // Opens both documents to count their pages, starting each at its first page
+    pub fn new(path_a: &str, path_b: &str) -> Result<Self, String> {
+        let count = |path: &str| render::document_page_count(path).ok_or_else(|| format!("Couldn't open {}", path));
+        Ok(Self {
+            paths: [path_a.to_string(), path_b.to_string()],
+            counts: [count(path_a)?, count(path_b)?],
+            pages: [0, 0],
+            synced: true,
+            changed: None,
+        })
+    }
+
+    // Turns the page of one side. In sync both sides turn, as far as the longer document goes, and the
+    // shorter one shows that it has no such page
+    pub fn step(&mut self, side: usize, forward: bool) {
+        let sides = if self.synced { vec![0, 1] } else { vec![side] };
+        let limit = if self.synced { self.counts[0].max(self.counts[1]) } else { self.counts[side] };
+        let current = self.pages[side];
+        let next = if forward {
+            current.checked_add(1).filter(|&next| next < limit)
+        } else {
+            current.checked_sub(1)
+        };
+        if let Some(next) = next {
+            for side in sides {
+                self.pages[side] = next;
+            }
+            self.changed = None;
+        }
+    }
+
+    // Turns sync on or off, turning it on brings the second side to the first side's page
+    pub fn toggle_sync(&mut self) -> bool {
+        self.synced = !self.synced;
+        if self.synced {
+            self.pages[1] = self.pages[0];
+            self.changed = None;
+        }
+        self.synced
+    }
+
+    // The side's page the way the viewer labels it, or that the document is too short for it
+    pub fn describe(&self, side: usize) -> String {
+        let (page, count) = (self.pages[side], self.counts[side]);
+        if page < count {
+            format!("Page {} of {}", page + 1, count)
+        } else {
+            format!("No page {}, this document has {} pages", page + 1, count)
+        }
+    }
+
+    // The side's current page at the pixel size it's rendered at alone, None past the document's end.
+    // A page that can't be rendered becomes the error placeholder
+    pub fn render_side(&self, pdfium: &Pdfium, side: usize, quality: RenderQuality) -> Option<image::RgbaImage> {
+        self.render_side_at(pdfium, side, quality, None)
+    }
+
+    fn render_side_at(
+        &self,
+        pdfium: &Pdfium,
+        side: usize,
+        quality: RenderQuality,
+        size: Option<(i32, i32)>,
+    ) -> Option<image::RgbaImage> {
+        if self.pages[side] >= self.counts[side] {
+            return None;
+        }
+        let result = pdfium
+            .load_pdf_from_file(self.paths[side].as_str(), None)
+            .and_then(|document| {
+                let page = pages::get_page(&document, self.pages[side])?;
+                let (width, height) = size.unwrap_or_else(|| render::page_render_size(&page));
+                render::render_page_at(&page, quality, true, width, height)
+            });
+        match result {
+            Ok(image) => Some(image),
+            Err(e) => {
+                eprintln!("Error rendering page {} of {}: {}", self.pages[side] + 1, self.paths[side], e);
+                let (width, height) = size.unwrap_or((600, 800));
+                Some(render::error_placeholder(width as u32, height as u32))
+            }
+        }
+    }
+
+    // Renders both current pages at the first side's size and marks where they differ, storing the
+    // changed share. A side past its document's end counts as a blank page. None when neither side has
+    // a page
+    pub fn heatmap(&mut self, pdfium: &Pdfium, quality: RenderQuality) -> Option<image::RgbaImage> {
+        let first = self.render_side(pdfium, 0, quality);
+        let size = first.as_ref().map(|image| (image.width() as i32, image.height() as i32));
+        let second = self.render_side_at(pdfium, 1, quality, size);
+        let (first, second) = match (first, second) {
+            (Some(first), Some(second)) => (first, second),
+            (Some(page), None) | (None, Some(page)) => {
+                let blank = image::RgbaImage::from_pixel(page.width(), page.height(), image::Rgba([255, 255, 255, 255]));
+                (page, blank)
+            }
+            (None, None) => return None,
+        };
+        let (map, changed) = difference(&first, &second);
+        self.changed = Some(changed);
+        Some(map)
+    }
+}
+
+// A transparent image the size of a, red where b differs from it, stronger the more it does, along
+// with the share of pixels that clearly changed. b is expected at a's size, anything outside it counts
+// as changed
+pub fn difference(a: &image::RgbaImage, b: &image::RgbaImage) -> (image::RgbaImage, f32) {
+    let mut changed = 0u64;
+    let map = image::RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let delta = if x < b.width() && y < b.height() {
+            let (pa, pb) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+            (0..3).map(|channel| pa[channel].abs_diff(pb[channel])).max().unwrap_or(0)
+        } else {
+            u8::MAX
+        };
+        if delta >= CHANGED_THRESHOLD {
+            changed += 1;
+        }
+        let alpha = (delta as u32 * HEATMAP_GAIN).min(u8::MAX as u32) as u8;
+        image::Rgba([230, 30, 30, alpha])
+    });
+    let total = (a.width() as u64 * a.height() as u64).max(1);
+    (map, changed as f32 / total as f32)
+}
+
+// Registers the callbacks of the side by side comparison of the open document with another
+pub fn bind(app: &App, settings: &Arc<Mutex<Settings>>, file_manager: &Arc<Mutex<FileManager>>) {
+    let comparison: Rc<RefCell<Option<Comparison>>> = Rc::new(RefCell::new(None));
+
+    /*  CALLBACK:
+        Returns the path of the open document, the first side of a comparison
+
+        # Arguments
+        N/A
+
+        # Return
+        * The document's path, empty if none is open
+    */
+    app.global::<BackendCompare>().on_current_document({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_filepath().into()
+    });
+
+    /*  CALLBACK:
+        Asks for the document to compare the open one with
+
+        # Arguments
+        N/A
+
+        # Return
+        * The picked PDF's path, empty if the user cancelled
+    */
+    app.global::<BackendCompare>().on_pick_document(|| interface::open_pdf_dialog().unwrap_or_default().into());
+
+    /*  CALLBACK:
+        Opens two documents side by side, each at its first page with sync scrolling on, and switches to
+        the compare view
+
+        # Arguments
+        * 'path_a' - document shown on the left, the heatmap is measured against it
+        * 'path_b' - document shown on the right
+
+        # Return
+        * Why the documents couldn't be compared, empty if they could or a path is missing
+    */
+    app.global::<BackendCompare>().on_compare_documents({
+        let app_weak = app.as_weak();
+        let comparison = comparison.clone();
+        move |path_a, path_b| {
+            if path_a.is_empty() || path_b.is_empty() {
+                return "".into();
+            }
+            if interface::same_file(path_a.as_str(), path_b.as_str()) {
+                return "Pick a different document to compare with".into();
+            }
+            match Comparison::new(path_a.as_str(), path_b.as_str()) {
+                Ok(opened) => {
+                    let app = app_weak.unwrap();
+                    app.global::<BackendCompare>().set_synced(opened.synced);
+                    app.global::<BackendCompare>().set_difference("".into());
+                    *comparison.borrow_mut() = Some(opened);
+                    app.set_active_page(2);
+                    "".into()
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    e.into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Turns sync scrolling of the comparison on or off. Turning it on brings the right side to the
+        left side's page
+
+        # Arguments
+        N/A
+
+        # Return
+        * true if the sides now scroll and turn pages together
+    */
+    app.global::<BackendCompare>().on_toggle_sync_scroll({
+        let comparison = comparison.clone();
+        move || match comparison.borrow_mut().as_mut() {
+            Some(comparison) => comparison.toggle_sync(),
+            None => false,
+        }
+    });
+
+    /*  CALLBACK:
+        Turns a page of the comparison, of both sides while they are in sync
+
+        # Arguments
+        * 'side' - 0 for the left document, 1 for the right
+        * 'forward' - true for the next page, false for the previous
+
+        # Return
+        N/A
+    */
+    app.global::<BackendCompare>().on_navigate({
+        let comparison = comparison.clone();
+        move |side, forward| {
+            if let Some(comparison) = comparison.borrow_mut().as_mut() {
+                comparison.step(side.clamp(0, 1) as usize, forward);
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Renders the current page of one side of the comparison
+
+        # Arguments
+        * 'side' - 0 for the left document, 1 for the right
+
+        # Return
+        * The page, an empty image when that document has no such page
+    */
+    app.global::<BackendCompare>().on_render_side({
+        let comparison = comparison.clone();
+        let cloned_settings = settings.clone();
+        move |side| {
+            let quality = cloned_settings.lock().unwrap().get_render_quality();
+            let pdfium = Pdfium::default();
+            comparison
+                .borrow()
+                .as_ref()
+                .and_then(|comparison| comparison.render_side(&pdfium, side.clamp(0, 1) as usize, quality))
+                .map(|page| render::to_slint_image(&page))
+                .unwrap_or_default()
+        }
+    });
+
+    /*  CALLBACK:
+        Labels the current page of one side of the comparison
+
+        # Arguments
+        * 'side' - 0 for the left document, 1 for the right
+
+        # Return
+        * e.g. "Page 3 of 10", or that the document has no such page
+    */
+    app.global::<BackendCompare>().on_describe_side({
+        let comparison = comparison.clone();
+        move |side| match comparison.borrow().as_ref() {
+            Some(comparison) => comparison.describe(side.clamp(0, 1) as usize).into(),
+            None => "".into(),
+        }
+    });
+
+    /*  CALLBACK:
+        Renders where the two current pages differ, red over transparent, to lay over the pages
+
+        # Arguments
+        N/A
+
+        # Return
+        * The heatmap at the left page's size, an empty image when neither side has a page
+    */
+    app.global::<BackendCompare>().on_get_heatmap({
+        let comparison = comparison.clone();
+        let cloned_settings = settings.clone();
+        move || {
+            let quality = cloned_settings.lock().unwrap().get_render_quality();
+            let pdfium = Pdfium::default();
+            comparison
+                .borrow_mut()
+                .as_mut()
+                .and_then(|comparison| comparison.heatmap(&pdfium, quality))
+                .map(|map| render::to_slint_image(&map))
+                .unwrap_or_default()
+        }
+    });
+
+    /*  CALLBACK:
+        Describes how much of the current pages differs, as measured by the last heatmap
+
+        # Arguments
+        N/A
+
+        # Return
+        * e.g. "4.2% of the page differs", empty before a heatmap was made
+    */
+    app.global::<BackendCompare>().on_get_difference({
+        let comparison = comparison.clone();
+        move || match comparison.borrow().as_ref().and_then(|comparison| comparison.changed) {
+            Some(0.0) => "The pages are identical".into(),
+            Some(changed) => format!("{:.1}% of the page differs", changed * 100.0).into(),
+            None => "".into(),
+        }
+    });
+
+    /*  CALLBACK:
+        Leaves the compare view for the open document
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendCompare>().on_close_compare({
+        let app_weak = app.as_weak();
+        let comparison = comparison.clone();
+        move || {
+            *comparison.borrow_mut() = None;
+            app_weak.unwrap().set_active_page(1);
+        }
+    });
+}
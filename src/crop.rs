@@ -0,0 +1,108 @@
+use image::RgbaImage;
+use std::collections::HashMap;
+
+// Channel value below which a pixel counts as content rather than paper
+const PAPER_THRESHOLD: u8 = 240;
+
+// Part of a rendered page to display, in pixels of the full render
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CropBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropBox {
+    // The whole render, i.e. no cropping
+    pub fn full(width: u32, height: u32) -> Self {
+        CropBox { x: 0, y: 0, width, height }
+    }
+
+    // The same part of a render scaled by the factor, kept inside an image of width x height
+    pub fn scaled(&self, scale: f32, width: u32, height: u32) -> Self {
+        let x = ((self.x as f32 * scale).round() as u32).min(width.saturating_sub(1));
+        let y = ((self.y as f32 * scale).round() as u32).min(height.saturating_sub(1));
+        CropBox {
+            x,
+            y,
+            width: ((self.width as f32 * scale).round() as u32).clamp(1, width - x),
+            height: ((self.height as f32 * scale).round() as u32).clamp(1, height - y),
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x as f32
+            && y >= self.y as f32
+            && x <= (self.x + self.width) as f32
+            && y <= (self.y + self.height) as f32
+    }
+}
+
+// Bounding box of everything that isn't white paper, grown by padding on each side. A blank page
+// keeps its full size
+pub fn content_bounds(image: &RgbaImage, padding: u32) -> CropBox {
+    let (width, height) = image.dimensions();
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a > 0 && (r < PAPER_THRESHOLD || g < PAPER_THRESHOLD || b < PAPER_THRESHOLD) {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+    }
+    if left > right || top > bottom {
+        return CropBox::full(width, height);
+    }
+    let x = left.saturating_sub(padding);
+    let y = top.saturating_sub(padding);
+    CropBox {
+        x,
+        y,
+        width: (right + padding + 1).min(width) - x,
+        height: (bottom + padding + 1).min(height) - y,
+    }
+}
+
+// Crop box for margins given as percentages of the page's width and height (left, top, right, bottom)
+pub fn from_margins(width: u32, height: u32, margins: [u8; 4]) -> CropBox {
+    let [left, top, right, bottom] = margins.map(|margin| margin.min(45) as u32);
+    let x = width * left / 100;
+    let y = height * top / 100;
+    CropBox {
+        x,
+        y,
+        width: (width - width * right / 100 - x).max(1),
+        height: (height - height * bottom / 100 - y).max(1),
+    }
+}
+
+pub fn crop_image(image: &RgbaImage, crop: CropBox) -> RgbaImage {
+    image::imageops::crop_imm(image, crop.x, crop.y, crop.width, crop.height).to_image()
+}
+
+// Detected content boxes by document and page, so a page is only scanned once
+#[derive(Default)]
+pub struct CropCache {
+    boxes: HashMap<(String, u32), CropBox>,
+}
+
+impl CropCache {
+    pub fn get(&self, path: &str, page: u32) -> Option<CropBox> {
+        self.boxes.get(&(path.to_string(), page)).copied()
+    }
+
+    pub fn get_or_detect(&mut self, path: &str, page: u32, image: &RgbaImage, padding: u32) -> CropBox {
+        *self
+            .boxes
+            .entry((path.to_string(), page))
+            .or_insert_with(|| content_bounds(image, padding))
+    }
+
+    // Needed when the padding changes, since it is part of every cached box
+    pub fn clear(&mut self) {
+        self.boxes.clear();
+    }
+}
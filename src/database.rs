@@ -0,0 +1,130 @@
+use crate::interface::FileInfo;
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Layout of database.json. Version 0 is the bare array of files written before the file had a version,
+// each later version is reached from the one before it by migrate
+pub const DATABASE_VERSION: u64 = 1;
+
+// The current layout as read, the version was already checked by then
+#[derive(Deserialize)]
+struct Database {
+    files: Vec<FileInfo>,
+}
+
+// Reads database.json in any layout PDFer has written, upgraded to the current one. A database from a
+// newer PDFer is read as far as this version understands it, fields it doesn't know are dropped
+pub fn parse(data: &str) -> serde_json::Result<Vec<FileInfo>> {
+    let mut value: Value = serde_json::from_str(data)?;
+    loop {
+        let version = version_of(&value)?;
+        if version >= DATABASE_VERSION {
+            if version > DATABASE_VERSION {
+                eprintln!("database.json is version {}, newer than this PDFer's {}", version, DATABASE_VERSION);
+            }
+            let database: Database = serde_json::from_value(value)?;
+            return Ok(database.files);
+        }
+        value = migrate(value, version);
+    }
+}
+
+// Whether the database was written by a newer PDFer, whose fields parse dropped
+pub fn is_newer(data: &str) -> bool {
+    serde_json::from_str::<Value>(data)
+        .ok()
+        .and_then(|value| version_of(&value).ok())
+        .is_some_and(|version| version > DATABASE_VERSION)
+}
+
+// Writes the files in the current layout
+pub fn serialize(files: &[FileInfo]) -> String {
+    #[derive(Serialize)]
+    struct DatabaseRef<'a> {
+        version: u64,
+        files: &'a [FileInfo],
+    }
+    serde_json::to_string(&DatabaseRef {
+        version: DATABASE_VERSION,
+        files,
+    })
+    .unwrap()
+}
+
+fn version_of(value: &Value) -> serde_json::Result<u64> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(fields) => fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| serde_json::Error::custom("database.json has no version")),
+        _ => Err(serde_json::Error::custom("database.json is neither a list of files nor a versioned database")),
+    }
+}
+
+// Upgrades a database from the version to the next one
+fn migrate(value: Value, version: u64) -> Value {
+    match version {
+        // the bare array becomes the files of a versioned object, the entries themselves are unchanged
+        0 => serde_json::json!({ "version": 1, "files": value }),
+        _ => unreachable!("no migration from version {}", version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // database.json as PDFer wrote it before it had a version
+    const VERSION_0: &str = r#"[
+        {"name": "a.pdf", "filepath": "/books/a.pdf", "cur_file_page": 12},
+        {"name": "b.pdf", "filepath": "/books/b.pdf", "cur_file_page": 0}
+    ]"#;
+
+    #[test]
+    fn a_bare_array_migrates_to_the_current_version() {
+        let mut files = parse(VERSION_0).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].get_name(), "a.pdf");
+        assert_eq!(files[0].get_filepath(), "/books/a.pdf");
+        assert_eq!(files[0].get_cur_page(), 12);
+        // fields added since are filled in with their defaults
+        assert!(files[1].get_highlights().is_empty());
+        assert!(!files[1].get_finished());
+    }
+
+    #[test]
+    fn migration_wraps_the_files_unchanged() {
+        let value: Value = serde_json::from_str(VERSION_0).unwrap();
+        let migrated = migrate(value.clone(), 0);
+        assert_eq!(version_of(&migrated).unwrap(), 1);
+        assert_eq!(migrated["files"], value);
+    }
+
+    #[test]
+    fn saved_databases_read_back() {
+        let files = parse(VERSION_0).unwrap();
+        let saved = serialize(&files);
+        assert_eq!(version_of(&serde_json::from_str(&saved).unwrap()).unwrap(), DATABASE_VERSION);
+        let read = parse(&saved).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].get_filepath(), "/books/a.pdf");
+    }
+
+    #[test]
+    fn newer_databases_are_read_as_far_as_understood() {
+        let newer = r#"{"version": 99, "files": [{"name": "a.pdf", "filepath": "/a.pdf", "cur_file_page": 1, "tags": ["x"]}]}"#;
+        assert_eq!(parse(newer).unwrap()[0].get_filepath(), "/a.pdf");
+        assert!(is_newer(newer));
+        assert!(!is_newer(VERSION_0));
+        assert!(!is_newer(&serialize(&[])));
+    }
+
+    #[test]
+    fn other_json_is_refused() {
+        assert!(parse(r#"{"files": []}"#).is_err());
+        assert!(parse("3").is_err());
+        assert!(parse("not json").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::highlights::Highlight;
+use crate::pages;
+use crate::pins::CommentPin;
+use crate::text;
+use pdfium_render::prelude::*;
+
+// Copies the pages first..=last (zero based) of the document at path into a new PDF saved at output.
+// A page that can't be copied is replaced by a blank page of its size saying so, rather than failing
+// the whole range. Returns the pages that were replaced
+pub fn export_page_range(path: &str, first: u32, last: u32, output: &str) -> Result<Vec<u32>, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let source = pdfium.load_pdf_from_file(path, None)?;
+    let mut document = pdfium.create_new_pdf()?;
+    let mut failed = Vec::new();
+
+    for index in first..=last {
+        let destination = document.pages().len();
+        let copied = pages::pdfium_index(index).and_then(|source_index| {
+            document
+                .pages_mut()
+                .copy_page_from_document(&source, source_index, destination)
+        });
+        if let Err(e) = copied {
+            eprintln!("Error copying page {}: {}", index + 1, e);
+            add_placeholder_page(&mut document, &source, index)?;
+            failed.push(index);
+        }
+    }
+    document.save_to_file(output)?;
+    Ok(failed)
+}
+
+// Blank page with a note, sized like the source page when its size can still be read
+fn add_placeholder_page(document: &mut PdfDocument, source: &PdfDocument, index: u32) -> Result<(), PdfiumError> {
+    let size = match pages::get_page(source, index) {
+        Ok(page) => PdfPagePaperSize::from_points(page.width(), page.height()),
+        Err(_) => PdfPagePaperSize::a4(),
+    };
+    let font = document.fonts_mut().helvetica();
+    let mut page = document.pages_mut().create_page_at_end(size)?;
+    let top = page.height().value - 72.0;
+    page.objects_mut().create_text_object(
+        PdfPoints::new(72.0),
+        PdfPoints::new(top),
+        format!("Page {} could not be copied from the original", index + 1).as_str(),
+        font,
+        PdfPoints::new(14.0),
+    )?;
+    Ok(())
+}
+
+// A Markdown summary of the document's highlights for review: the text under each, grouped by page
+// and in reading order, followed by the page's comment pins top to bottom. A highlight whose
+// characters yield no text, or on a page that can't be loaded, is listed as such rather than dropped
+pub fn highlights_summary(
+    path: &str,
+    name: &str,
+    highlights: &[Highlight],
+    pins: &[CommentPin],
+) -> Result<String, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let mut ordered: Vec<&Highlight> = highlights.iter().collect();
+    ordered.sort_by_key(|highlight| (highlight.page, highlight.first));
+    let mut ordered_pins: Vec<&CommentPin> = pins.iter().collect();
+    ordered_pins.sort_by(|a, b| a.page.cmp(&b.page).then(a.y.total_cmp(&b.y)));
+    let mut marked_pages: Vec<u32> = ordered
+        .iter()
+        .map(|highlight| highlight.page)
+        .chain(ordered_pins.iter().map(|pin| pin.page))
+        .collect();
+    marked_pages.sort_unstable();
+    marked_pages.dedup();
+
+    let mut summary = format!("# Highlights from {}\n", name);
+    if marked_pages.is_empty() {
+        summary += "\nNo highlights yet.\n";
+    }
+    for page_index in marked_pages {
+        summary += &format!("\n## Page {}\n\n", page_index + 1);
+        let page_highlights: Vec<&&Highlight> = ordered.iter().filter(|highlight| highlight.page == page_index).collect();
+        let boxes = if page_highlights.is_empty() {
+            Vec::new()
+        } else {
+            match pages::get_page(&document, page_index) {
+                Ok(page) => text::text_boxes(&page),
+                Err(e) => {
+                    eprintln!("Error loading page {}: {}", page_index + 1, e);
+                    Vec::new()
+                }
+            }
+        };
+        for highlight in page_highlights {
+            let first = highlight.first as usize;
+            let last = (highlight.last as usize).min(boxes.len().saturating_sub(1));
+            let highlighted: String = boxes
+                .get(first..=last)
+                .unwrap_or_default()
+                .iter()
+                .map(|text_box| text_box.text.as_str())
+                .collect();
+            let highlighted = highlighted.split_whitespace().collect::<Vec<_>>().join(" ");
+            if highlighted.is_empty() {
+                summary += "- *(no text under this highlight)*\n";
+            } else {
+                summary += &format!("- {}\n", highlighted);
+            }
+        }
+        for pin in ordered_pins.iter().filter(|pin| pin.page == page_index) {
+            summary += &format!("- > {}\n", pin.text.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+    Ok(summary)
+}
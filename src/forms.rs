@@ -0,0 +1,71 @@
+use crate::pages;
+use pdfium_render::prelude::*;
+
+// A fillable text field or checkbox, addressed by its page and annotation index
+#[derive(Clone)]
+pub struct FormField {
+    pub page: u32,
+    pub annotation: usize,
+    pub name: String,
+    pub is_checkbox: bool,
+    pub value: String,
+    pub checked: bool,
+}
+
+// Lists the text fields and checkboxes in the document with their current values
+pub fn list_fields(path: &str) -> Result<Vec<FormField>, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let mut fields = Vec::new();
+
+    for (page_index, page) in document.pages().iter().enumerate() {
+        for (annotation_index, annotation) in page.annotations().iter().enumerate() {
+            let Some(field) = annotation.as_form_field() else {
+                continue;
+            };
+            let name = field.name().unwrap_or_else(|| format!("Field {}", fields.len() + 1));
+            if let Some(text) = field.as_text_field() {
+                fields.push(FormField {
+                    page: page_index as u32,
+                    annotation: annotation_index,
+                    name,
+                    is_checkbox: false,
+                    value: text.value().unwrap_or_default(),
+                    checked: false,
+                });
+            } else if let Some(checkbox) = field.as_checkbox_field() {
+                fields.push(FormField {
+                    page: page_index as u32,
+                    annotation: annotation_index,
+                    name,
+                    is_checkbox: true,
+                    value: String::new(),
+                    checked: checkbox.is_checked().unwrap_or(false),
+                });
+            }
+        }
+    }
+    Ok(fields)
+}
+
+// Writes the filled in values into a copy of the document saved at output, leaving the source untouched
+pub fn save_filled(path: &str, fields: &[FormField], output: &str) -> Result<(), PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+
+    for filled in fields {
+        let page = pages::get_page(&document, filled.page)?;
+        let mut annotation = page.annotations().get(filled.annotation)?;
+        let Some(field) = annotation.as_form_field_mut() else {
+            continue;
+        };
+        if filled.is_checkbox {
+            if let Some(checkbox) = field.as_checkbox_field_mut() {
+                checkbox.set_checked(filled.checked)?;
+            }
+        } else if let Some(text) = field.as_text_field_mut() {
+            text.set_value(filled.value.as_str())?;
+        }
+    }
+    document.save_to_file(output)
+}
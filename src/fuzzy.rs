@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+
+// Scores how well query matches candidate as a case-insensitive subsequence. Consecutive characters
+// and matches at the start of a word score higher, gaps score lower. None means no match at all.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut matched = 0;
+    let mut last: Option<usize> = None;
+
+    for (i, ch) in chars.iter().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        if *ch != query[matched] {
+            continue;
+        }
+        score += 1;
+        match last {
+            Some(prev) if prev + 1 == i => score += 5,
+            Some(prev) => score -= (i - prev - 1).min(3) as i32,
+            None => score -= i.min(3) as i32,
+        }
+        if i == 0 || !chars[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(i);
+        matched += 1;
+    }
+
+    if matched < query.len() {
+        return None;
+    }
+    Some(score)
+}
+
+// Returns the indices of the matching candidates, best match first. Equal scores keep their original
+// order so recency is used as the tie breaker.
+pub fn rank(query: &str, candidates: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(usize, i32)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, candidate)| score(query, candidate).map(|s| (i, s)))
+        .collect();
+    scored.sort_by_key(|&(_, s)| Reverse(s));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn letters_out_of_order_do_not_match() {
+        assert_eq!(score("fdp", "file.pdf"), None);
+        assert_eq!(score("zzz", "file.pdf"), None);
+        assert_eq!(score("pdff", "file.pdf"), None);
+    }
+
+    #[test]
+    fn case_and_spaces_in_the_query_are_ignored() {
+        assert_eq!(score("RUST book", "the-rust-book.pdf"), score("rustbook", "The-Rust-Book.pdf"));
+        assert_eq!(score("  ", "anything.pdf"), Some(0));
+    }
+
+    #[test]
+    fn consecutive_letters_beat_scattered_ones() {
+        assert!(score("book", "bookmarks.pdf") > score("book", "b-o-o-k.pdf"));
+    }
+
+    #[test]
+    fn word_starts_beat_the_middle_of_a_word() {
+        assert!(score("book", "my-book.pdf") > score("book", "mybook.pdf"));
+    }
+
+    #[test]
+    fn ranking_puts_the_best_match_first_and_drops_the_rest() {
+        let recents = names(&["mybook.pdf", "notes.txt", "my-book.pdf"]);
+        assert_eq!(rank("book", &recents), [2, 0]);
+    }
+
+    #[test]
+    fn equal_scores_keep_the_recents_order() {
+        let recents = names(&["report-2023.pdf", "report-2024.pdf"]);
+        assert_eq!(rank("report", &recents), [0, 1]);
+    }
+}
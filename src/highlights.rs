@@ -0,0 +1,265 @@
+use crate::interface::FileManager;
+use crate::render;
+use crate::session::SessionLog;
+use crate::settings::{self, Settings};
+use crate::text;
+use crate::{App, BackendPDF, HighlightColor};
+use serde::{Deserialize, Serialize};
+use slint::{ComponentHandle, VecModel};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+// Colors offered next to a selection, as (name, "#RRGGBB"). The first is the default
+pub const PALETTE: [(&str, &str); 4] = [
+    ("Yellow", "#ffeb3b"),
+    ("Green", "#8bc34a"),
+    ("Pink", "#f48fb1"),
+    ("Blue", "#64b5f6"),
+];
+// Hues of the custom color picker, each in a light and a strong shade
+const PICKER_HUES: u32 = 12;
+// Opacity of a highlight drawn over the page, enough to tint the text without hiding it
+const OVERLAY_ALPHA: u8 = 110;
+
+// A highlighted run of characters on a page. first and last index the page's character boxes as
+// text::text_boxes returns them, both included
+#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize)]
+pub struct Highlight {
+    pub page: u32,
+    pub first: u32,
+    pub last: u32,
+    // "#RRGGBB"
+    pub color: String,
+}
+
+impl Highlight {
+    pub fn covers(&self, page: u32, index: u32) -> bool {
+        self.page == page && self.first <= index && index <= self.last
+    }
+}
+
+// Color of the character, the most recent highlight wins where they overlap
+pub fn color_at(highlights: &[Highlight], page: u32, index: u32) -> Option<&str> {
+    highlights
+        .iter()
+        .rev()
+        .find(|highlight| highlight.covers(page, index))
+        .map(|highlight| highlight.color.as_str())
+}
+
+// Red, green and blue of a "#RRGGBB" color
+pub fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+// Translucent color a highlight of the "#RRGGBB" color is drawn over its characters in, gray when
+// highlights follow a grayscale page. Transparent for no highlight
+pub fn overlay_color(color: Option<&str>, gray: bool) -> slint::Color {
+    match color.and_then(parse_hex) {
+        Some((r, g, b)) if gray => {
+            let gray = render::luminance(r, g, b);
+            slint::Color::from_argb_u8(OVERLAY_ALPHA, gray, gray, gray)
+        }
+        Some((r, g, b)) => slint::Color::from_argb_u8(OVERLAY_ALPHA, r, g, b),
+        None => slint::Color::from_argb_u8(0, 0, 0, 0),
+    }
+}
+
+// Swatches of the custom color picker as "#RRGGBB", light shades first
+pub fn picker_colors() -> Vec<String> {
+    [(0.35, 1.0), (0.75, 0.9)]
+        .iter()
+        .flat_map(|&(saturation, value)| {
+            (0..PICKER_HUES).map(move |hue| hsv_hex(hue as f32 * 360.0 / PICKER_HUES as f32, saturation, value))
+        })
+        .collect()
+}
+
+fn hsv_hex(hue: f32, saturation: f32, value: f32) -> String {
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+// Registers the callbacks for highlighting selected text and picking the highlight color
+pub fn bind(
+    app: &App,
+    settings: &Arc<Mutex<Settings>>,
+    file_manager: &Arc<Mutex<FileManager>>,
+    session: &Rc<RefCell<SessionLog>>,
+    schedule_save: &Rc<dyn Fn()>,
+) {
+    /*  CALLBACK:
+        Returns the preset highlight colors
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of colors with their names
+    */
+    app.global::<BackendPDF>().on_get_highlight_palette(|| {
+        let colors: Vec<HighlightColor> = PALETTE
+            .iter()
+            .map(|&(name, hex)| highlight_color(name, hex))
+            .collect();
+        slint::ModelRc::new(VecModel::from(colors))
+    });
+
+    /*  CALLBACK:
+        Returns the swatches of the custom highlight color picker
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of colors, named by their hex value
+    */
+    app.global::<BackendPDF>().on_get_picker_colors(|| {
+        let colors: Vec<HighlightColor> = picker_colors()
+            .iter()
+            .map(|hex| highlight_color(hex, hex))
+            .collect();
+        slint::ModelRc::new(VecModel::from(colors))
+    });
+
+    /*  CALLBACK:
+        Sets the color used for new highlights, kept as the default for later sessions
+
+        # Arguments
+        * 'color' - "#RRGGBB" color
+
+        # Return
+        * false if the color isn't a valid hex color, which leaves the current one
+    */
+    app.global::<BackendPDF>().on_set_highlight_color({
+        let cloned_settings = settings.clone();
+        move |color| {
+            if parse_hex(color.as_str()).is_none() {
+                return false;
+            }
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_highlight_color(color.trim());
+            settings.save(settings::SETTINGS_FILE);
+            true
+        }
+    });
+
+    /*  CALLBACK:
+        Highlights the characters of a page whose centre is inside a rectangle, in the current
+        highlight color. Nothing is added if no characters are selected
+
+        # Arguments
+        * 'page_index' - 0-based page the characters are on
+        * 'boxes' - the page's character boxes, as get-text-boxes returned them
+        * 'x0', 'y0', 'x1', 'y1' - corners of the selection in pixels of the displayed image
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_add_highlight({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        let session = session.clone();
+        move |page_index, boxes, x0, y0, x1, y1| {
+            if cloned_file_manager.lock().unwrap().get_cur_file_info().get_read_only() {
+                return;
+            }
+            let Some((first, last)) = text::selected_range(&boxes, x0, y0, x1, y1) else {
+                return;
+            };
+            let color = cloned_settings.lock().unwrap().get_highlight_color().to_string();
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().add_highlight(Highlight {
+                page: page_index.max(0) as u32,
+                first,
+                last,
+                color,
+            });
+            let path = file_manager.get_cur_file_info().get_filepath();
+            let name = file_manager.get_cur_file_info().get_name();
+            session.borrow_mut().highlight_added(path.as_str(), name.as_str());
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Removes the page's highlights that overlap the characters inside a rectangle
+
+        # Arguments
+        * 'page_index' - 0-based page the characters are on
+        * 'boxes' - the page's character boxes, as get-text-boxes returned them
+        * 'x0', 'y0', 'x1', 'y1' - corners of the selection in pixels of the displayed image
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_remove_highlights({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |page_index, boxes, x0, y0, x1, y1| {
+            let Some((first, last)) = text::selected_range(&boxes, x0, y0, x1, y1) else {
+                return;
+            };
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            if file_manager.get_cur_file_info().get_read_only() {
+                return;
+            }
+            file_manager
+                .get_cur_file_info()
+                .remove_highlights(page_index.max(0) as u32, first, last);
+            schedule_save();
+        }
+    });
+}
+
+// Slint color entry for a "#RRGGBB" color
+fn highlight_color(name: &str, hex: &str) -> HighlightColor {
+    let (r, g, b) = parse_hex(hex).unwrap_or((0, 0, 0));
+    HighlightColor {
+        name: name.into(),
+        hex: hex.into(),
+        color: slint::Color::from_rgb_u8(r, g, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_keep_their_color_by_default() {
+        assert_eq!(overlay_color(Some("#ff0000"), false), slint::Color::from_argb_u8(110, 255, 0, 0));
+    }
+
+    #[test]
+    fn highlights_go_gray_with_the_page() {
+        // the same gray the page's own red turns
+        let gray = render::luminance(255, 0, 0);
+        assert_eq!(overlay_color(Some("#ff0000"), true), slint::Color::from_argb_u8(110, gray, gray, gray));
+    }
+
+    #[test]
+    fn unhighlighted_characters_stay_clear() {
+        assert_eq!(overlay_color(None, true).alpha(), 0);
+        assert_eq!(overlay_color(Some("not a color"), false).alpha(), 0);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::pages;
+use pdfium_render::prelude::*;
+
+// Longest side of the previews listed in the images panel
+pub const PREVIEW_SIDE: u32 = 160;
+
+// The image objects placed directly on a page, in drawing order, each as stored in the PDF rather
+// than at the size the page shows it. An image pdfium can't decode is skipped, images nested inside
+// form objects aren't listed
+pub fn page_images(path: &str, page_index: u32) -> Result<Vec<image::RgbaImage>, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let page = pages::get_page(&document, page_index)?;
+    let images = page
+        .objects()
+        .iter()
+        .filter_map(|object| {
+            let image = object.as_image_object()?;
+            match image.get_raw_image() {
+                Ok(image) => Some(image.into_rgba8()),
+                Err(e) => {
+                    eprintln!("Error decoding an image on page {}: {}", page_index + 1, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(images)
+}
+
+// A copy shrunk to fit PREVIEW_SIDE, smaller images are kept as they are
+pub fn preview(image: &image::RgbaImage) -> image::RgbaImage {
+    if image.width() <= PREVIEW_SIDE && image.height() <= PREVIEW_SIDE {
+        return image.clone();
+    }
+    let scale = PREVIEW_SIDE as f32 / image.width().max(image.height()) as f32;
+    let width = ((image.width() as f32 * scale).round() as u32).max(1);
+    let height = ((image.height() as f32 * scale).round() as u32).max(1);
+    image::imageops::thumbnail(image, width, height)
+}
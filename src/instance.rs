@@ -0,0 +1,297 @@
+use crate::platform;
+use crate::txt_file;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Held by the instance that started first, so a second one knows it shares database.json. It holds
+// "<pid> <endpoint>", the endpoint being the local socket or named pipe where the first instance
+// takes files to open
+pub const LOCK_FILE: &str = "database.lock";
+// How long a second instance waits on the first before opening the file itself, and how long the
+// first waits for a connected instance to send its path
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Process id and endpoint in the lock file, the endpoint is empty until the instance listens
+fn read_lock(filename: &str) -> Option<(u32, String)> {
+    let data = txt_file::read_file(filename).ok()?;
+    let (pid, endpoint) = data.trim().split_once(' ').unwrap_or((data.trim(), ""));
+    Some((pid.parse().ok()?, endpoint.to_string()))
+}
+
+// Takes the lock file, false if another running instance holds it. The file is created only if it
+// doesn't exist, so two instances starting together can't both take it. A lock whose process is gone
+// was left by a crash and is taken over, as is one that stayed unreadable for longer than an
+// instance takes to write it. This is advisory only: saves merge with the database on disk either
+// way, the lock just tells a second instance that it isn't alone
+pub fn acquire_lock(filename: &str) -> bool {
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(filename) {
+            Ok(mut file) => {
+                if let Err(e) = write!(file, "{}", std::process::id()) {
+                    eprintln!("Error writing {}: {}", filename, e);
+                }
+                return true;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let held = match read_lock(filename) {
+                    Some((pid, _)) => pid == std::process::id() || platform::process_running(pid),
+                    None => fs::metadata(filename)
+                        .and_then(|metadata| metadata.modified())
+                        .is_ok_and(|modified| {
+                            SystemTime::now().duration_since(modified).unwrap_or_default() < FORWARD_TIMEOUT
+                        }),
+                };
+                if held {
+                    return false;
+                }
+                let _ = fs::remove_file(filename);
+            }
+            Err(e) => {
+                eprintln!("Error creating {}: {}", filename, e);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+pub fn release_lock(filename: &str) {
+    #[cfg(unix)]
+    let _ = fs::remove_file(endpoint());
+    let _ = fs::remove_file(filename);
+}
+
+// Hands a file to the instance holding the lock, false if it isn't listening or doesn't answer in
+// time, in which case the caller opens the file itself
+pub fn forward(filename: &str, path: &str) -> bool {
+    let Some((_, endpoint)) = read_lock(filename) else {
+        return false;
+    };
+    if endpoint.is_empty() {
+        return false;
+    }
+    // the exchange runs on its own thread, a named pipe can't time out a read by itself
+    let (sender, receiver) = mpsc::channel();
+    let path = path.to_string();
+    thread::spawn(move || {
+        let forwarded = connect(&endpoint).is_ok_and(|mut connection| {
+            if writeln!(connection, "{}", path).is_err() {
+                return false;
+            }
+            // the running instance answers once it took the path
+            let mut answer = String::new();
+            matches!(BufReader::new(connection).read_line(&mut answer), Ok(n) if n > 0)
+        });
+        let _ = sender.send(forwarded);
+    });
+    receiver.recv_timeout(FORWARD_TIMEOUT).unwrap_or(false)
+}
+
+// Takes files forwarded by later instances on a local endpoint, recorded in the lock file, and passes
+// each to open on a background thread. Only the lock holder should listen. The endpoint is a Unix
+// socket only its user can connect to in the runtime directory, or a named pipe refusing remote
+// clients on Windows
+pub fn listen(filename: &str, open: impl Fn(String) + Send + 'static) {
+    let endpoint = endpoint();
+    let listener = match Listener::bind(&endpoint) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error listening for other instances: {}", e);
+            return;
+        }
+    };
+    let lock = format!("{} {}", std::process::id(), endpoint);
+    if let Err(e) = txt_file::write_to_file(filename, lock.as_str()) {
+        eprintln!("Error writing {}: {}", filename, e);
+        return;
+    }
+
+    thread::spawn(move || loop {
+        let mut connection = match listener.accept() {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Error taking a file from another instance: {}", e);
+                return;
+            }
+        };
+        let Some(path) = read_path(&mut connection) else {
+            continue;
+        };
+        let _ = writeln!(connection, "ok");
+        open(path);
+    });
+}
+
+// The first line a connected instance sent, None if it sent nothing in time
+fn read_path(connection: &mut impl Read) -> Option<String> {
+    let mut path = String::new();
+    BufReader::new(connection).read_line(&mut path).ok()?;
+    let path = path.trim().to_string();
+    (!path.is_empty()).then_some(path)
+}
+
+// The socket in the user's runtime directory, the temp directory where there is none
+#[cfg(unix)]
+fn endpoint() -> String {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(std::env::temp_dir, std::path::PathBuf::from);
+    dir.join(format!("pdfer-{}.sock", std::process::id())).to_string_lossy().into_owned()
+}
+
+#[cfg(unix)]
+fn connect(endpoint: &str) -> io::Result<std::os::unix::net::UnixStream> {
+    let stream = std::os::unix::net::UnixStream::connect(endpoint)?;
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    Ok(stream)
+}
+
+#[cfg(unix)]
+struct Listener(std::os::unix::net::UnixListener);
+
+#[cfg(unix)]
+impl Listener {
+    fn bind(endpoint: &str) -> io::Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+
+        // a socket left by a crashed instance with the same id is in the way
+        let _ = fs::remove_file(endpoint);
+        let listener = std::os::unix::net::UnixListener::bind(endpoint)?;
+        fs::set_permissions(endpoint, fs::Permissions::from_mode(0o600))?;
+        Ok(Self(listener))
+    }
+
+    // The next connection, which gets FORWARD_TIMEOUT to send its path so an idle one can't hold up
+    // the others
+    fn accept(&self) -> io::Result<std::os::unix::net::UnixStream> {
+        let (stream, _) = self.0.accept()?;
+        stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+#[cfg(windows)]
+fn endpoint() -> String {
+    format!(r"\\.\pipe\pdfer-{}", std::process::id())
+}
+
+#[cfg(windows)]
+fn connect(endpoint: &str) -> io::Result<fs::File> {
+    OpenOptions::new().read(true).write(true).open(endpoint)
+}
+
+#[cfg(windows)]
+mod pipe {
+    use std::ffi::c_void;
+
+    pub const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+    pub const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x8;
+    pub const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    pub const ERROR_PIPE_CONNECTED: i32 = 535;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security_attributes: *mut c_void,
+        ) -> *mut c_void;
+        pub fn ConnectNamedPipe(pipe: *mut c_void, overlapped: *mut c_void) -> i32;
+    }
+}
+
+// A named pipe has one instance per client, a new one is created for each connection
+#[cfg(windows)]
+struct Listener(Vec<u16>);
+
+#[cfg(windows)]
+impl Listener {
+    fn bind(endpoint: &str) -> io::Result<Self> {
+        use std::os::windows::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::new(endpoint).encode_wide().chain(std::iter::once(0)).collect();
+        Ok(Self(name))
+    }
+
+    // The next connection. A blocking pipe can't time out a read, so the path is read on a thread of
+    // its own and an idle connection only holds up itself
+    fn accept(&self) -> io::Result<PipeConnection> {
+        use std::os::windows::io::FromRawHandle;
+
+        let handle = unsafe {
+            pipe::CreateNamedPipeW(
+                self.0.as_ptr(),
+                pipe::PIPE_ACCESS_DUPLEX,
+                pipe::PIPE_REJECT_REMOTE_CLIENTS,
+                pipe::PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { fs::File::from_raw_handle(handle) };
+        if unsafe { pipe::ConnectNamedPipe(handle, std::ptr::null_mut()) } == 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(pipe::ERROR_PIPE_CONNECTED) {
+                return Err(error);
+            }
+        }
+        Ok(PipeConnection::spawn(file))
+    }
+}
+
+// A pipe client read on its own thread, see Listener::accept. Reading gives the line it sent, or
+// nothing once FORWARD_TIMEOUT passed
+#[cfg(windows)]
+struct PipeConnection {
+    file: fs::File,
+    line: mpsc::Receiver<Vec<u8>>,
+}
+
+#[cfg(windows)]
+impl PipeConnection {
+    fn spawn(file: fs::File) -> Self {
+        let (sender, line) = mpsc::channel();
+        if let Ok(reader) = file.try_clone() {
+            thread::spawn(move || {
+                let mut bytes = Vec::new();
+                let _ = BufReader::new(reader).read_until(b'\n', &mut bytes);
+                let _ = sender.send(bytes);
+            });
+        }
+        Self { file, line }
+    }
+}
+
+#[cfg(windows)]
+impl Read for PipeConnection {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.line.recv_timeout(FORWARD_TIMEOUT).unwrap_or_default();
+        let n = bytes.len().min(buffer.len());
+        buffer[..n].copy_from_slice(&bytes[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(windows)]
+impl Write for PipeConnection {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.file.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,23 +1,138 @@
 use native_dialog::FileDialog;
 use serde::{Deserialize, Serialize};
+use crate::database;
+use crate::highlights::Highlight;
+use crate::options::named_options;
+use crate::pages::ReadingDirection;
+use crate::pins::CommentPin;
+use crate::render;
+use crate::settings::Startup;
+use crate::text::{self, TextScript};
+use crate::timestamps::{self, TimestampFormat};
+use crate::txt_file;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+use std::rc::Rc;
 use std::result::Result;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
 
+pub const DATABASE_FILE: &str = "database.json";
+// How long changes to the recents must be quiet before the database is saved
+pub const SAVE_DELAY: Duration = Duration::from_millis(1500);
+// Group of the recents whose folder no longer exists, e.g. a removed drive
+pub const UNAVAILABLE_FOLDER: &str = "Unavailable";
 
-#[derive(Default)]
+// How long one read of a document may take before its volume is treated as stalled, and how often
+// it is tried. Network shares can fail once and then answer, so a failed read is retried
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const PROBE_ATTEMPTS: u32 = 3;
+
+#[derive(Default, Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct FileInfo {
     name: String,
     filepath: String,
-    cur_file_page: u16,
+    cur_file_page: u32,
     //attached_txt: String
+    #[serde(default)]
+    file_size: u64,
+    #[serde(default)]
+    page_count: u32,
+    #[serde(default)]
+    modified: u64,
+    #[serde(skip)]
+    accessible: bool,
+    // manual crop as percentages of the page (left, top, right, bottom), all 0 when not cropped
+    #[serde(default)]
+    crop_margins: [u8; 4],
+    #[serde(default)]
+    highlights: Vec<Highlight>,
+    // comments pinned to points of the pages
+    #[serde(default)]
+    pins: Vec<CommentPin>,
+    // seconds since the Unix epoch, 0 for files read before it was recorded
+    #[serde(default)]
+    last_read: u64,
+    // when the file first went into the recents, seconds since the Unix epoch, 0 for files added
+    // before it was recorded
+    #[serde(default)]
+    added_at: u64,
+    #[serde(default)]
+    finished: bool,
+    // percent the user zoomed this document to, None while it follows the default zoom or page fit
+    #[serde(default)]
+    zoom: Option<u32>,
+    // N of the 1:N scale the document's drawings are at, 0 until one is set
+    #[serde(default)]
+    measure_scale: u32,
+    // no edits of the document's highlights, and exports never overwrite an existing file
+    #[serde(default)]
+    read_only: bool,
+    // writing system the text is extracted and searched for
+    #[serde(default)]
+    text_script: TextScript,
+    // which way the pages run, right to left flips the page turning arrows and buttons
+    #[serde(default)]
+    reading_direction: ReadingDirection,
+    // colors this document is shown in whatever the global mode, None while it follows that
+    #[serde(default)]
+    page_colors: Option<render::PageColors>,
+    // whether the pages are scanned images without extractable text, None until it was checked
+    #[serde(default)]
+    scanned: Option<bool>,
+    // notes font size while font sizes are kept per document, None until it's changed for this one
+    #[serde(default)]
+    notes_font_size: Option<u32>,
+    // notes kept with each page while notes are per page, by 0-based page. Pages without a note aren't
+    // listed
+    #[serde(default)]
+    page_notes: BTreeMap<u32, String>,
+    // place in the manual order of the recents starting at 1, 0 until the recents were reordered with
+    // it among them. Unplaced files come first
+    #[serde(default)]
+    order: u32,
 }
 
 impl FileInfo {
-    fn new(filename: &str, name: &str, cur_file_page: u16) -> Self {
+    fn new(filename: &str, name: &str, cur_file_page: u32) -> Self {
         Self {
             name: name.to_string(),
             filepath: filename.to_string(),
-            cur_file_page
+            cur_file_page,
+            ..Default::default()
+        }
+    }
+
+    // Takes what another instance saved for the same file, field by field. What the user set follows
+    // the copy of whoever read the file last, what was read from the file follows the copy that saw
+    // the newer file. Ties keep this copy
+    fn merge(&mut self, other: FileInfo) {
+        if other.added_at != 0 && (self.added_at == 0 || other.added_at < self.added_at) {
+            self.added_at = other.added_at;
+        }
+        if other.modified > self.modified {
+            self.file_size = other.file_size;
+            self.page_count = other.page_count;
+            self.modified = other.modified;
+            self.scanned = other.scanned;
+        }
+        if other.last_read > self.last_read {
+            *self = FileInfo {
+                file_size: self.file_size,
+                page_count: self.page_count,
+                modified: self.modified,
+                accessible: self.accessible,
+                scanned: self.scanned,
+                added_at: self.added_at,
+                order: self.order,
+                ..other
+            };
         }
     }
 
@@ -29,14 +144,299 @@ impl FileInfo {
         self.filepath.clone()
     }
 
-    pub fn get_cur_page(&mut self) -> u16 {
+    pub fn get_modified(&self) -> u64 {
+        self.modified
+    }
+
+    // When the file was last read, as the recents show it
+    pub fn get_last_read(&self, format: TimestampFormat) -> String {
+        timestamps::format(self.last_read, timestamps::now(), format)
+    }
+
+    // When the file was added to the recents, as the recents show it, empty if that isn't known
+    pub fn get_added(&self, format: TimestampFormat) -> String {
+        match timestamps::format(self.added_at, timestamps::now(), format) {
+            added if added.is_empty() => added,
+            added => format!("Added {}", added),
+        }
+    }
+
+    pub fn get_text_script(&self) -> TextScript {
+        self.text_script
+    }
+
+    pub fn set_text_script(&mut self, script: TextScript) {
+        self.text_script = script;
+    }
+
+    pub fn get_reading_direction(&self) -> ReadingDirection {
+        self.reading_direction
+    }
+
+    pub fn set_reading_direction(&mut self, direction: ReadingDirection) {
+        self.reading_direction = direction;
+    }
+
+    pub fn get_page_colors(&self) -> Option<render::PageColors> {
+        self.page_colors
+    }
+
+    pub fn set_page_colors(&mut self, colors: Option<render::PageColors>) {
+        self.page_colors = colors;
+    }
+
+    // True only once the document was checked and found to be a scan
+    pub fn get_scanned(&self) -> bool {
+        self.scanned == Some(true)
+    }
+
+    // Whether the document is a scan, checking a few of its pages the first time it's asked and keeping
+    // the answer with it in the recents. A document that can't be opened counts as not a scan
+    pub fn classify_scanned(&mut self) -> bool {
+        if self.scanned.is_none() {
+            self.scanned = text::detect_scanned(&self.filepath);
+        }
+        self.get_scanned()
+    }
+
+    pub fn get_notes_font_size(&self) -> Option<u32> {
+        self.notes_font_size
+    }
+
+    pub fn set_notes_font_size(&mut self, size: u32) {
+        self.notes_font_size = Some(size);
+    }
+
+    pub fn get_page_note(&self, page: u32) -> String {
+        self.page_notes.get(&page).cloned().unwrap_or_default()
+    }
+
+    // Keeps the page's note, a note emptied out is dropped
+    pub fn set_page_note(&mut self, page: u32, text: &str) {
+        if text.trim().is_empty() {
+            self.page_notes.remove(&page);
+        } else {
+            self.page_notes.insert(page, text.to_string());
+        }
+    }
+
+    pub fn has_page_notes(&self) -> bool {
+        !self.page_notes.is_empty()
+    }
+
+    pub fn get_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn get_measure_scale(&self) -> u32 {
+        self.measure_scale
+    }
+
+    pub fn set_measure_scale(&mut self, scale: u32) {
+        self.measure_scale = scale;
+    }
+
+    pub fn get_zoom(&self) -> Option<u32> {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: Option<u32>) {
+        self.zoom = zoom;
+    }
+
+    pub fn get_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn set_finished(&mut self, finished: bool) {
+        self.finished = finished;
+    }
+
+    // Share of the document read from 0 to 1, counting the current page as read. A finished document
+    // is complete wherever it was left
+    pub fn get_progress(&self) -> f32 {
+        if self.finished {
+            return 1.0;
+        }
+        if self.page_count == 0 {
+            return 0.0;
+        }
+        ((self.cur_file_page + 1) as f32 / self.page_count as f32).min(1.0)
+    }
+
+    pub fn get_crop_margins(&self) -> [u8; 4] {
+        self.crop_margins
+    }
+
+    pub fn set_crop_margins(&mut self, margins: [u8; 4]) {
+        self.crop_margins = margins;
+    }
+
+    pub fn get_highlights(&self) -> &Vec<Highlight> {
+        &self.highlights
+    }
+
+    pub fn add_highlight(&mut self, highlight: Highlight) {
+        self.highlights.push(highlight);
+    }
+
+    // Drops every highlight on the page that overlaps the characters first to last
+    pub fn remove_highlights(&mut self, page: u32, first: u32, last: u32) {
+        self.highlights.retain(|highlight| {
+            highlight.page != page || highlight.last < first || highlight.first > last
+        });
+    }
+
+    pub fn get_pins(&self) -> &Vec<CommentPin> {
+        &self.pins
+    }
+
+    pub fn add_pin(&mut self, pin: CommentPin) {
+        self.pins.push(pin);
+    }
+
+    // Changes the text of a pin, or drops the pin when the text is empty
+    pub fn set_pin_text(&mut self, index: usize, text: &str) {
+        if index >= self.pins.len() {
+            return;
+        }
+        if text.is_empty() {
+            self.pins.remove(index);
+        } else {
+            self.pins[index].text = text.to_string();
+        }
+    }
+
+    pub fn get_cur_page(&mut self) -> u32 {
         return self.cur_file_page;
     }
 
-    pub fn set_cur_page(&mut self, num:u16) {
+    pub fn set_cur_page(&mut self, num:u32) {
         self.cur_file_page = num;
         println!("new page: {}", self.cur_file_page);
     }
+
+    // Re-reads the file size and page count, and whether it's a scan, but only reloads the PDF when its
+    // modified time changed or it was never checked for being a scan
+    pub fn refresh_details(&mut self) {
+        self.refresh_details_with(render::document_page_count, text::detect_scanned);
+    }
+
+    // refresh_details with the loads of the page count and of whether it's a scan passed in
+    fn refresh_details_with(
+        &mut self,
+        page_count: impl FnOnce(&str) -> Option<u32>,
+        scanned: impl FnOnce(&str) -> Option<bool>,
+    ) {
+        let metadata = match fs::metadata(&self.filepath) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                self.accessible = false;
+                return;
+            }
+        };
+        self.accessible = true;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+        if modified == self.modified && self.page_count > 0 && self.scanned.is_some() {
+            return;
+        }
+        self.modified = modified;
+        self.file_size = metadata.len();
+        match page_count(&self.filepath) {
+            Some(count) => self.set_page_count(count),
+            None => self.accessible = false,
+        }
+        self.scanned = scanned(&self.filepath);
+    }
+
+    // Page count as the loaded document reports it, which can differ from what the file's header
+    // says once pdfium has repaired a broken file. Navigation and the page indicator use this count
+    pub fn get_page_count(&self) -> u32 {
+        self.page_count
+    }
+
+    // Stores the count from a fresh load of the document, moving the saved page back onto the last
+    // page if the document got shorter
+    pub fn set_page_count(&mut self, count: u32) {
+        self.page_count = count;
+        if count > 0 && self.cur_file_page >= count {
+            println!(
+                "{} now has {} pages, moving the saved page {} to the last page",
+                self.filepath,
+                count,
+                self.cur_file_page + 1
+            );
+            self.cur_file_page = count - 1;
+        }
+    }
+
+    // Size and page count for the recents list, or a dash if the file can no longer be read
+    pub fn get_details(&self) -> String {
+        if !self.accessible {
+            return "—".to_string();
+        }
+        format!("{} · {} pages", format_size(self.file_size), self.page_count)
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        return format!("{} {}", bytes, units[0]);
+    }
+    format!("{:.1} {}", size, units[unit])
+}
+
+// Orders offered for the recents, shared by the grid and list layouts
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum RecentsSort {
+        #[default]
+        LastRead => "Last read",
+        // newest to the recents first, files added before that was recorded last
+        LastAdded => "Last added",
+        Name => "Name",
+        Size => "Size",
+        // the order the recents were dragged into
+        Manual => "Manual",
+    }
+}
+
+// Which recents are listed, by whether they were read to the end
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum RecentsFilter {
+        #[default]
+        All => "All",
+        Unfinished => "Unfinished",
+        Finished => "Finished",
+    }
+}
+
+impl RecentsFilter {
+    pub fn keeps(&self, file: &FileInfo) -> bool {
+        match self {
+            RecentsFilter::All => true,
+            RecentsFilter::Unfinished => !file.finished,
+            RecentsFilter::Finished => file.finished,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -44,6 +444,28 @@ pub struct FileManager {
     files: Vec<FileInfo>,
     cur_file_info: FileInfo,
     cur_file_path: String,
+    // the last change to the recents, kept until it is undone or the list changes otherwise. Only for
+    // this session, it isn't saved with the database
+    #[serde(skip)]
+    undo: Option<RecentsUndo>,
+    // paths cleared from the recents this session, or stored paths since resolved to another, left out
+    // when merging the database on disk so another instance's copy doesn't bring them back
+    #[serde(skip)]
+    cleared: Vec<String>,
+}
+
+// A document read_document read, for open_read to make the current one
+pub struct ReadDocument {
+    given_path: String,
+    details: FileInfo,
+}
+
+// What a recents change did, and what recents changes touch as it was before it: the recents
+// themselves, with their finished marks and manual order, and whether the open document was finished
+struct RecentsUndo {
+    description: String,
+    files: Vec<FileInfo>,
+    cur_finished: bool,
 }
 
 impl FileManager {
@@ -52,42 +474,177 @@ impl FileManager {
             files: Vec::new(),
             cur_file_info: FileInfo::new("../assets/blank.pdf", "blank.pdf", 0),
             cur_file_path: "../assets/blank.pdf".to_string(),
+            undo: None,
+            cleared: Vec::new(),
         }
     }
 
-    pub fn add_new_file(&mut self) -> bool {
-        //open file from system
-        if let Some(file_path) = FileDialog::new()
-            .set_location("~/Desktop")
-            .add_filter("PDF File", &["pdf"])
-            .show_open_single_file()
-            .unwrap()
-        {
-            let name = file_path.file_name().unwrap().to_str().unwrap();
-            self.cur_file_info = FileInfo::new(file_path.to_str().unwrap(), name, 0);
-            println!("Selected file: {:?}", file_path);
-            self.set_cur_path(file_path.to_str().unwrap().to_string());
-            return true;
+    // Makes the file at path the current document, reading it on the calling thread. Returns false if
+    // it was already open, or why it couldn't be opened. The window opens documents with
+    // read_document on a thread of their own and open_read instead, this is for startup
+    pub fn open_path(&mut self, path: String) -> Result<bool, String> {
+        let read = read_document(path.clone(), |resolved| self.entry_to_read(resolved, &path))?;
+        Ok(read.map(|read| self.open_read(read)).is_some())
+    }
+
+    // The entry read_document re-reads the details of the file at path into: a copy of its stored one if
+    // it's in the recents, found by the path given if that wasn't resolved yet, or a new one. None if
+    // the file is already the open document
+    pub fn entry_to_read(&self, path: &str, given_path: &str) -> Option<FileInfo> {
+        if self.is_open(path) {
+            return None;
+        }
+        match self.find_file(path).or_else(|| self.find_file(given_path)) {
+            Some(index) => Some(self.files[index].clone()),
+            None => {
+                let name = Path::new(path).file_name().map_or(path.into(), |name| name.to_string_lossy().into_owned());
+                Some(FileInfo::new(path, &name, 0))
+            }
+        }
+    }
+
+    // Makes a document read_document read the current one. If it was opened meanwhile nothing changes,
+    // and if it is in the recents the stored entry (and its saved page) is reused, with the details
+    // read, instead of creating a duplicate. The file may have been edited since it was last read, the
+    // new page count brings the saved page back into it
+    pub fn open_read(&mut self, read: ReadDocument) {
+        let details = read.details;
+        let path = details.filepath.clone();
+        if self.is_open(&path) {
+            return;
+        }
+        // opening reorders the recents, undoing an earlier change now would undo that too
+        self.undo = None;
+        self.add_file();
+        // a recent whose stored path wasn't resolved yet is found by that path
+        self.cur_file_info = match self.find_file(&path).or_else(|| self.find_file(&read.given_path)) {
+            Some(index) => {
+                let mut file_info = self.files.remove(index);
+                if file_info.filepath != path {
+                    self.cleared.push(std::mem::replace(&mut file_info.filepath, path.clone()));
+                }
+                file_info.file_size = details.file_size;
+                file_info.modified = details.modified;
+                file_info.accessible = details.accessible;
+                file_info.scanned = details.scanned;
+                file_info.set_page_count(details.page_count);
+                file_info
+            }
+            None => {
+                let mut file_info = details;
+                if file_info.added_at == 0 {
+                    file_info.added_at = timestamps::now();
+                }
+                file_info
+            }
+        };
+        self.cur_file_info.last_read = timestamps::now();
+        self.set_cur_path(path);
+    }
+
+    // false while only the blank placeholder is loaded
+    pub fn has_document(&self) -> bool {
+        self.cur_file_path != "../assets/blank.pdf"
+    }
+
+    pub fn is_open(&self, path: &str) -> bool {
+        self.cur_file_path == path
+    }
+
+    // Marks the open document or a recent file finished, or unmarks it. Returns the new state, None if
+    // the path is neither
+    pub fn toggle_finished(&mut self, path: &str) -> Option<bool> {
+        let index = if self.is_open(path) { None } else { Some(self.find_file(path)?) };
+        let file = index.map_or(&self.cur_file_info, |index| &self.files[index]);
+        let description = if file.finished {
+            format!("Unmarked {}", file.get_name())
         } else {
-            return false;
+            format!("Marked {} finished", file.get_name())
+        };
+        self.remember(description);
+        let file = match index {
+            Some(index) => &mut self.files[index],
+            None => &mut self.cur_file_info,
+        };
+        file.finished = !file.finished;
+        Some(file.finished)
+    }
+
+    // Keeps the recents as they are before a change, replacing whatever change was undoable before
+    fn remember(&mut self, description: String) {
+        self.undo = Some(RecentsUndo {
+            description,
+            files: self.files.clone(),
+            cur_finished: self.cur_file_info.finished,
+        });
+    }
+
+    // What the last change to the recents did, None if there is nothing to undo
+    pub fn last_recents_change(&self) -> Option<String> {
+        self.undo.as_ref().map(|undo| undo.description.clone())
+    }
+
+    // Puts the recents back the way they were before the last change. Only what recents changes touch
+    // goes back, the list, the finished marks and the manual order, so highlights, notes and the like
+    // added since are kept. Returns what was undone, None if there was nothing to undo
+    pub fn undo_recents_change(&mut self) -> Option<String> {
+        let undo = self.undo.take()?;
+        let mut live = std::mem::take(&mut self.files);
+        self.files = undo
+            .files
+            .into_iter()
+            .map(|before| match live.iter().position(|a_file| a_file.filepath == before.filepath) {
+                Some(index) => {
+                    let mut a_file = live.remove(index);
+                    a_file.finished = before.finished;
+                    a_file.order = before.order;
+                    a_file
+                }
+                None => before,
+            })
+            .collect();
+        self.cur_file_info.finished = undo.cur_finished;
+        let files = &self.files;
+        self.cleared.retain(|path| !files.iter().any(|a_file| &a_file.filepath == path));
+        Some(undo.description)
+    }
+
+    // Empties the recents, the open document stays open and is listed again once another is opened.
+    // Can be undone like the other recents changes. Returns how many files were cleared
+    pub fn clear_recents(&mut self) -> usize {
+        let count = self.files.len();
+        if count == 0 {
+            return 0;
+        }
+        self.remember(format!("Cleared {} recent files", count));
+        self.cleared.extend(self.files.drain(..).map(|a_file| a_file.filepath));
+        count
+    }
+
+    pub fn find_file(&self, path: &str) -> Option<usize> {
+        self.files.iter().position(|n| n.get_filepath() == path)
+    }
+
+    // The open document's entry or the recent one with the path, None if it's neither
+    pub fn file_mut(&mut self, path: &str) -> Option<&mut FileInfo> {
+        if self.is_open(path) {
+            return Some(&mut self.cur_file_info);
         }
+        let index = self.find_file(path)?;
+        Some(&mut self.files[index])
     }
 
     pub fn add_file(& mut self) -> bool {
         //open file from system
         if self.cur_file_path != "../assets/blank.pdf".to_string() {
-            self.files.insert(0, FileInfo::new(&self.cur_file_info.get_filepath(), &self.cur_file_info.get_name(), self.cur_file_info.get_cur_page()));
+            self.cur_file_info.last_read = timestamps::now();
+            self.files.insert(0, self.cur_file_info.clone());
             return true;
         } else {
             return false;
         }
     }
 
-    pub fn set_cur_file_info(&mut self, str: String) {
-        let index = self.files.iter().position(|n| n.get_filepath() == str).unwrap();
-        self.cur_file_info = self.files.remove(index);
-    }
-
     pub fn get_cur_file_info(&mut self) -> &mut FileInfo {
         return &mut self.cur_file_info;
     }
@@ -103,11 +660,839 @@ impl FileManager {
         self.cur_file_path = str;
     }
 
+    // The file read most recently before this session, which is always the first in the recents
+    pub fn last_read(&self) -> Option<&FileInfo> {
+        self.files.first()
+    }
+
+    // Opens what PDFer starts on: the PDF given on the command line whatever the startup choice,
+    // otherwise the last read document if the user asked for that. True once a document is open,
+    // false leaves the opening page up
+    pub fn open_on_startup(&mut self, startup: Startup, arg: Option<String>) -> Result<bool, String> {
+        let path = match arg {
+            Some(path) => Some(path),
+            None if startup == Startup::LastDocument => self.last_read().map(|file| file.get_filepath()),
+            None => None,
+        };
+        match path {
+            Some(path) => self.open_path(path).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    // The recents in the given order. They are stored most recently read first, so that order is free
+    pub fn sorted_files(&self, sort: RecentsSort) -> Vec<&FileInfo> {
+        let mut files: Vec<&FileInfo> = self.files.iter().collect();
+        match sort {
+            RecentsSort::LastRead => (),
+            RecentsSort::LastAdded => files.sort_by_key(|a_file| Reverse(a_file.added_at)),
+            RecentsSort::Name => files.sort_by_key(|a_file| a_file.name.to_lowercase()),
+            RecentsSort::Size => files.sort_by_key(|a_file| Reverse(a_file.file_size)),
+            RecentsSort::Manual => files.sort_by_key(|a_file| a_file.order),
+        }
+        files
+    }
+
+    // Moves a recent file to the place of another in the manual order, numbering all recents by their
+    // new places. Returns false if either isn't in the recents or they are the same
+    pub fn reorder(&mut self, from: &str, to: &str) -> bool {
+        let mut order: Vec<String> = self
+            .sorted_files(RecentsSort::Manual)
+            .iter()
+            .map(|a_file| a_file.get_filepath())
+            .collect();
+        let (Some(from_index), Some(to_index)) = (
+            order.iter().position(|path| path == from),
+            order.iter().position(|path| path == to),
+        ) else {
+            return false;
+        };
+        if from_index == to_index {
+            return false;
+        }
+        self.remember(format!("Moved {}", self.files[self.find_file(from).unwrap()].get_name()));
+        let moved = order.remove(from_index);
+        order.insert(to_index, moved);
+        for (place, path) in order.iter().enumerate() {
+            if let Some(index) = self.find_file(path) {
+                self.files[index].order = place as u32 + 1;
+            }
+        }
+        true
+    }
+
     pub fn get_files(&self) -> &Vec<FileInfo> {
         return &self.files;
     }
 
+    // Writes the recents, with the open document first, without changing the manager itself. Files
+    // only in the database on disk were added by another instance since this one loaded it, they are
+    // kept after ours rather than overwritten, unless this instance cleared them. Where both have a
+    // file the two entries are merged, see FileInfo::merge
+    pub fn save(&self, filename: &str) {
+        match self.try_save(filename) {
+            Ok(_) => println!("File Saved"),
+            Err(e) => eprintln!("Error saving file: {}", e),
+        }
+    }
+
+    // Writes the recents like save, but hands the error back, for the save on close that mustn't lose
+    // the session quietly. A database that didn't parse, or one from a newer PDFer whose fields would be
+    // dropped, is first copied to a .bak beside it, and isn't overwritten if that copy fails
+    pub fn try_save(&self, filename: &str) -> std::io::Result<()> {
+        let mut files = Vec::new();
+        if self.has_document() {
+            files.push(self.cur_file_info.clone());
+        }
+        files.extend(self.files.iter().cloned());
+        if let Ok(data) = txt_file::read_file(filename) {
+            let on_disk = match database::parse(data.as_str()) {
+                Ok(on_disk) if !database::is_newer(data.as_str()) => on_disk,
+                parsed => {
+                    let backup = format!("{}.bak", filename);
+                    fs::write(&backup, data.as_bytes())?;
+                    eprintln!("{} wasn't read in full, it was kept as {}", filename, backup);
+                    parsed.unwrap_or_default()
+                }
+            };
+            for a_file in on_disk {
+                match files.iter_mut().find(|known| known.filepath == a_file.filepath) {
+                    Some(known) => known.merge(a_file),
+                    None if !self.cleared.contains(&a_file.filepath) => files.push(a_file),
+                    None => {}
+                }
+            }
+        }
+
+        let json = database::serialize(&files);
+        txt_file::write_to_file(filename, json.as_str())
+    }
+
+    // Stores the recents as read from the database. They show their stored details until
+    // refreshed_details re-read them on a background thread and apply_details takes the result
     pub fn set_files(&mut self, data: Vec<FileInfo>) {
         self.files = data;
+        for a_file in self.files.iter_mut() {
+            a_file.accessible = true;
+        }
+    }
+
+    // Takes the details refreshed_details re-read, by the path each file was stored with. Files opened
+    // or removed since are left alone. A stored path that resolves to another is dropped from the
+    // database on save, and of entries that now share a path only the one read last is kept
+    pub fn apply_details(&mut self, refreshed: Vec<(String, FileInfo)>) {
+        for (stored_path, details) in refreshed {
+            let Some(index) = self.find_file(&stored_path) else {
+                continue;
+            };
+            if details.filepath != stored_path {
+                self.cleared.push(stored_path);
+            }
+            let a_file = &mut self.files[index];
+            a_file.filepath = details.filepath;
+            a_file.file_size = details.file_size;
+            a_file.modified = details.modified;
+            a_file.accessible = details.accessible;
+            a_file.scanned = details.scanned;
+            a_file.set_page_count(details.page_count);
+        }
+        // the recents are kept most recently read first, and the open document isn't among them
+        let mut seen = vec![self.cur_file_path.clone()];
+        self.files.retain(|a_file| {
+            if seen.contains(&a_file.filepath) {
+                return false;
+            }
+            seen.push(a_file.filepath.clone());
+            true
+        });
+    }
+}
+
+// The recents with their paths resolved and details re-read, each with the path it was stored with.
+// This reads every file, which can take a while on network shares, so it runs off the UI thread
+pub fn refreshed_details(files: Vec<FileInfo>) -> Vec<(String, FileInfo)> {
+    files
+        .into_iter()
+        .map(|mut a_file| {
+            let stored_path = a_file.filepath.clone();
+            a_file.filepath = canonical_path(&stored_path);
+            a_file.refresh_details();
+            (stored_path, a_file)
+        })
+        .collect()
+}
+
+// The given recents grouped by the folder each is in, the groups in the order their first file comes
+// and the files in theirs. Files whose folder is gone are grouped under UNAVAILABLE_FOLDER, last
+pub fn folder_groups<'a>(files: &[&'a FileInfo]) -> Vec<(String, Vec<&'a FileInfo>)> {
+    let mut groups: Vec<(String, Vec<&FileInfo>)> = Vec::new();
+    let mut unavailable = Vec::new();
+    for &a_file in files {
+        let folder = Path::new(&a_file.filepath).parent().filter(|folder| folder.is_dir());
+        let Some(folder) = folder else {
+            unavailable.push(a_file);
+            continue;
+        };
+        let folder = folder.display().to_string();
+        match groups.iter_mut().find(|(group, _)| *group == folder) {
+            Some((_, group_files)) => group_files.push(a_file),
+            None => groups.push((folder, vec![a_file])),
+        }
+    }
+    if !unavailable.is_empty() {
+        groups.push((UNAVAILABLE_FOLDER.to_string(), unavailable));
+    }
+    groups
+}
+
+// Returns a function that saves the database to filename once changes have been quiet for SAVE_DELAY.
+// Every call restarts the timer, so a burst of changes (e.g. paging quickly) is written once
+pub fn save_scheduler(file_manager: &Arc<Mutex<FileManager>>, filename: &str) -> Rc<dyn Fn()> {
+    let save_timer = slint::Timer::default();
+    let file_manager = file_manager.clone();
+    let filename = filename.to_string();
+    Rc::new(move || {
+        let file_manager = file_manager.clone();
+        let filename = filename.clone();
+        save_timer.start(slint::TimerMode::SingleShot, SAVE_DELAY, move || {
+            file_manager.lock().unwrap().save(filename.as_str());
+        });
+    })
+}
+
+// Resolves symlinks and relative parts of a path, keeping the path as given if that fails. Windows
+// gives the resolved path in its \\?\ form, which is written back the usual way
+pub fn canonical_path(path: &str) -> String {
+    match fs::canonicalize(path) {
+        Ok(canonical) => strip_verbatim(canonical.to_str().unwrap_or(path)),
+        Err(_) => strip_verbatim(path),
+    }
+}
+
+// A \\?\C:\ path as C:\ and a \\?\UNC\ one as \\server\share. Other verbatim paths, e.g. to devices,
+// have no usual form and stay as they are
+fn strip_verbatim(path: &str) -> String {
+    if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{}", share);
+    }
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) if rest.len() >= 3 && rest.as_bytes()[0].is_ascii_alphabetic() && rest[1..].starts_with(":\\") => {
+            rest.to_string()
+        }
+        _ => path.to_string(),
+    }
+}
+
+// Resolves symlinks in the given path, checks the file can be read and re-reads its details into the
+// entry entry_to_read gives for the resolved path. Ok(None) if the file is already the open document.
+// All of it can stall on a network share, so the window calls this off the UI thread and hands the
+// result to FileManager::open_read
+pub fn read_document(
+    given_path: String,
+    entry: impl FnOnce(&str) -> Option<FileInfo>,
+) -> Result<Option<ReadDocument>, String> {
+    let path = canonical_path(&given_path);
+    let Some(mut details) = entry(&path) else {
+        return Ok(None);
+    };
+    check_readable(&path)?;
+    details.filepath = path;
+    details.refresh_details_with(load_page_count, text::detect_scanned);
+    Ok(Some(ReadDocument { given_path, details }))
+}
+
+// Page count of the document at path, loading it again if a load fails the way a flaky network share
+// can. None once every attempt failed
+fn load_page_count(path: &str) -> Option<u32> {
+    (1..=PROBE_ATTEMPTS).find_map(|attempt| {
+        let count = render::document_page_count(path);
+        if count.is_none() {
+            eprintln!("Loading {} failed (attempt {})", path, attempt);
+        }
+        count
+    })
+}
+
+// Reads the start of the file, retrying a few times before reporting the volume as slow or
+// unavailable. Each read runs on a thread of its own, so one stuck on a stalled SMB/NFS mount is given
+// up on after PROBE_TIMEOUT rather than holding up the caller for good
+pub fn check_readable(path: &str) -> Result<(), String> {
+    probe_readable(path, PROBE_TIMEOUT)
+}
+
+fn probe_readable(path: &str, timeout: Duration) -> Result<(), String> {
+    for attempt in 1..=PROBE_ATTEMPTS {
+        let (sender, receiver) = mpsc::channel();
+        let probe_path = path.to_string();
+        thread::spawn(move || {
+            let mut header = [0; 5];
+            let result = fs::File::open(&probe_path).and_then(|mut file| file.read(&mut header));
+            let _ = sender.send(result);
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(_)) => return Ok(()),
+            Ok(Err(e)) if e.kind() == ErrorKind::NotFound => {
+                return Err(format!("{} no longer exists", path));
+            }
+            Ok(Err(e)) => eprintln!("Reading {} failed (attempt {}): {}", path, attempt, e),
+            Err(_) => eprintln!("Reading {} timed out (attempt {})", path, attempt),
+        }
+    }
+    Err(format!(
+        "Couldn't read {}. It may be on a slow or unavailable network drive",
+        path
+    ))
+}
+
+// Asks where to save a PDF, suggesting the given path. Returns None if the user cancels
+pub fn save_pdf_dialog(suggested: &Path) -> Option<String> {
+    let mut dialog = FileDialog::new().add_filter("PDF File", &["pdf"]);
+    if let Some(dir) = suggested.parent() {
+        dialog = dialog.set_location(dir);
+    }
+    if let Some(name) = suggested.file_name().and_then(|name| name.to_str()) {
+        dialog = dialog.set_filename(name);
+    }
+    match dialog.show_save_single_file() {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// Asks for a PDF to open, None if the user cancels
+pub fn open_pdf_dialog() -> Option<String> {
+    match FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("PDF File", &["pdf"])
+        .show_open_single_file()
+    {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// Whether two paths lead to the same file, seen through symlinks and relative parts
+pub fn same_file(a: &str, b: &str) -> bool {
+    canonical_path(a) == canonical_path(b)
+}
+
+// Why an export can't be written to output, None if it can. The document it comes from is never
+// overwritten, and for a read-only document no existing file is either
+pub fn refuse_export(output: &str, source: &FileInfo) -> Option<String> {
+    if same_file(output, &source.filepath) {
+        return Some("Choose a new file name, the original PDF is not overwritten".to_string());
+    }
+    if source.read_only && Path::new(output).exists() {
+        return Some("This document is read-only, save to a new file instead of replacing one".to_string());
+    }
+    None
+}
+
+// Asks where to save a session report, suggesting a Markdown file. None if the user cancelled
+pub fn save_report_dialog(suggested_name: &str) -> Option<String> {
+    match FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("Markdown", &["md"])
+        .add_filter("Text file", &["txt"])
+        .set_filename(suggested_name)
+        .show_save_single_file()
+    {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// Asks where to save an image taken from a page, suggesting a PNG file. None if the user cancelled
+pub fn save_image_dialog(suggested_name: &str) -> Option<String> {
+    match FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("PNG image", &["png"])
+        .set_filename(suggested_name)
+        .show_save_single_file()
+    {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// Asks where to write the recents when database.json can't be, suggesting a JSON file. None if the
+// user cancelled
+pub fn save_database_dialog() -> Option<String> {
+    match FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("JSON", &["json"])
+        .set_filename(DATABASE_FILE)
+        .show_save_single_file()
+    {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// Shortens a file name for the recents list. When extensions are hidden the stem is trimmed and ends
+// in "..." so a truncated name never shows "...pdf". A trimmed name is max_len characters long, or just
+// the ending when max_len is too short to keep any of the name
+pub fn trim_file_name(name: &str, max_len: usize, hide_extension: bool) -> String {
+    let (shown, suffix) = if hide_extension {
+        (Path::new(name).file_stem().and_then(|s| s.to_str()).unwrap_or(name), "...")
+    } else {
+        (name, "...pdf")
+    };
+    if shown.chars().count() > max_len {
+        let keep = max_len.saturating_sub(suffix.chars().count());
+        let trimmed: String = shown.chars().take(keep).collect();
+        return trimmed + suffix;
+    }
+    shown.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::txt_file::temp_path;
+    use std::cell::Cell;
+
+    fn recent(path: &str, page: u32, last_read: u64) -> FileInfo {
+        let mut file = FileInfo::new(path, path, page);
+        file.last_read = last_read;
+        file
+    }
+
+    fn manager(files: Vec<FileInfo>) -> FileManager {
+        let mut manager = FileManager::new();
+        manager.files = files;
+        manager
+    }
+
+    // A recent for a file that exists, its details already checked so opening it doesn't load the
+    // file as a PDF
+    fn checked_recent(name: &str, page: u32) -> FileInfo {
+        let path = temp_path(&format!("{}.pdf", name));
+        fs::write(&path, "%PDF-").unwrap();
+        let mut file = recent(&canonical_path(&path), page, 100);
+        file.modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map_or(0, |time| time.duration_since(UNIX_EPOCH).unwrap().as_secs());
+        file.page_count = 10;
+        file.scanned = Some(false);
+        file
+    }
+
+    #[test]
+    fn two_managers_saving_merge_instead_of_overwriting() {
+        let filename = temp_path("merge.json");
+        let _ = fs::remove_file(&filename);
+        let first = manager(vec![recent("a.pdf", 3, 100), recent("b.pdf", 1, 100)]);
+        let second = manager(vec![recent("a.pdf", 7, 200), recent("c.pdf", 2, 150)]);
+
+        second.try_save(&filename).unwrap();
+        first.try_save(&filename).unwrap();
+        let saved = database::parse(&txt_file::read_file(&filename).unwrap()).unwrap();
+        let _ = fs::remove_file(&filename);
+
+        let paths: Vec<&str> = saved.iter().map(|file| file.filepath.as_str()).collect();
+        assert_eq!(paths, ["a.pdf", "b.pdf", "c.pdf"]);
+        // the second instance read a.pdf last, its page wins
+        assert_eq!(saved[0].cur_file_page, 7);
+        assert_eq!(saved[0].last_read, 200);
+    }
+
+    #[test]
+    fn merge_keeps_the_newer_value_of_each_field() {
+        let mut ours = recent("a.pdf", 3, 200);
+        ours.modified = 10;
+        ours.page_count = 20;
+        ours.added_at = 50;
+        let mut theirs = recent("a.pdf", 9, 100);
+        theirs.modified = 30;
+        theirs.page_count = 25;
+        theirs.added_at = 40;
+        theirs.finished = true;
+
+        ours.merge(theirs);
+        // read last here, so the reading state stays ours
+        assert_eq!(ours.cur_file_page, 3);
+        assert!(!ours.finished);
+        // the other instance saw the newer file
+        assert_eq!(ours.page_count, 25);
+        assert_eq!(ours.modified, 30);
+        assert_eq!(ours.added_at, 40);
+    }
+
+    #[test]
+    fn merge_ties_keep_this_copy() {
+        let mut ours = recent("a.pdf", 3, 100);
+        ours.merge(recent("a.pdf", 5, 100));
+        assert_eq!(ours.cur_file_page, 3);
+    }
+
+    #[test]
+    fn save_leaves_out_cleared_files() {
+        let filename = temp_path("cleared.json");
+        let _ = fs::remove_file(&filename);
+        manager(vec![recent("a.pdf", 0, 100)]).try_save(&filename).unwrap();
+        let mut cleared = manager(vec![recent("a.pdf", 0, 100)]);
+        cleared.clear_recents();
+
+        cleared.try_save(&filename).unwrap();
+        let saved = database::parse(&txt_file::read_file(&filename).unwrap()).unwrap();
+        let _ = fs::remove_file(&filename);
+        assert!(saved.is_empty());
+    }
+
+    #[test]
+    fn a_database_not_read_in_full_is_kept_before_saving() {
+        let filename = temp_path("newer.json");
+        let newer = r#"{"version": 99, "files": [{"name": "b.pdf", "filepath": "b.pdf", "cur_file_page": 1, "tags": ["x"]}]}"#;
+        fs::write(&filename, newer).unwrap();
+
+        manager(vec![recent("a.pdf", 0, 100)]).try_save(&filename).unwrap();
+        let saved = database::parse(&txt_file::read_file(&filename).unwrap()).unwrap();
+        let backup = txt_file::read_file(&format!("{}.bak", filename));
+        let _ = fs::remove_file(&filename);
+        let _ = fs::remove_file(format!("{}.bak", filename));
+        // what was understood is merged in as usual
+        assert_eq!(saved.len(), 2);
+        assert_eq!(backup.unwrap(), newer);
+    }
+
+    #[test]
+    fn opening_the_open_document_keeps_a_single_copy() {
+        let file = checked_recent("open", 4);
+        let path = file.get_filepath();
+        let mut manager = manager(Vec::new());
+        manager.cur_file_info = file;
+        manager.set_cur_path(path.clone());
+
+        manager.open_path(path.clone()).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(manager.is_open(&path));
+        assert!(manager.get_files().is_empty());
+        assert_eq!(manager.get_cur_file_info().get_cur_page(), 4);
+    }
+
+    #[test]
+    fn the_open_document_isnt_read_again() {
+        // a file that doesn't exist, reading it would be refused
+        let path = temp_path("gone.pdf");
+        let mut manager = manager(Vec::new());
+        manager.cur_file_info = recent(&path, 2, 100);
+        manager.set_cur_path(path.clone());
+
+        let read = read_document(path.clone(), |resolved| manager.entry_to_read(resolved, &path));
+        assert!(matches!(read, Ok(None)));
+    }
+
+    #[test]
+    fn opening_a_recent_reuses_its_entry() {
+        let file = checked_recent("recent", 6);
+        let path = file.get_filepath();
+        let mut manager = manager(vec![file]);
+
+        manager.open_path(path.clone()).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(manager.is_open(&path));
+        assert_eq!(manager.find_file(&path), None);
+        assert_eq!(manager.get_cur_file_info().get_cur_page(), 6);
+    }
+
+    #[test]
+    fn recently_added_and_recently_read_order_differently() {
+        // stored most recently read first: the old document was read today, the new one yesterday
+        let mut old = recent("old.pdf", 0, 200);
+        old.added_at = 10;
+        let mut new = recent("new.pdf", 0, 100);
+        new.added_at = 90;
+        let manager = manager(vec![old, new]);
+
+        let names = |sort| manager.sorted_files(sort).iter().map(|file| file.get_filepath()).collect::<Vec<_>>();
+        assert_eq!(names(RecentsSort::LastRead), ["old.pdf", "new.pdf"]);
+        assert_eq!(names(RecentsSort::LastAdded), ["new.pdf", "old.pdf"]);
+    }
+
+    #[test]
+    fn reading_a_recent_keeps_when_it_was_added() {
+        let mut file = checked_recent("added", 0);
+        file.added_at = 7;
+        let path = file.get_filepath();
+        let mut manager = manager(vec![file]);
+
+        manager.open_path(path.clone()).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(manager.get_cur_file_info().added_at, 7);
+        assert!(manager.get_cur_file_info().last_read > 7);
+    }
+
+    #[test]
+    fn a_pdf_on_the_command_line_opens_whatever_the_startup_choice() {
+        for (startup, name) in [
+            (Startup::OpeningPage, "argument-opening"),
+            (Startup::LastDocument, "argument-last"),
+            (Startup::BlankNote, "argument-blank"),
+        ] {
+            // the given PDF is a recent too, so opening it doesn't load it as a PDF
+            let last = checked_recent(&format!("{}-recent", name), 2);
+            let given = checked_recent(name, 0);
+            let argument = given.get_filepath();
+            let mut manager = manager(vec![last, given]);
+
+            assert_eq!(manager.open_on_startup(startup, Some(argument.clone())), Ok(true));
+            let _ = fs::remove_file(&argument);
+            let _ = fs::remove_file(manager.files[0].get_filepath());
+            assert!(manager.is_open(&argument));
+        }
+    }
+
+    #[test]
+    fn last_document_reopens_the_last_read() {
+        let file = checked_recent("startup-last", 4);
+        let path = file.get_filepath();
+        let mut manager = manager(vec![file]);
+
+        assert_eq!(manager.open_on_startup(Startup::LastDocument, None), Ok(true));
+        let _ = fs::remove_file(&path);
+        assert!(manager.is_open(&path));
+        assert_eq!(manager.get_cur_file_info().get_cur_page(), 4);
+    }
+
+    #[test]
+    fn the_other_choices_stay_on_the_opening_page() {
+        let file = checked_recent("startup-other", 4);
+        let path = file.get_filepath();
+        let mut manager = manager(vec![file]);
+
+        assert_eq!(manager.open_on_startup(Startup::OpeningPage, None), Ok(false));
+        assert_eq!(manager.open_on_startup(Startup::BlankNote, None), Ok(false));
+        let _ = fs::remove_file(&path);
+        assert!(!manager.has_document());
+    }
+
+    #[test]
+    fn without_a_last_document_the_opening_page_stays() {
+        assert_eq!(manager(vec![]).open_on_startup(Startup::LastDocument, None), Ok(false));
+
+        let mut manager = manager(vec![recent(&temp_path("startup-gone.pdf"), 0, 100)]);
+        assert!(manager.open_on_startup(Startup::LastDocument, None).is_err());
+        assert!(!manager.has_document());
+    }
+
+    #[test]
+    fn short_names_show_in_full() {
+        assert_eq!(trim_file_name("notes.pdf", 20, false), "notes.pdf");
+        assert_eq!(trim_file_name("notes.pdf", 20, true), "notes");
+    }
+
+    #[test]
+    fn long_names_keep_the_extension_when_it_is_shown() {
+        let name = "a-very-long-document-name.pdf";
+        assert_eq!(trim_file_name(name, 15, false), "a-very-lo...pdf");
+    }
+
+    #[test]
+    fn long_names_hide_the_extension_when_it_is_hidden() {
+        let name = "a-very-long-document-name.pdf";
+        let trimmed = trim_file_name(name, 15, true);
+        assert_eq!(trimmed, "a-very-long-...");
+        assert!(!trimmed.contains("pdf"));
+    }
+
+    #[test]
+    fn trimmed_names_never_run_past_the_limit() {
+        let name = "a-very-long-document-name.pdf";
+        for max_len in 6..40 {
+            assert!(trim_file_name(name, max_len, false).chars().count() <= max_len);
+            assert!(trim_file_name(name, max_len, true).chars().count() <= max_len);
+        }
+        // too short for any of the name, only the ending is left
+        assert_eq!(trim_file_name(name, 2, false), "...pdf");
+        assert_eq!(trim_file_name(name, 0, true), "...");
+    }
+
+    #[test]
+    fn hiding_the_extension_can_make_a_name_fit() {
+        // 16 characters with the extension, 12 without
+        assert_eq!(trim_file_name("quarterly-12.pdf", 12, false), "quarte...pdf");
+        assert_eq!(trim_file_name("quarterly-12.pdf", 12, true), "quarterly-12");
+    }
+
+    // Drives slint's timers from a clock the test moves by hand
+    struct TestPlatform(Rc<Cell<Duration>>);
+
+    impl slint::platform::Platform for TestPlatform {
+        fn create_window_adapter(&self) -> Result<Rc<dyn slint::platform::WindowAdapter>, slint::PlatformError> {
+            Err(slint::PlatformError::NoPlatform)
+        }
+
+        fn duration_since_start(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn changes_are_saved_once_they_have_been_quiet() {
+        let clock = Rc::new(Cell::new(Duration::ZERO));
+        slint::platform::set_platform(Box::new(TestPlatform(clock.clone()))).unwrap();
+        let advance = |by: Duration| {
+            clock.set(clock.get() + by);
+            slint::platform::update_timers_and_animations();
+        };
+        let filename = temp_path("debounce.json");
+        let _ = fs::remove_file(&filename);
+        let file_manager = Arc::new(Mutex::new(manager(vec![recent("a.pdf", 2, 100)])));
+        let schedule_save = save_scheduler(&file_manager, &filename);
+
+        schedule_save();
+        advance(SAVE_DELAY / 2);
+        // another change restarts the wait
+        schedule_save();
+        advance(SAVE_DELAY / 2);
+        assert!(fs::metadata(&filename).is_err());
+        advance(SAVE_DELAY / 2);
+        let saved = database::parse(&txt_file::read_file(&filename).unwrap()).unwrap();
+        let _ = fs::remove_file(&filename);
+        assert_eq!(saved[0].cur_file_page, 2);
+    }
+
+    #[test]
+    fn verbatim_paths_are_written_the_usual_way() {
+        assert_eq!(strip_verbatim(r"\\?\C:\Books\a.pdf"), r"C:\Books\a.pdf");
+        assert_eq!(strip_verbatim(r"\\?\UNC\server\share\a.pdf"), r"\\server\share\a.pdf");
+        assert_eq!(strip_verbatim(r"\\?\GLOBALROOT\Device\a.pdf"), r"\\?\GLOBALROOT\Device\a.pdf");
+        assert_eq!(strip_verbatim("/home/me/a.pdf"), "/home/me/a.pdf");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_resolve_to_their_target() {
+        let file = checked_recent("target", 0);
+        let link = temp_path("link.pdf");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(file.get_filepath(), &link).unwrap();
+        let resolved = canonical_path(&link);
+        let _ = fs::remove_file(&link);
+        let _ = fs::remove_file(file.get_filepath());
+        assert_eq!(resolved, file.get_filepath());
+    }
+
+    #[test]
+    fn missing_files_are_reported_as_gone() {
+        let error = check_readable(&temp_path("missing.pdf")).unwrap_err();
+        assert!(error.ends_with("no longer exists"));
+    }
+
+    // A FIFO with no writer blocks whoever opens it, like a read from a stalled network share
+    #[cfg(unix)]
+    #[test]
+    fn reads_that_time_out_report_a_slow_volume() {
+        let fifo = temp_path("stalled.pdf");
+        let _ = fs::remove_file(&fifo);
+        assert!(std::process::Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+
+        let result = probe_readable(&fifo, Duration::from_millis(50));
+        // opening the other end lets the stuck probes finish
+        drop(fs::OpenOptions::new().write(true).open(&fifo));
+        let _ = fs::remove_file(&fifo);
+        assert!(result.unwrap_err().contains("slow or unavailable network drive"));
+    }
+
+    #[test]
+    fn a_shorter_reload_moves_the_saved_page_onto_the_last_page() {
+        let mut file = recent("a.pdf", 40, 100);
+        file.set_page_count(30);
+        assert_eq!(file.get_page_count(), 30);
+        assert_eq!(file.get_cur_page(), 29);
+    }
+
+    #[test]
+    fn a_longer_reload_keeps_the_saved_page() {
+        let mut file = recent("a.pdf", 12, 100);
+        file.set_page_count(20);
+        file.set_page_count(25);
+        assert_eq!(file.get_cur_page(), 12);
+    }
+
+    #[test]
+    fn a_document_without_pages_keeps_the_saved_page() {
+        let mut file = recent("a.pdf", 3, 100);
+        file.set_page_count(0);
+        assert_eq!(file.get_cur_page(), 3);
+    }
+
+    #[test]
+    fn an_unchanged_file_keeps_the_count_its_last_load_gave() {
+        // e.g. the count after pdfium repaired the file, which its header may not agree with
+        let mut file = checked_recent("repaired", 8);
+        let path = file.get_filepath();
+        file.page_count = 9;
+
+        file.refresh_details();
+        let _ = fs::remove_file(&path);
+        assert_eq!(file.get_page_count(), 9);
+        assert_eq!(file.get_cur_page(), 8);
+    }
+
+    #[test]
+    fn a_recent_edited_to_fewer_pages_reopens_on_its_last_page() {
+        let mut file = checked_recent("shrunk", 40);
+        let path = file.get_filepath();
+        file.page_count = 50;
+        // a modified time that no longer matches, as after the file was edited elsewhere
+        file.modified -= 1;
+
+        file.refresh_details_with(|_| Some(30), |_| Some(false));
+        let _ = fs::remove_file(&path);
+        assert_eq!(file.get_page_count(), 30);
+        assert_eq!(file.get_cur_page(), 29);
+    }
+
+    #[test]
+    fn older_recents_read_left_to_right() {
+        let file: FileInfo = serde_json::from_str(r#"{"name": "a.pdf", "filepath": "/a.pdf", "cur_file_page": 0}"#).unwrap();
+        assert_eq!(file.get_reading_direction(), ReadingDirection::LeftToRight);
+    }
+
+    #[test]
+    fn a_failed_save_reports_the_error_and_keeps_the_recents() {
+        // a database.json that can't be written, as on a full or read-only disk
+        let unwritable = temp_path("missing-folder/database.json");
+        let manager = manager(vec![recent("a.pdf", 5, 100)]);
+        assert!(manager.try_save(&unwritable).is_err());
+
+        // saving elsewhere afterwards still has everything
+        let elsewhere = temp_path("elsewhere.json");
+        manager.try_save(&elsewhere).unwrap();
+        let saved = database::parse(&txt_file::read_file(&elsewhere).unwrap()).unwrap();
+        let _ = fs::remove_file(&elsewhere);
+        assert_eq!(saved[0].cur_file_page, 5);
+    }
+
+    #[test]
+    fn undoing_a_finished_mark_keeps_highlights_added_since() {
+        let mut manager = manager(Vec::new());
+        manager.cur_file_info = recent("/open.pdf", 3, 100);
+        manager.set_cur_path("/open.pdf".to_string());
+
+        assert_eq!(manager.toggle_finished("/open.pdf"), Some(true));
+        manager.get_cur_file_info().add_highlight(Highlight {
+            page: 3,
+            first: 0,
+            last: 9,
+            color: "#FFFF00".to_string(),
+        });
+        manager.undo_recents_change().unwrap();
+        assert!(!manager.get_cur_file_info().get_finished());
+        assert_eq!(manager.get_cur_file_info().get_highlights().len(), 1);
+    }
+
+    #[test]
+    fn undoing_a_reorder_keeps_page_notes_added_since() {
+        let mut manager = manager(vec![recent("/a.pdf", 0, 100), recent("/b.pdf", 0, 90)]);
+
+        assert!(manager.reorder("/a.pdf", "/b.pdf"));
+        manager.file_mut("/a.pdf").unwrap().set_page_note(2, "kept");
+        manager.undo_recents_change().unwrap();
+        let a_file = manager.file_mut("/a.pdf").unwrap();
+        assert_eq!(a_file.order, 0);
+        assert_eq!(a_file.get_page_note(2), "kept");
     }
 }
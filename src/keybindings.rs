@@ -0,0 +1,366 @@
+use crate::settings::{self, Settings};
+use crate::{App, AppService, KeyBinding};
+use serde::{Deserialize, Serialize};
+use slint::platform::Key;
+use slint::{ComponentHandle, VecModel};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// Every rebindable action as (action, label, default keys). Defaults follow common PDF readers
+pub const ACTIONS: &[(&str, &str, &str)] = &[
+    ("next-page", "Next page", "Right"),
+    ("previous-page", "Previous page", "Left"),
+    ("next-bookmark", "Next bookmark", "Ctrl+Right"),
+    ("previous-bookmark", "Previous bookmark", "Ctrl+Left"),
+    ("zoom-in", "Zoom in", "="),
+    ("zoom-out", "Zoom out", "-"),
+    ("reset-zoom", "Reset zoom", "Ctrl+0"),
+    ("save-notes", "Save notes", "Ctrl+S"),
+    ("notes-font-up", "Larger notes text", "Ctrl+Shift+="),
+    ("notes-font-down", "Smaller notes text", "Ctrl+Shift+-"),
+    ("distraction-free", "Distraction-free reading", "F11"),
+    ("floating-page", "Floating page window", "Ctrl+Shift+M"),
+    ("toggle-thumbnails", "Show or hide thumbnails", "F9"),
+    ("toggle-toc", "Show or hide contents", "Ctrl+T"),
+    ("quick-open", "Quick open", "Ctrl+P"),
+    ("search-all", "Search all documents", "Ctrl+Shift+F"),
+    ("always-on-top", "Keep the window on top", "Ctrl+Shift+A"),
+    ("toggle-dark-mode", "Toggle dark mode", "Ctrl+D"),
+    ("cycle-page-colors", "Next page colors", "Ctrl+Shift+C"),
+    ("show-keybindings", "Keyboard shortcuts", "Ctrl+K"),
+    ("show-help", "Shortcut help", "F1"),
+    ("ui-scale-up", "Larger interface", "Ctrl+="),
+    ("ui-scale-down", "Smaller interface", "Ctrl+-"),
+];
+
+const NAMED_KEYS: &[(Key, &str)] = &[
+    (Key::RightArrow, "Right"),
+    (Key::LeftArrow, "Left"),
+    (Key::UpArrow, "Up"),
+    (Key::DownArrow, "Down"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::Escape, "Escape"),
+    (Key::Return, "Enter"),
+    (Key::Tab, "Tab"),
+    (Key::Backspace, "Backspace"),
+    (Key::Delete, "Delete"),
+    (Key::Space, "Space"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+];
+
+// Characters shift gives on common layouts. A combination names the key itself, so "Ctrl+Shift+="
+// matches whether the platform reports = or + for it
+const SHIFTED_KEYS: &[(char, char)] = &[('+', '='), ('_', '-')];
+
+const MODIFIER_KEYS: &[Key] = &[
+    Key::Shift,
+    Key::ShiftR,
+    Key::Control,
+    Key::ControlR,
+    Key::Alt,
+    Key::AltGr,
+    Key::Meta,
+    Key::MetaR,
+    Key::CapsLock,
+];
+
+// Builds a combination like "Ctrl+Shift+P" from a Slint key event. Returns None for a lone modifier
+pub fn combo(text: &str, control: bool, alt: bool, shift: bool) -> Option<String> {
+    let mut chars = text.chars();
+    let ch = chars.next()?;
+    if chars.next().is_some() || MODIFIER_KEYS.iter().any(|key| char::from(*key) == ch) {
+        return None;
+    }
+    let ch = match SHIFTED_KEYS.iter().find(|(shifted, _)| shift && *shifted == ch) {
+        Some((_, key)) => *key,
+        None => ch,
+    };
+    let key = match NAMED_KEYS.iter().find(|(key, _)| char::from(*key) == ch) {
+        Some((_, name)) => name.to_string(),
+        None => ch.to_uppercase().to_string(),
+    };
+
+    let mut combo = String::new();
+    if control {
+        combo += "Ctrl+";
+    }
+    if alt {
+        combo += "Alt+";
+    }
+    if shift {
+        combo += "Shift+";
+    }
+    Some(combo + &key)
+}
+
+// User changes on top of the defaults. Only overrides are stored, so actions added later still get
+// their default keys
+#[derive(Default, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct KeyBindings {
+    overrides: BTreeMap<String, String>,
+}
+
+impl KeyBindings {
+    pub fn get_binding(&self, action: &str) -> String {
+        match self.overrides.get(action) {
+            Some(keys) => keys.clone(),
+            None => ACTIONS
+                .iter()
+                .find(|(name, _, _)| *name == action)
+                .map_or(String::new(), |(_, _, keys)| keys.to_string()),
+        }
+    }
+
+    // Finds the action bound to a key combination
+    pub fn resolve(&self, keys: &str) -> Option<&'static str> {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| self.get_binding(action) == keys)
+            .map(|(action, _, _)| *action)
+    }
+
+    // Binds keys to action, refusing keys another action already uses
+    pub fn set_binding(&mut self, action: &str, keys: &str) -> Result<(), String> {
+        if let Some(other) = self.resolve(keys) {
+            if other != action {
+                return Err(format!("{} is already used by \"{}\"", keys, label(other)));
+            }
+        }
+        self.overrides.insert(action.to_string(), keys.to_string());
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.overrides.clear();
+    }
+}
+
+pub fn label(action: &str) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(name, _, _)| *name == action)
+        .map_or("", |(_, label, _)| label)
+}
+
+// Registers the callbacks that turn key presses into actions and let the user rebind them
+pub fn bind(app: &App, settings: &Arc<Mutex<Settings>>) {
+    /*  CALLBACK:
+        Turns a Slint key event into a key combination string such as "Ctrl+Shift+P"
+
+        # Arguments
+        * 'text' - text of the key event
+        * 'control', 'alt', 'shift' - modifier state of the key event
+
+        # Return
+        * the key combination, or "" if only a modifier is pressed
+    */
+    app.global::<AppService>().on_key_combo(|text, control, alt, shift| {
+        combo(text.as_str(), control, alt, shift)
+            .unwrap_or_default()
+            .into()
+    });
+
+    /*  CALLBACK:
+        Looks up the action bound to a key combination
+
+        # Arguments
+        * 'keys' - key combination from key-combo
+
+        # Return
+        * the action name, or "" if the keys aren't bound
+    */
+    app.global::<AppService>().on_resolve_key({
+        let cloned_settings = settings.clone();
+        move |keys| {
+            let settings = cloned_settings.lock().unwrap();
+            settings
+                .get_keybindings()
+                .resolve(keys.as_str())
+                .unwrap_or_default()
+                .into()
+        }
+    });
+
+    /*  CALLBACK:
+        Returns every action with its current key combination for the shortcuts panel
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of key bindings
+    */
+    app.global::<AppService>().on_get_key_bindings({
+        let cloned_settings = settings.clone();
+        move || {
+            let settings = cloned_settings.lock().unwrap();
+            let bindings: Vec<KeyBinding> = ACTIONS
+                .iter()
+                .map(|(action, label, _)| KeyBinding {
+                    action: (*action).into(),
+                    label: (*label).into(),
+                    keys: settings.get_keybindings().get_binding(action).into(),
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(bindings))
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the actions whose name or keys contain the query, ignoring case, with their current
+        key combinations, for the shortcut help
+
+        # Arguments
+        * 'query' - text typed in the help's search box, every action if empty
+        * 'revision' - unused, changes whenever the help opens so rebound keys show
+
+        # Return
+        * A Slint vector type of key bindings
+    */
+    app.global::<AppService>().on_filter_key_bindings({
+        let cloned_settings = settings.clone();
+        move |query, _revision| {
+            let settings = cloned_settings.lock().unwrap();
+            let query = query.trim().to_lowercase();
+            let bindings: Vec<KeyBinding> = ACTIONS
+                .iter()
+                .map(|(action, label, _)| (action, label, settings.get_keybindings().get_binding(action)))
+                .filter(|(_, label, keys)| label.to_lowercase().contains(&query) || keys.to_lowercase().contains(&query))
+                .map(|(action, label, keys)| KeyBinding {
+                    action: (*action).into(),
+                    label: (*label).into(),
+                    keys: keys.into(),
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(bindings))
+        }
+    });
+
+    /*  CALLBACK:
+        Binds a key combination to an action and saves the settings
+
+        # Arguments
+        * 'action' - action to rebind
+        * 'keys' - new key combination
+
+        # Return
+        * "" on success, otherwise a message naming the action the keys conflict with
+    */
+    app.global::<AppService>().on_set_key_binding({
+        let cloned_settings = settings.clone();
+        move |action, keys| {
+            let mut settings = cloned_settings.lock().unwrap();
+            match settings.get_keybindings_mut().set_binding(action.as_str(), keys.as_str()) {
+                Ok(_) => {
+                    settings.save(settings::SETTINGS_FILE);
+                    "".into()
+                }
+                Err(e) => e.into(),
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Restores the default key bindings
+
+        # Arguments
+        N/A
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_reset_key_bindings({
+        let cloned_settings = settings.clone();
+        move || {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.get_keybindings_mut().reset();
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combos_name_the_modifiers_in_order() {
+        assert_eq!(combo("p", true, false, true), Some("Ctrl+Shift+P".to_string()));
+        assert_eq!(combo("x", true, true, true), Some("Ctrl+Alt+Shift+X".to_string()));
+        assert_eq!(combo(&char::from(Key::F11).to_string(), false, false, false), Some("F11".to_string()));
+    }
+
+    #[test]
+    fn a_lone_modifier_is_no_combo() {
+        assert_eq!(combo(&char::from(Key::Control).to_string(), true, false, false), None);
+        assert_eq!(combo("", false, false, false), None);
+    }
+
+    #[test]
+    fn shifted_symbols_name_their_key() {
+        assert_eq!(combo("+", true, false, true), Some("Ctrl+Shift+=".to_string()));
+        assert_eq!(combo("_", true, false, true), Some("Ctrl+Shift+-".to_string()));
+        assert_eq!(combo("+", false, false, false), Some("+".to_string()));
+    }
+
+    #[test]
+    fn every_default_resolves_to_its_action() {
+        let bindings = KeyBindings::default();
+        for (action, _, keys) in ACTIONS {
+            assert_eq!(bindings.resolve(keys), Some(*action), "{} is taken", keys);
+        }
+        assert_eq!(bindings.resolve("Ctrl+Alt+Shift+F12"), None);
+    }
+
+    #[test]
+    fn keys_another_action_uses_are_refused() {
+        let mut bindings = KeyBindings::default();
+        let error = bindings.set_binding("zoom-in", "Ctrl+S").unwrap_err();
+        assert!(error.contains("Save notes"));
+        assert_eq!(bindings.get_binding("zoom-in"), "=");
+        // an action's own keys can be set again
+        assert!(bindings.set_binding("save-notes", "Ctrl+S").is_ok());
+    }
+
+    #[test]
+    fn a_rebound_action_leaves_its_old_keys() {
+        let mut bindings = KeyBindings::default();
+        bindings.set_binding("next-page", "N").unwrap();
+        assert_eq!(bindings.resolve("N"), Some("next-page"));
+        assert_eq!(bindings.resolve("Right"), None);
+    }
+
+    #[test]
+    fn overrides_survive_saving_and_loading() {
+        let mut bindings = KeyBindings::default();
+        bindings.set_binding("zoom-in", "Ctrl+I").unwrap();
+        let loaded: KeyBindings = serde_json::from_str(&serde_json::to_string(&bindings).unwrap()).unwrap();
+        assert_eq!(loaded.resolve("Ctrl+I"), Some("zoom-in"));
+        // the actions left alone keep their defaults
+        assert_eq!(loaded.get_binding("zoom-out"), "-");
+    }
+
+    #[test]
+    fn reset_brings_the_defaults_back() {
+        let mut bindings = KeyBindings::default();
+        bindings.set_binding("next-page", "N").unwrap();
+        bindings.reset();
+        assert_eq!(bindings.resolve("Right"), Some("next-page"));
+        assert_eq!(bindings.resolve("N"), None);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::interface::FileManager;
+use crate::measure::ShownArea;
+use crate::pages;
+use crate::platform;
+use crate::settings::{store_setting, Settings};
+use crate::{App, BackendPDF};
+use pdfium_render::prelude::*;
+use slint::{ComponentHandle, VecModel};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+// Schemes of the web addresses a link on the page may open. Anything else, e.g. a file: or javascript:
+// link, could start a program rather than a browser
+const OPENED_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+// Where a link on the page goes
+#[derive(Clone, PartialEq, Debug)]
+pub enum LinkTarget {
+    // 0-based page of this document
+    Page(u32),
+    Uri(String),
+}
+
+// A link's clickable area. The corners are fractions of the whole page's width and height from its top
+// left corner, like comment pins, so the area can be mapped onto any zoom or crop
+#[derive(Clone, Debug)]
+pub struct PageLink {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub target: LinkTarget,
+}
+
+// The links of the page that go somewhere PDFer can follow, a page of this document or an address.
+// Links into other documents or launching files are left out
+pub fn page_links(page: &PdfPage) -> Vec<PageLink> {
+    let (width, height) = (page.width().value, page.height().value);
+    page.links()
+        .iter()
+        .filter_map(|link| Some(PageLink::new(link.rect().ok()?, width, height, link_target(&link)?)))
+        .collect()
+}
+
+impl PageLink {
+    // The link for a rectangle in points on a page of the given size. PDF coordinates start at the
+    // bottom left corner, so the top is flipped over
+    fn new(rect: PdfRect, page_width: f32, page_height: f32, target: LinkTarget) -> Self {
+        let (width, height) = (page_width.max(1.0), page_height.max(1.0));
+        PageLink {
+            left: rect.left().value / width,
+            top: 1.0 - rect.top().value / height,
+            right: rect.right().value / width,
+            bottom: 1.0 - rect.bottom().value / height,
+            target,
+        }
+    }
+}
+
+// Like bookmarks, links carry their destination or an action holding it, or a web address
+fn link_target(link: &PdfLink) -> Option<LinkTarget> {
+    if let Some(destination) = link.destination() {
+        return destination.page_index().ok().map(|page| LinkTarget::Page(u32::from(page)));
+    }
+    let action = link.action()?;
+    if let Some(local) = action.as_local_destination_action() {
+        let page = local.destination().ok()?.page_index().ok()?;
+        return Some(LinkTarget::Page(u32::from(page)));
+    }
+    let uri = action.as_uri_action()?.uri().ok()?;
+    let uri = uri.trim();
+    (!uri.is_empty()).then(|| LinkTarget::Uri(uri.to_string()))
+}
+
+// Whether the address is one PDFer hands to the default browser or mail program
+pub fn opens_externally(uri: &str) -> bool {
+    let lower = uri.trim().to_lowercase();
+    OPENED_SCHEMES.iter().any(|scheme| lower.starts_with(scheme))
+}
+
+// Registers the callbacks for the links on the page and for following them
+pub fn bind(
+    app: &App,
+    settings: &Arc<Mutex<Settings>>,
+    file_manager: &Arc<Mutex<FileManager>>,
+    shown_area: &Rc<Cell<ShownArea>>,
+) {
+    /*  CALLBACK:
+        Returns the links of a page that PDFer can follow, placed on the displayed image. Links cropped
+        away are left out
+
+        # Arguments
+        * 'page_index' - 0-based page
+
+        # Return
+        * A Slint vector type of links, each going to a page or opening an address
+    */
+    app.global::<BackendPDF>().on_get_links({
+        let cloned_file_manager = file_manager.clone();
+        let shown_area = shown_area.clone();
+        move |page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let area = shown_area.get();
+            let pdfium = Pdfium::default();
+            let mut found = Vec::new();
+            if let Ok(file_path) = file_manager.get_cur_path() {
+                if let Ok(document) = pdfium.load_pdf_from_file(file_path.as_str(), None) {
+                    if let Ok(page) = pages::get_page(&document, page_index.max(0) as u32) {
+                        found = page_links(&page);
+                    }
+                }
+            }
+            let shown: Vec<crate::PageLink> = found
+                .into_iter()
+                .filter_map(|link| {
+                    let (x, y, w, h) = area.shown_rect(link.left, link.top, link.right, link.bottom)?;
+                    let (page, url) = match link.target {
+                        LinkTarget::Page(page) => (page as i32, String::new()),
+                        LinkTarget::Uri(uri) => (-1, uri),
+                    };
+                    Some(crate::PageLink {
+                        x,
+                        y,
+                        w,
+                        h,
+                        page,
+                        url: url.into(),
+                    })
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(shown))
+        }
+    });
+
+    /*  CALLBACK:
+        Opens the address of a link on the page in the default browser or mail program. Only web and
+        mail addresses are opened, anything else could start a program
+
+        # Arguments
+        * 'url' - the link's address
+
+        # Return
+        * why the link wasn't opened, empty if it was
+    */
+    app.global::<BackendPDF>().on_open_link_url(|url| {
+        if !opens_externally(url.as_str()) {
+            return format!("Not opened, only web and mail links are: {}", url).into();
+        }
+        match platform::open_with_default(url.as_str()) {
+            Ok(_) => "".into(),
+            Err(e) => {
+                eprintln!("Error opening link {}: {}", url, e);
+                format!("Couldn't open the link: {}", e).into()
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Stores whether following a link out of the document asks first
+
+        # Arguments
+        * 'confirm' - true to ask before opening an address
+
+        # Return
+        N / A
+    */
+    app.global::<BackendPDF>().on_set_confirm_links(store_setting(settings, Settings::set_confirm_links));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A link an inch in from the top left corner of a US Letter page, 612 by 792 points
+    fn letter_link() -> PageLink {
+        let rect = PdfRect::new_from_values(684.0, 72.0, 720.0, 306.0);
+        PageLink::new(rect, 612.0, 792.0, LinkTarget::Page(4))
+    }
+
+    fn letter_area() -> ShownArea {
+        ShownArea {
+            left: 0.0,
+            top: 0.0,
+            width: 612.0,
+            height: 792.0,
+            page_width: 612.0,
+            page_height: 792.0,
+        }
+    }
+
+    fn assert_near(actual: (f32, f32, f32, f32), expected: (f32, f32, f32, f32)) {
+        let pairs = [(actual.0, expected.0), (actual.1, expected.1), (actual.2, expected.2), (actual.3, expected.3)];
+        assert!(pairs.iter().all(|(a, b)| (a - b).abs() < 1e-5), "{:?} is not {:?}", actual, expected);
+    }
+
+    #[test]
+    fn link_rectangles_are_measured_from_the_top_left() {
+        let link = letter_link();
+        assert_near((link.left, link.top, link.right, link.bottom), (72.0 / 612.0, 72.0 / 792.0, 0.5, 108.0 / 792.0));
+        assert_eq!(link.target, LinkTarget::Page(4));
+    }
+
+    #[test]
+    fn links_land_where_they_are_on_the_displayed_page() {
+        let link = letter_link();
+        let shown = letter_area().shown_rect(link.left, link.top, link.right, link.bottom).unwrap();
+        assert_near(shown, (72.0 / 612.0, 72.0 / 792.0, 234.0 / 612.0, 36.0 / 792.0));
+    }
+
+    #[test]
+    fn links_follow_a_crop() {
+        // the top half of the page, shown from an inch in on the left
+        let area = ShownArea {
+            left: 72.0,
+            width: 540.0,
+            height: 396.0,
+            ..letter_area()
+        };
+        let link = letter_link();
+        let shown = area.shown_rect(link.left, link.top, link.right, link.bottom).unwrap();
+        assert_near(shown, (0.0, 72.0 / 396.0, 234.0 / 540.0, 36.0 / 396.0));
+    }
+
+    #[test]
+    fn links_cropped_away_are_left_out() {
+        let area = ShownArea {
+            top: 396.0,
+            height: 396.0,
+            ..letter_area()
+        };
+        let link = letter_link();
+        assert_eq!(area.shown_rect(link.left, link.top, link.right, link.bottom), None);
+    }
+
+    #[test]
+    fn only_web_and_mail_addresses_open_externally() {
+        assert!(opens_externally("https://example.com"));
+        assert!(opens_externally(" HTTP://example.com"));
+        assert!(opens_externally("mailto:someone@example.com"));
+        assert!(!opens_externally("file:///etc/passwd"));
+        assert!(!opens_externally("javascript:alert(1)"));
+        assert!(!opens_externally("example.com"));
+    }
+}
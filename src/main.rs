@@ -16,29 +16,64 @@ Copyright Disclaimer: This code is open source and free to use with proper attri
 //imports
 use pdfium_render::prelude::*;
 slint::include_modules!();
-use slint::VecModel;
+use slint::{Model, VecModel};
+mod annotations;
+mod citation;
+mod clipboard;
+mod compare;
+mod crop;
+mod database;
+mod export;
+mod forms;
+mod fuzzy;
+mod highlights;
+mod images;
+mod instance;
 mod interface;
+mod keybindings;
+mod links;
+mod markdown;
+mod measure;
+mod ocr;
+mod options;
+mod overlay;
+mod page_cache;
+mod pages;
+mod pins;
+mod platform;
+mod recovery;
+mod render;
+mod search;
+mod session;
+mod settings;
+mod speech;
+mod text;
+mod thumbnails;
+mod toc;
+mod timestamps;
 mod txt_file;
 use serde_json::Result;
-use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+use settings::{store_named_setting, store_setting, Settings};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use std::env;
 
 fn main() -> Result<()> {
     //ideally result should also have: Result<(), slint::PlatformError>
 
-
-
-
     // Application window -- define all global callbacks on this window
     let app = App::new().unwrap();
 
     // Initializes the file manager with local data if available
     let mut initial_file_manager = interface::FileManager::new();
-    match txt_file::read_file("database.json") {
+    match txt_file::read_file(interface::DATABASE_FILE) {
         Ok(data) => {
             if data != "" {
-                initial_file_manager.set_files(serde_json::from_str(data.as_str())?)
+                initial_file_manager.set_files(database::parse(data.as_str())?)
             }
         }
         Err(_) => (),
@@ -46,6 +81,230 @@ fn main() -> Result<()> {
 
     let file_manager = Arc::new(Mutex::new(initial_file_manager));
 
+    let settings = Arc::new(Mutex::new(settings::Settings::load(settings::SETTINGS_FILE)));
+    app.global::<AppService>()
+        .set_hide_extensions(settings.lock().unwrap().get_hide_extensions());
+    app.global::<BackendPDF>()
+        .set_render_quality(settings.lock().unwrap().get_render_quality().name().into());
+    app.global::<BackendPDF>()
+        .set_page_colors(settings.lock().unwrap().get_page_colors().name().into());
+    app.global::<BackendPDF>()
+        .set_contrast(settings.lock().unwrap().get_contrast() as i32);
+    app.global::<BackendPDF>()
+        .set_page_background(settings.lock().unwrap().get_page_background().name().into());
+    app.global::<BackendPDF>()
+        .set_gray_highlights(settings.lock().unwrap().get_gray_highlights());
+    app.global::<BackendPDF>()
+        .set_render_annotations(settings.lock().unwrap().get_render_annotations());
+    app.global::<BackendPDF>()
+        .set_page_fit(settings.lock().unwrap().get_page_fit().name().into());
+    app.global::<AppService>()
+        .set_default_zoom(render::zoom_name(settings.lock().unwrap().get_default_zoom()).into());
+    app.global::<AppService>()
+        .set_page_boundary(settings.lock().unwrap().get_page_boundary().name().into());
+    app.global::<BackendPDF>()
+        .set_speech_rate(settings.lock().unwrap().get_speech_rate() as i32);
+    app.global::<BackendTextEditor>()
+        .set_notes_font_size(settings.lock().unwrap().get_notes_font_size() as i32);
+    app.global::<BackendTextEditor>()
+        .set_font_step(settings.lock().unwrap().get_font_step() as i32);
+    app.global::<BackendTextEditor>()
+        .set_font_scope(settings.lock().unwrap().get_font_scope().name().into());
+    app.global::<BackendTextEditor>()
+        .set_notes_format(settings.lock().unwrap().get_notes_format().name().into());
+    app.global::<BackendTextEditor>()
+        .set_page_notes(settings.lock().unwrap().get_page_notes());
+    app.global::<PDFerPalette>()
+        .set_theme(settings.lock().unwrap().get_theme().name().into());
+    app.global::<AppService>()
+        .set_startup(settings.lock().unwrap().get_startup().name().into());
+    app.global::<PDFerPalette>()
+        .set_ui_scale(settings.lock().unwrap().get_ui_scale());
+    app.global::<AppService>()
+        .set_confirm_exit(settings.lock().unwrap().get_confirm_exit());
+    app.global::<AppService>()
+        .set_confirm_clear_recents(settings.lock().unwrap().get_confirm_clear_recents());
+    app.global::<BackendPDF>()
+        .set_confirm_links(settings.lock().unwrap().get_confirm_links());
+    app.global::<AppService>()
+        .set_open_in_running_instance(settings.lock().unwrap().get_open_in_running_instance());
+    app.global::<AppService>()
+        .set_distraction_free(settings.lock().unwrap().get_distraction_free());
+    app.global::<AppService>().set_always_on_top_supported(platform::always_on_top_supported());
+    app.global::<AppService>()
+        .set_always_on_top(settings.lock().unwrap().get_always_on_top());
+    app.global::<AppService>()
+        .set_toolbar_autohide(settings.lock().unwrap().get_toolbar_autohide());
+    app.global::<AppService>()
+        .set_toolbar_hide_delay(settings.lock().unwrap().get_toolbar_hide_delay() as i32);
+    app.global::<AppService>()
+        .set_toolbar_reveal_distance(settings.lock().unwrap().get_toolbar_reveal_distance() as i32);
+    app.global::<AppService>()
+        .set_idle_minutes(settings.lock().unwrap().get_idle_minutes() as i32);
+    app.global::<AppService>()
+        .set_idle_save(settings.lock().unwrap().get_idle_save());
+    app.global::<AppService>()
+        .set_idle_minimize(settings.lock().unwrap().get_idle_minimize());
+    app.global::<AppService>()
+        .set_save_notes_on_page_turn(settings.lock().unwrap().get_save_notes_on_page_turn());
+    app.global::<BackendPDF>()
+        .set_thumbnails_shown(settings.lock().unwrap().get_thumbnails_shown());
+    app.global::<BackendPDF>()
+        .set_thumbnail_size(settings.lock().unwrap().get_thumbnail_size().name().into());
+    app.global::<BackendPDF>()
+        .set_thumbnail_wheel(settings.lock().unwrap().get_thumbnail_wheel().name().into());
+    app.global::<BackendPDF>()
+        .set_toc_shown(settings.lock().unwrap().get_toc_shown());
+    app.global::<BackendPDF>()
+        .set_page_indicator(settings.lock().unwrap().get_page_indicator().name().into());
+    let page_number = settings.lock().unwrap().get_page_number_overlay().clone();
+    app.global::<BackendPDF>().set_page_number_overlay(PageNumberOptions {
+        enabled: page_number.enabled,
+        corner: page_number.corner.name().into(),
+        size: page_number.size as i32,
+        color: page_number.color.into(),
+        show_total: page_number.show_total,
+    });
+    app.global::<BackendPDF>()
+        .set_auto_crop(settings.lock().unwrap().get_auto_crop());
+    app.global::<BackendPDF>()
+        .set_highlight_color(settings.lock().unwrap().get_highlight_color().into());
+    app.global::<BackendPDF>()
+        .set_crop_padding(settings.lock().unwrap().get_crop_padding() as i32);
+    app.global::<BackendPDF>()
+        .set_center_page(settings.lock().unwrap().get_center_page());
+    app.global::<BackendPDF>()
+        .set_touch_mode(settings.lock().unwrap().get_touch_mode());
+    app.global::<BackendPDF>()
+        .set_page_gap(settings.lock().unwrap().get_page_gap() as i32);
+    app.global::<BackendPDF>()
+        .set_render_cap(settings.lock().unwrap().get_render_cap() as i32);
+    app.global::<BackendPDF>()
+        .set_render_side(settings.lock().unwrap().get_render_side() as i32);
+    app.global::<BackendPDF>()
+        .set_selection_dpi(settings.lock().unwrap().get_selection_dpi() as i32);
+    app.global::<BackendPDF>()
+        .set_citation_style(settings.lock().unwrap().get_citation_style().name().into());
+    app.global::<BackendPDF>()
+        .set_warmup_pages(settings.lock().unwrap().get_warmup_pages() as i32);
+    app.global::<BackendPDF>()
+        .set_page_cache_megabytes(settings.lock().unwrap().get_page_cache_megabytes() as i32);
+    app.global::<AppService>()
+        .set_recents_as_list(settings.lock().unwrap().get_recents_as_list());
+    app.global::<AppService>()
+        .set_recents_grouped(settings.lock().unwrap().get_recents_grouped());
+    app.global::<AppService>()
+        .set_recents_sort(settings.lock().unwrap().get_recents_sort().name().into());
+    app.global::<AppService>()
+        .set_open_on_double_click(settings.lock().unwrap().get_open_on_double_click());
+    app.global::<AppService>()
+        .set_timestamp_format(settings.lock().unwrap().get_timestamp_format().name().into());
+    app.global::<AppService>()
+        .set_recents_filter(settings.lock().unwrap().get_recents_filter().name().into());
+    app.global::<AppService>()
+        .set_auto_finish(settings.lock().unwrap().get_auto_finish());
+    app.global::<AppService>()
+        .set_first_run_prompt(settings.lock().unwrap().get_first_run_prompt());
+
+    // A second instance shares database.json and recovery.json with the first. A PDF given on the
+    // command line is handed to the first instance if the user wants a single window, and this one
+    // exits. Otherwise it runs on, merging its recents on save, but leaves crash recovery to the first
+    // instance, whose live snapshot would otherwise look like a leftover
+    let holds_lock = instance::acquire_lock(instance::LOCK_FILE);
+    if !holds_lock {
+        if let Some(path) = env::args().nth(1) {
+            if settings.lock().unwrap().get_open_in_running_instance()
+                && instance::forward(instance::LOCK_FILE, interface::canonical_path(&path).as_str())
+            {
+                println!("Opened {} in the running PDFer", path);
+                return Ok(());
+            }
+        }
+        println!("Another PDFer is running, recents will be merged with it on save");
+    }
+
+    // A PDF given on the command line is opened straight away, whatever the startup choice. Otherwise
+    // the last read document is reopened, or the scratchpad shown, if the user asked for that. Either
+    // way a missing file leaves the opening page up.
+    let startup = settings.lock().unwrap().get_startup();
+    match file_manager.lock().unwrap().open_on_startup(startup, env::args().nth(1)) {
+        Ok(true) => app.set_active_page(1),
+        Ok(false) => {
+            if startup == settings::Startup::BlankNote {
+                let scratch = txt_file::read_file(txt_file::SCRATCH_FILE).unwrap_or_default();
+                app.global::<BackendTextEditor>().set_scratch_text(scratch.into());
+                app.global::<BackendTextEditor>().set_scratch_shown(true);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}, showing the opening page", e);
+            app.global::<AppService>().set_error_message(e.into());
+        }
+    }
+
+    // A recovery snapshot left behind means the last session didn't exit cleanly, offer to restore it
+    let leftover_snapshot = if holds_lock { recovery::load_leftover(recovery::RECOVERY_FILE) } else { None };
+    if let Some(snapshot) = &leftover_snapshot {
+        app.global::<AppService>().set_recovery_details(snapshot.describe().into());
+        app.global::<AppService>().set_recovery_shown(true);
+    }
+
+    // The first launch opens the file dialog over an empty opening page, unless the user turned that
+    // off, so there is something to read straight away. It only happens once, later launches with no
+    // recents show the welcome instead
+    let first_run = settings.lock().unwrap().get_first_run();
+    if first_run {
+        let mut settings = settings.lock().unwrap();
+        settings.set_first_run(false);
+        settings.save(settings::SETTINGS_FILE);
+    }
+    if first_run
+        && settings.lock().unwrap().get_first_run_prompt()
+        && app.get_active_page() == 0
+        && !app.global::<BackendTextEditor>().get_scratch_shown()
+        && leftover_snapshot.is_none()
+        && file_manager.lock().unwrap().get_files().is_empty()
+    {
+        // once the window is up, so the dialog has something to sit over
+        let app_weak = app.as_weak();
+        slint::Timer::single_shot(Duration::ZERO, move || {
+            if let Some(app) = app_weak.upgrade() {
+                app.global::<AppService>().invoke_open_file();
+            }
+        });
+    }
+
+    // The recents are shown with their stored details straight away, their paths are resolved and the
+    // details re-read on a thread of their own so a file on a slow share can't hold up the window
+    {
+        let files = file_manager.lock().unwrap().get_files().clone();
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        thread::spawn(move || {
+            let refreshed = interface::refreshed_details(files);
+            cloned_file_manager.lock().unwrap().apply_details(refreshed);
+            let _ = app_weak.upgrade_in_event_loop(|app| {
+                let service = app.global::<AppService>();
+                service.set_recents_revision(service.get_recents_revision() + 1);
+            });
+        });
+    }
+
+    // Saves the database once changes have been quiet for a moment
+    let schedule_save = interface::save_scheduler(&file_manager, interface::DATABASE_FILE);
+
+    // Files forwarded by later instances open here as if picked from the recents
+    if holds_lock {
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        instance::listen(instance::LOCK_FILE, move |path| {
+            let cloned_file_manager = cloned_file_manager.clone();
+            let _ = app_weak.upgrade_in_event_loop(move |app| {
+                open_document(&app, &cloned_file_manager, path, None);
+            });
+        });
+    }
+
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
     // CALLBACKS USED IN OPENING PAGE:
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -64,15 +323,15 @@ fn main() -> Result<()> {
         let cloned_file_manager = file_manager.clone();
         move || {
             let app = app_weak.unwrap();
-            let mut file_manager = cloned_file_manager.lock().unwrap();
-            if file_manager.add_new_file() {
-                app.set_active_page(1);
+            if let Some(path) = interface::open_pdf_dialog() {
+                open_document(&app, &cloned_file_manager, path, None);
             }
         }
     });
 
     /*  CALLBACK:
-        User selected PDF from recents, then sets the active page to split-page
+        User selected PDF from recents, then sets the active page to split-page. If the PDF is already
+        the open document it is focused rather than reopened
         
         # Arguments
         N/A
@@ -85,11 +344,8 @@ fn main() -> Result<()> {
         let cloned_file_manager = file_manager.clone();
         move |file_path| {
             let app = app_weak.unwrap();
-            let mut file_manager = cloned_file_manager.lock().unwrap();
-            app.set_active_page(1);
             println!("{}", file_path.to_string());
-            file_manager.set_cur_path(file_path.to_string());
-            file_manager.set_cur_file_info(file_path.to_string());
+            open_document(&app, &cloned_file_manager, file_path.to_string(), None);
         }
     });
 
@@ -97,19 +353,29 @@ fn main() -> Result<()> {
         Returns all previously opened PDFs as slint vector for use in opening-page recent pdf buttons
         
         # Arguments
-        N/A
+        * 'sort' - order of the files: Last read, Last added, Name, Size or Manual
+        * 'format' - how the time each was last read, or added when sorted by that, is shown: Relative
+          or Date
+        * 'filter' - which files are listed: All, Unfinished or Finished
+        * 'revision' - unused, changes whenever the list has to be fetched again
 
         # Return
         * A Slint vector type with info for files previously opened
     */
     app.global::<AppService>().on_get_recent_files({
         let cloned_file_manager = file_manager.clone();
-        move || {
+        move |sort, format, filter, _revision| {
             let file_manager = cloned_file_manager.lock().unwrap();
             let mut recent_list = Vec::new();
+            let sort = interface::RecentsSort::from_name(sort.as_str()).unwrap_or_default();
+            let format = timestamps::TimestampFormat::from_name(format.as_str()).unwrap_or_default();
+            let filter = interface::RecentsFilter::from_name(filter.as_str()).unwrap_or_default();
 
-            for a_file in file_manager.get_files().iter() {
-                recent_list.push((a_file.get_name().into(), a_file.get_filepath().into()));
+            for a_file in file_manager.sorted_files(sort) {
+                if !filter.keeps(a_file) {
+                    continue;
+                }
+                recent_list.push(recent_entry(a_file, sort, format));
             }
 
             //let my_vec : Vec<(slint::SharedString, slint::SharedString)> = recent_list.into_iter().map(Into::into).collect();
@@ -119,270 +385,3896 @@ fn main() -> Result<()> {
         }
     });
 
-    /* CALLBACK:
-        Returns the number of previously opened PDFs
+    /*  CALLBACK:
+        Returns the recents grouped by the folder each file is in, for the grouped list. Files whose
+        folder no longer exists are grouped under Unavailable, last
 
         # Arguments
-        N/A
+        * 'sort' - order of the files within each group, and of the groups by their first file
+        * 'format' - how the time each was last read, or added, is shown: Relative or Date
+        * 'filter' - which files are listed: All, Unfinished or Finished
+        * 'revision' - unused, changes whenever the list has to be fetched again
 
         # Return
-        number of files previously opened
+        * A Slint vector type of folder groups, collapsed ones still listing their files
     */
-    app.global::<AppService>().on_get_num_recent_files({
+    app.global::<AppService>().on_get_recent_groups({
         let cloned_file_manager = file_manager.clone();
-        move || {
-            let mut count = 0;
+        let cloned_settings = settings.clone();
+        move |sort, format, filter, _revision| {
             let file_manager = cloned_file_manager.lock().unwrap();
-
-            for _a_file in file_manager.get_files().iter() {
-                count += 1;
-            }
-            return count;
+            let settings = cloned_settings.lock().unwrap();
+            let sort = interface::RecentsSort::from_name(sort.as_str()).unwrap_or_default();
+            let format = timestamps::TimestampFormat::from_name(format.as_str()).unwrap_or_default();
+            let filter = interface::RecentsFilter::from_name(filter.as_str()).unwrap_or_default();
+            let files: Vec<&interface::FileInfo> =
+                file_manager.sorted_files(sort).into_iter().filter(|a_file| filter.keeps(a_file)).collect();
+            let groups: Vec<RecentGroup> = interface::folder_groups(&files)
+                .into_iter()
+                .map(|(folder, group_files)| {
+                    let name = std::path::Path::new(&folder)
+                        .file_name()
+                        .map_or(folder.clone(), |name| name.to_string_lossy().into_owned());
+                    let entries: Vec<RecentFile> =
+                        group_files.iter().map(|a_file| recent_entry(a_file, sort, format)).collect();
+                    RecentGroup {
+                        name: name.into(),
+                        collapsed: settings.is_folder_collapsed(&folder),
+                        folder: folder.into(),
+                        count: entries.len() as i32,
+                        files: slint::ModelRc::new(VecModel::from(entries)),
+                    }
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(groups))
         }
     });
 
     /* CALLBACK:
-        Returns trimmed file name if name exceeds max length
+        Stores whether the list of recents is grouped by folder
 
         # Arguments
-        * 'name' - name of pdf files currently on record
+        * 'grouped' - true to group the list
 
         # Return
-        * a shorted version of the pdf name
+        N/A
     */
-    let max_name_len = 15;
-    app.global::<AppService>().on_trim_file_name(move |name| {
-        if name.len() > max_name_len as usize {
-            let mut new_name: String = name[0..max_name_len - 5].to_string();
-            new_name += "...pdf";
-            return new_name.into();
+    app.global::<AppService>().on_set_recents_grouped(store_setting(
+        &settings,
+        Settings::set_recents_grouped,
+    ));
+
+    /* CALLBACK:
+        Collapses a folder's group of recents, or expands it, kept between sessions
+
+        # Arguments
+        * 'folder' - the group's folder, or Unavailable
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_toggle_recents_group({
+        let cloned_settings = settings.clone();
+        move |folder| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.toggle_folder_collapsed(folder.as_str());
+            settings.save(settings::SETTINGS_FILE);
         }
-        return name.into();
     });
 
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    // CALLBACKS USED IN PDF RENDERING:
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    /* CALLBACK:
+        Returns the names of the orders the recents can be sorted in
 
-    /*  CALLBACK:
-       Renders current page of PDF and returns as Slint image
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of sort names
+    */
+    app.global::<AppService>().on_get_recents_sorts(|| {
+        let names: Vec<slint::SharedString> =
+            interface::RecentsSort::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /* CALLBACK:
+        Stores the order of the recents
+
+        # Arguments
+        * 'sort' - Last read, Last added, Name, Size or Manual
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_recents_sort({
+        let cloned_settings = settings.clone();
+        move |sort| {
+            let Some(sort) = interface::RecentsSort::from_name(sort.as_str()) else {
+                eprintln!("Unknown sort: {}", sort);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_recents_sort(sort);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Returns the names of the ways the time a recent file was last read can be shown
 
         # Arguments
         N/A
 
         # Return
-        * A Slint rgba8 type which is used to display pdf image
+        * A Slint vector type of format names
     */
-    app.global::<BackendPDF>().on_display({
-        let cloned_file_manager = file_manager.clone();
-        move || {
-            let mut file_manager = cloned_file_manager.lock().unwrap();
-            let current_page = file_manager.get_cur_file_info().get_cur_page();
-            let pdfium = Pdfium::default();
-            let file_path = file_manager.get_cur_path().unwrap();
-            let document = pdfium.load_pdf_from_file(file_path.as_str(), None).unwrap();
-            let page = document.pages().get(current_page as u16).unwrap();
-            let render_config = PdfRenderConfig::new()
-                .set_target_width(2000)
-                .set_maximum_height(2000);
-
-            let image = page
-                .render_with_config(&render_config)
-                .unwrap()
-                .as_image()
-                .into_rgba8();
+    app.global::<AppService>().on_get_timestamp_formats(|| {
+        let names: Vec<slint::SharedString> =
+            timestamps::TimestampFormat::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
 
-            let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
-                image.as_raw(),
-                image.width(),
-                image.height(),
-            );
+    /* CALLBACK:
+        Stores how the time a recent file was last read is shown
+
+        # Arguments
+        * 'format' - Relative or Date
 
-            Image::from_rgba8(buffer)
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_timestamp_format({
+        let cloned_settings = settings.clone();
+        move |format| {
+            let Some(format) = timestamps::TimestampFormat::from_name(format.as_str()) else {
+                eprintln!("Unknown timestamp format: {}", format);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_timestamp_format(format);
+            settings.save(settings::SETTINGS_FILE);
         }
     });
 
     /* CALLBACK:
-       Navigates to the previous page in the pdf file
+        Returns the names of the ways the recents can be filtered by whether they were finished
 
         # Arguments
         N/A
 
         # Return
-        N / A
+        * A Slint vector type of filter names
     */
-    app.global::<BackendPDF>().on_navigate_previous({
+    app.global::<AppService>().on_get_recents_filters(|| {
+        let names: Vec<slint::SharedString> =
+            interface::RecentsFilter::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /* CALLBACK:
+        Stores which recents are listed
+
+        # Arguments
+        * 'filter' - All, Unfinished or Finished
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_recents_filter({
+        let cloned_settings = settings.clone();
+        move |filter| {
+            let Some(filter) = interface::RecentsFilter::from_name(filter.as_str()) else {
+                eprintln!("Unknown recents filter: {}", filter);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_recents_filter(filter);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Marks a document finished, or unmarks it
+
+        # Arguments
+        * 'path' - path of the open document or a recent file
+
+        # Return
+        * Whether the document is now finished
+    */
+    app.global::<AppService>().on_toggle_finished({
+        let app_weak = app.as_weak();
         let cloned_file_manager = file_manager.clone();
-        move || {
+        move |path| {
             let mut file_manager = cloned_file_manager.lock().unwrap();
-            let num = file_manager.get_cur_file_info().get_cur_page();
-            if num > 0 {
-                file_manager.get_cur_file_info().set_cur_page(num - 1);
+            match file_manager.toggle_finished(path.as_str()) {
+                Some(finished) => {
+                    file_manager.save(interface::DATABASE_FILE);
+                    let change = file_manager.last_recents_change().unwrap_or_default();
+                    app_weak.unwrap().global::<AppService>().set_recents_toast(change.into());
+                    finished
+                }
+                None => {
+                    eprintln!("Not in the recents: {}", path);
+                    false
+                }
             }
         }
     });
 
-    /*  CALLBACK:
-       Navigates to the next page in the pdf file
-       
+    /* CALLBACK:
+        Moves a recent file to where another one is in the manual order, after it was dragged there.
+        The move can be undone from the toast under the recents
+
+        # Arguments
+        * 'from' - path of the dragged recent file
+        * 'to' - path of the recent file it was dropped on
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_reorder_recents({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move |from, to| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            if !file_manager.reorder(from.as_str(), to.as_str()) {
+                return;
+            }
+            file_manager.save(interface::DATABASE_FILE);
+            let app = app_weak.unwrap();
+            let service = app.global::<AppService>();
+            service.set_recents_toast(file_manager.last_recents_change().unwrap_or_default().into());
+            service.set_recents_revision(service.get_recents_revision() + 1);
+        }
+    });
+
+    /* CALLBACK:
+        Takes back the last change to the recents, shown in the toast under them. Only the last change
+        can be undone and nothing is kept between sessions
+
         # Arguments
         N/A
 
         # Return
-        N / A
+        N/A
     */
-    app.global::<BackendPDF>().on_navigate_next({
+    app.global::<AppService>().on_undo_recents_change({
+        let app_weak = app.as_weak();
         let cloned_file_manager = file_manager.clone();
         move || {
+            let app = app_weak.unwrap();
             let mut file_manager = cloned_file_manager.lock().unwrap();
-            let pdfium = Pdfium::default();
-            let file_path = file_manager.get_cur_path().unwrap();
-            let document = pdfium.load_pdf_from_file(file_path.as_str(), None).unwrap();
-            let num = file_manager.get_cur_file_info().get_cur_page();
-            if num + 1 < document.pages().len().into() {
-                file_manager.get_cur_file_info().set_cur_page(num + 1);
+            if file_manager.undo_recents_change().is_some() {
+                file_manager.save(interface::DATABASE_FILE);
             }
+            let service = app.global::<AppService>();
+            service.set_recents_toast("".into());
+            service.set_recents_revision(service.get_recents_revision() + 1);
         }
     });
 
+    /* CALLBACK:
+        Empties the recents, after the user confirmed unless they said not to ask again. The open
+        document stays open and the clear can be undone from the toast under the recents
+
+        # Arguments
+        N/A
 
-    app.global::<BackendPDF>().on_get_page({
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_clear_recents({
+        let app_weak = app.as_weak();
         let cloned_file_manager = file_manager.clone();
         move || {
             let mut file_manager = cloned_file_manager.lock().unwrap();
-            let pdfium = Pdfium::default();
-            let file_path = file_manager.get_cur_path().unwrap();
-            let document = pdfium.load_pdf_from_file(file_path.as_str(), None).unwrap();
-            let cur = file_manager.get_cur_file_info().get_cur_page();
-            let total: u16 = document.pages().len().into();
-            format!("{} of {}", cur, total).into()
+            if file_manager.clear_recents() == 0 {
+                return;
+            }
+            file_manager.save(interface::DATABASE_FILE);
+            let app = app_weak.unwrap();
+            let service = app.global::<AppService>();
+            service.set_recents_toast(file_manager.last_recents_change().unwrap_or_default().into());
+            service.set_recents_revision(service.get_recents_revision() + 1);
         }
     });
 
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    // CALLBACKS USED IN TEXT EDITOR:
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
-
-    /*  CALLBACK:
-        Prompt user to select txt file and returns path as String
+    /* CALLBACK:
+        Stores whether a document is marked finished when its last page is shown
 
         # Arguments
+        * 'auto_finish' - true to mark it automatically
+
+        # Return
         N/A
+    */
+    app.global::<AppService>().on_set_auto_finish(store_setting(&settings, Settings::set_auto_finish));
+
+    /* CALLBACK:
+        Stores whether a recent file opens on double click, a single click then only selects it
+
+        # Arguments
+        * 'double_click' - true to open on double click, false to open on a single click
 
         # Return
-        N / A
+        N/A
     */
-    app.global::<BackendTextEditor>()
-        .on_open_text_file(|| txt_file::open_file_txt().into());
+    app.global::<AppService>().on_set_open_on_double_click(store_setting(
+        &settings,
+        Settings::set_open_on_double_click,
+    ));
 
-    /*  CALLBACK:
-        Saves text to specified file path (file_name)
+    /* CALLBACK:
+        Stores whether the recents are shown as a compact list instead of a grid of covers
 
         # Arguments
-        * 'file_name' - file path of txt file
-        * 'text' - data to be stored in txt file
+        * 'as_list' - true for the list layout
 
         # Return
-        N / A
+        N/A
     */
-    app.global::<BackendTextEditor>().on_save_file(
-        |file_name, text| match txt_file::write_to_file(file_name.as_str(), text.as_str()) {
-            Ok(_) => println!("File Saved"),
-            Err(e) => eprintln!("Error saving file: {}", e),
-        },
-    );
+    app.global::<AppService>().on_set_recents_as_list(store_setting(
+        &settings,
+        Settings::set_recents_as_list,
+    ));
 
-    /*  CALLBACK:
-        Returns text at path (file_name) as String
+    /* CALLBACK:
+        Returns the first page of a recent PDF as a cover for the grid layout, cached on disk
 
         # Arguments
-        * 'file_name' - file path of txt file
+        * 'file_path' - path of a previously opened PDF
 
         # Return
-        starting text to be displayed on slint text editor
+        * A Slint image, empty if the PDF can't be read
     */
-    app.global::<BackendTextEditor>().on_read_file(|file_name| {
-        if file_name == "err".to_string() {
-            eprintln!("Error opening text file");
-            return "".to_string().into();
-        }
-        let mut text = "".to_string();
-        match txt_file::read_file(file_name.as_str()) {
-            Ok(txt) => text = txt,
-            Err(e) => eprintln!("Error loading file: {}", e),
+    app.global::<AppService>().on_get_cover({
+        let cloned_file_manager = file_manager.clone();
+        move |file_path| {
+            let file_manager = cloned_file_manager.lock().unwrap();
+            let modified = match file_manager.find_file(file_path.as_str()) {
+                Some(index) => file_manager.get_files()[index].get_modified(),
+                None => 0,
+            };
+            thumbnails::cover(file_path.as_str(), modified).unwrap_or_default()
         }
-        return text.to_string().into();
     });
 
-    /*  CALLBACK:
-        Returns new_size as i32 if new_size is a number between 1 & 256
-        
+    // Text recognized in scanned pages, shared with the search and OCR workers
+    let ocr_cache = Arc::new(Mutex::new(ocr::OcrCache::load(ocr::OCR_FILE)));
+
+    search::bind(&app, &file_manager, &ocr_cache);
+
+    /* CALLBACK:
+        Returns the previously opened PDFs whose names fuzzy match the query, best match first
+
         # Arguments
-        * 'new_size' - size of display font user desires
-        * 'old_font' - previously displayed font
+        * 'query' - text typed into the quick open palette
 
         # Return
-        return new font size to slint text editor
+        * A Slint vector type with info for the matching files
     */
-    app.global::<BackendTextEditor>()
-        .on_set_font_size(|new_size, old_font| {
-            let mut numeric = true;
-            let mut font: i32 = 0;
-            for ch in new_size.chars() {
-                font = font * 10;
-                if !ch.is_numeric() {
-                    numeric = false;
-                    break;
-                } else {
-                    font += ch.to_digit(10).unwrap() as i32;
-                }
-            }
-            if !numeric {
-                font = old_font;
-            }
-            if font > 256 {
-                return 256;
-            }
-            if font <= 0 {
-                return 1;
-            }
-            return font;
-        });
+    app.global::<AppService>().on_filter_recent_files({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move |query| {
+            let file_manager = cloned_file_manager.lock().unwrap();
+            let files = file_manager.get_files();
+            let format = cloned_settings.lock().unwrap().get_timestamp_format();
+            let names: Vec<String> = files.iter().map(|a_file| a_file.get_name()).collect();
 
+            let matches: Vec<RecentFile> = fuzzy::rank(query.as_str(), &names)
+                .into_iter()
+                .map(|i| RecentFile {
+                    name: files[i].get_name().into(),
+                    path: files[i].get_filepath().into(),
+                    details: files[i].get_details().into(),
+                    last_read: files[i].get_last_read(format).into(),
+                    progress: files[i].get_progress(),
+                    finished: files[i].get_finished(),
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(matches))
+        }
+    });
 
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    // GENERAL APPLICATION CALLBACKS:
-    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    
     /* CALLBACK:
-        Saves local data when application window is closed
+        Returns the number of previously opened PDFs
 
         # Arguments
-        N / A
+        N/A
 
         # Return
-        slint command to close window
+        number of files previously opened
     */
-    app.window().on_close_requested({
+    app.global::<AppService>().on_get_num_recent_files({
         let cloned_file_manager = file_manager.clone();
-        move || {
-            let mut file_manager = cloned_file_manager.lock().unwrap();
-            file_manager.add_file();
-            let files = file_manager.get_files();
-
-            let json = serde_json::to_string(&files).unwrap();
+        move |_revision| {
+            let mut count = 0;
+            let file_manager = cloned_file_manager.lock().unwrap();
 
-            match txt_file::write_to_file("database.json", json.as_str()) {
-                Ok(_) => println!("File Saved"),
-                Err(e) => eprintln!("Error saving file: {}", e),
+            for _a_file in file_manager.get_files().iter() {
+                count += 1;
             }
-
-            println!("the json is {}", json);
-            slint::CloseRequestResponse::HideWindow
+            return count;
         }
     });
 
-    let _ = app.run();
+    /* CALLBACK:
+        Returns trimmed file name if name exceeds max length
 
-    Ok(())
+        # Arguments
+        * 'name' - name of pdf files currently on record
+        * 'hide_extension' - whether the .pdf extension is left out of the displayed name
+
+        # Return
+        * a shorted version of the pdf name
+    */
+    let max_name_len = 15;
+    app.global::<AppService>().on_trim_file_name(move |name, hide_extension| {
+        interface::trim_file_name(name.as_str(), max_name_len, hide_extension).into()
+    });
+
+    /* CALLBACK:
+        Returns the tooltip of a recent file, its full name and path, for entries whose displayed name
+        is shortened or has its extension hidden
+
+        # Arguments
+        * 'name' - untrimmed name of the pdf file
+        * 'path' - full path of the pdf file
+        * 'hide_extension' - whether the .pdf extension is left out of the displayed name
+
+        # Return
+        * the name and path on two lines, empty if the whole name is displayed
+    */
+    app.global::<AppService>().on_recent_tooltip(move |name, path, hide_extension| {
+        if interface::trim_file_name(name.as_str(), max_name_len, hide_extension) == name.as_str() {
+            return "".into();
+        }
+        format!("{}\n{}", name, path).into()
+    });
+
+    /* CALLBACK:
+        Stores whether recent file names are displayed without their extension
+
+        # Arguments
+        * 'hide' - true to hide extensions
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_hide_extensions(store_setting(
+        &settings,
+        Settings::set_hide_extensions,
+    ));
+
+    /* CALLBACK:
+        Returns the names of what PDFer can show on startup
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of startup choices
+    */
+    app.global::<AppService>().on_get_startups(|| {
+        let names: Vec<slint::SharedString> = settings::Startup::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /* CALLBACK:
+        Stores what PDFer shows on startup when no PDF is given on the command line
+
+        # Arguments
+        * 'name' - Opening Page, Last Document or Blank Note
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_startup(store_named_setting(
+        &settings,
+        "startup choice",
+        settings::Startup::from_name,
+        Settings::set_startup,
+    ));
+
+    /* CALLBACK:
+        Stores whether the first launch opens the file dialog while there are no recents
+
+        # Arguments
+        * 'prompt' - true to open the dialog on the first launch
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_first_run_prompt(store_setting(
+        &settings,
+        Settings::set_first_run_prompt,
+    ));
+
+    /* CALLBACK:
+        Stores the interface scale used for fonts and control sizes
+
+        # Arguments
+        * 'scale' - scale factor, 1.0 is the normal size
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_ui_scale(store_setting(&settings, Settings::set_ui_scale));
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    // CALLBACKS USED IN PDF RENDERING:
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    /*  CALLBACK:
+       Renders current page of PDF and returns as Slint image
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint rgba8 type which is used to display pdf image
+    */
+    let crop_cache = Arc::new(Mutex::new(crop::CropCache::default()));
+    // Size of the page pane in physical pixels, reported by the UI as it lays out, and how the last
+    // render compares to the pixel budget. Crop boxes and text boxes are in budget pixels, so they
+    // are scaled by it to match what is shown
+    let pane_size = Rc::new(Cell::new((0.0_f32, 0.0_f32)));
+    let display_scale = Rc::new(Cell::new(1.0_f32));
+    // Part of the page the last render shows, for mapping ruler points and comment pins onto the page
+    let shown_area = Rc::new(Cell::new(measure::ShownArea {
+        left: 0.0,
+        top: 0.0,
+        width: 0.0,
+        height: 0.0,
+        page_width: 0.0,
+        page_height: 0.0,
+    }));
+    // Zoom in percent the last render is shown at, whether set or the result of the page fit
+    let shown_zoom = Rc::new(Cell::new(100.0_f32));
+    // Documents and pages read since startup, for the session report
+    let session = Rc::new(RefCell::new(session::SessionLog::new()));
+    // Why the last render fell back to the placeholder, None if it didn't
+    let render_failure: Rc<RefCell<Option<render::RenderFailure>>> = Rc::new(RefCell::new(None));
+    // Renders of recently shown pages and of those rendered ahead when a document opens. A new
+    // document bumps the warmup generation, which stops the warmup of the previous one
+    let page_cache = Arc::new(Mutex::new(page_cache::PageCache::new(settings.lock().unwrap().get_page_cache_megabytes())));
+    let warmup_generation = Arc::new(AtomicUsize::new(0));
+    let viewed_page = Arc::new(AtomicUsize::new(0));
+    let warmed_document = Rc::new(RefCell::new(String::new()));
+    // Document and page of the last render, to tell a page turn from a rerender of the same page
+    let rendered_page: Rc<RefCell<Option<(String, u32)>>> = Rc::new(RefCell::new(None));
+    // Why the last render is shown at less than its zoom or fit asked for, empty if it isn't
+    let render_notice = Rc::new(RefCell::new(String::new()));
+    app.global::<BackendPDF>().on_display({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let cloned_crop_cache = crop_cache.clone();
+        let pane_size = pane_size.clone();
+        let display_scale = display_scale.clone();
+        let render_failure = render_failure.clone();
+        let render_notice = render_notice.clone();
+        let page_cache = page_cache.clone();
+        let warmup_generation = warmup_generation.clone();
+        let viewed_page = viewed_page.clone();
+        let warmed_document = warmed_document.clone();
+        let rendered_page = rendered_page.clone();
+        let session = session.clone();
+        let shown_zoom = shown_zoom.clone();
+        let shown_area = shown_area.clone();
+        let app_weak = app.as_weak();
+        move || {
+            let previous_failure = render_failure.borrow_mut().take();
+            render_notice.borrow_mut().clear();
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            // a warmup running ahead checks this to stop, so it's set before waiting for pdfium
+            viewed_page.store(file_manager.get_cur_file_info().get_cur_page() as usize, Ordering::SeqCst);
+            let pdfium = Pdfium::default();
+            let Ok(file_path) = file_manager.get_cur_path() else {
+                return slint::Image::default();
+            };
+            // the file can vanish or its share drop after opening, and a page can be corrupt. Either
+            // way the placeholder is shown with the reason instead of panicking
+            let placeholder = || {
+                let (width, height) = render::render_target(612.0, 792.0, render::MAX_RENDER_PIXELS);
+                render::to_slint_image(&render::error_placeholder(width as u32, height as u32))
+            };
+            let mut document = match pdfium.load_pdf_from_file(file_path.as_str(), None) {
+                Ok(document) => document,
+                Err(e) => {
+                    eprintln!("Error loading {}: {}", file_path, e);
+                    let message = format!("{} couldn't be read", file_manager.get_cur_file_info().get_name());
+                    *render_failure.borrow_mut() = Some(render::RenderFailure::new(message, &e).after(previous_failure.as_ref()));
+                    return placeholder();
+                }
+            };
+            // every render loads the document, which keeps the cached page count current if the file
+            // changed or was repaired differently, and the page within it
+            file_manager.get_cur_file_info().set_page_count(pages::page_count(&document));
+            let current_page = file_manager.get_cur_file_info().get_cur_page();
+            let mut page = match pages::get_page(&document, current_page) {
+                Ok(page) => page,
+                Err(e) => {
+                    eprintln!("Error loading page {}: {}", current_page + 1, e);
+                    let message = format!("Page {} couldn't be loaded", current_page + 1);
+                    *render_failure.borrow_mut() = Some(render::RenderFailure::new(message, &e).after(previous_failure.as_ref()));
+                    return placeholder();
+                }
+            };
+            if file_manager.has_document() {
+                let name = file_manager.get_cur_file_info().get_name();
+                session.borrow_mut().visit(file_path.as_str(), name.as_str(), current_page);
+            }
+            let settings = cloned_settings.lock().unwrap();
+
+            // turning the page saves notes with unsaved edits if the user asked for that. The save
+            // waits until the render is done, which runs inside the image's binding
+            let left_page = rendered_page
+                .borrow_mut()
+                .replace((file_path.clone(), current_page))
+                .filter(|(path, page)| *path == file_path && *page != current_page);
+            if let (Some((_, left_page)), true) = (left_page, settings.get_save_notes_on_page_turn()) {
+                let app_weak = app_weak.clone();
+                let session = session.clone();
+                let path = file_path.clone();
+                let name = file_manager.get_cur_file_info().get_name();
+                slint::Timer::single_shot(Duration::ZERO, move || {
+                    let Some(app) = app_weak.upgrade() else {
+                        return;
+                    };
+                    match save_dirty_notes(&app.global::<BackendTextEditor>()) {
+                        Ok(true) => session.borrow_mut().notes_saved(path.as_str(), name.as_str(), left_page),
+                        Ok(false) => {}
+                        Err(e) => eprintln!("Error saving notes on leaving page {}: {}", left_page + 1, e),
+                    }
+                });
+            }
+
+            let quality = settings.get_render_quality();
+            let annotations = settings.get_render_annotations();
+            let background = settings.get_page_background();
+            let mut image = None;
+
+            // reaching the last page marks the document finished, paging back doesn't unmark it
+            if settings.get_auto_finish() && current_page + 1 >= pages::page_count(&document) {
+                file_manager.get_cur_file_info().set_finished(true);
+            }
+
+            // the document's own margins win over the detected ones
+            let margins = file_manager.get_cur_file_info().get_crop_margins();
+            let crop_box = if margins != [0; 4] {
+                let (width, height) = render::page_render_size(&page);
+                Some(crop::from_margins(width as u32, height as u32, margins))
+            } else if settings.get_auto_crop() {
+                let mut crop_cache = cloned_crop_cache.lock().unwrap();
+                match crop_cache.get(file_path.as_str(), current_page) {
+                    Some(crop_box) => Some(crop_box),
+                    // detected without the page number, which would otherwise count as content. A page
+                    // that doesn't render isn't cropped, it gets the placeholder below
+                    None => match render::render_page(&page, quality, annotations) {
+                        Ok(plain) => {
+                            let crop_box = crop_cache.get_or_detect(
+                                file_path.as_str(),
+                                current_page,
+                                &plain,
+                                settings.get_crop_padding(),
+                            );
+                            // detection needs the page on white, which is only the page shown on white
+                            if background == render::PageBackground::White {
+                                image = Some(plain);
+                            }
+                            Some(crop_box)
+                        }
+                        Err(_) => None,
+                    },
+                }
+            } else {
+                None
+            };
+
+            let page_number = settings.get_page_number_overlay();
+            if page_number.enabled {
+                let text = if page_number.show_total {
+                    format!("{} / {}", current_page + 1, pages::page_count(&document))
+                } else {
+                    (current_page + 1).to_string()
+                };
+                match overlay::draw_page_number(&mut document, &mut page, &text, page_number) {
+                    Ok(_) => image = None,
+                    Err(e) => eprintln!("Error drawing page number: {}", e),
+                }
+            }
+
+            // a zoom sizes the whole page, cropping then cuts from it. Without one, with a fit mode the
+            // shown part of the page, cropped or not, is what fills the pane
+            let (budget_width, budget_height) = render::page_render_size(&page);
+            let scale_factor = app_weak.upgrade().map_or(1.0, |app| app.window().scale_factor());
+            let zoom = render::resolve_zoom(file_manager.get_cur_file_info().get_zoom(), settings.get_default_zoom());
+            let shown = crop_box.unwrap_or(crop::CropBox::full(budget_width as u32, budget_height as u32));
+            let cap = settings.get_render_cap().max(1) as f32 * 1_000_000.0;
+            let max_side = settings.get_render_side() as f32;
+            let (width, height) = render::display_size(
+                page.width().value,
+                page.height().value,
+                zoom,
+                scale_factor,
+                pane_size.get(),
+                settings.get_page_fit(),
+                (shown.width as f32 / budget_width as f32, shown.height as f32 / budget_height as f32),
+                (cap, max_side),
+            );
+            // a fit of a tightly cropped page scales the whole page up past the limit, so the final size
+            // is checked against the cap before anything is allocated for it
+            let (width, height) = match render::clamp_render_size(width, height, cap, max_side) {
+                Some((clamped_width, clamped_height)) => {
+                    eprintln!(
+                        "Render of page {} clamped from {}x{} to {}x{}",
+                        current_page + 1,
+                        width,
+                        height,
+                        clamped_width,
+                        clamped_height
+                    );
+                    *render_notice.borrow_mut() = if width.max(height) as f32 > max_side {
+                        format!(
+                            "Shown at reduced resolution, the page would exceed the {} px render limit",
+                            settings.get_render_side()
+                        )
+                    } else {
+                        format!(
+                            "Shown at reduced resolution, the page would exceed the {} MP render limit",
+                            settings.get_render_cap()
+                        )
+                    };
+                    (clamped_width, clamped_height)
+                }
+                None => (width, height),
+            };
+            shown_zoom.set(render::zoom_of(page.width().value, width, scale_factor));
+            let scale = width as f32 / budget_width as f32;
+            display_scale.set(scale);
+            shown_area.set(measure::ShownArea {
+                left: shown.x as f32 / budget_width as f32 * page.width().value,
+                top: shown.y as f32 / budget_height as f32 * page.height().value,
+                width: shown.width as f32 / budget_width as f32 * page.width().value,
+                height: shown.height as f32 / budget_height as f32 * page.height().value,
+                page_width: page.width().value,
+                page_height: page.height().value,
+            });
+
+            // the render before recoloring and cropping is cached. The page number overlay draws on
+            // the page itself, so those renders aren't
+            let modified = file_manager.get_cur_file_info().get_modified();
+            let cache_key = (!page_number.enabled).then(|| page_cache::PageKey {
+                path: file_path.clone(),
+                modified,
+                page: current_page,
+                width,
+                height,
+                quality,
+                annotations,
+                background,
+            });
+            viewed_page.store(current_page as usize, Ordering::SeqCst);
+            let warmup_pages = settings.get_warmup_pages();
+            // auto crop sizes every page by its own content, so the pages ahead can't be sized yet
+            let predictable = margins != [0; 4] || !settings.get_auto_crop();
+            if cache_key.is_some() && warmup_pages > 0 && predictable && *warmed_document.borrow() != file_path {
+                *warmed_document.borrow_mut() = file_path.clone();
+                let generation = warmup_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                let first = current_page + 1;
+                let end = (first + warmup_pages).min(pages::page_count(&document));
+                let warmup = page_cache::Warmup {
+                    path: file_path.clone(),
+                    modified,
+                    pages: first..end,
+                    quality,
+                    annotations,
+                    background,
+                    zoom,
+                    scale_factor,
+                    pane: pane_size.get(),
+                    fit: settings.get_page_fit(),
+                    shown: (shown.width as f32 / budget_width as f32, shown.height as f32 / budget_height as f32),
+                    cap,
+                    max_side,
+                };
+                let page_cache = page_cache.clone();
+                let warmup_generation = warmup_generation.clone();
+                let viewed_page = viewed_page.clone();
+                thread::spawn(move || {
+                    // a jump past the pages being warmed makes them pointless
+                    page_cache::warm_up(&page_cache, &warmup, || {
+                        warmup_generation.load(Ordering::SeqCst) != generation
+                            || viewed_page.load(Ordering::SeqCst) >= warmup.pages.end as usize
+                    });
+                });
+            }
+            let cached = cache_key.as_ref().and_then(|key| page_cache.lock().unwrap().get(key));
+            let mut image = match cached.or(image) {
+                Some(image) if image.width() == width as u32 => image,
+                _ => match render::render_page_on(&page, quality, annotations, background, width, height) {
+                    Ok(image) => {
+                        if let Some(key) = cache_key {
+                            page_cache.lock().unwrap().insert(key, image.clone());
+                        }
+                        image
+                    }
+                    Err(e) => {
+                        eprintln!("Error rendering page {}: {}", current_page + 1, e);
+                        let message = format!("Page {} couldn't be rendered", current_page + 1);
+                        *render_failure.borrow_mut() = Some(render::RenderFailure::new(message, &e).after(previous_failure.as_ref()));
+                        render::error_placeholder(width as u32, height as u32)
+                    }
+                },
+            };
+            let page_colors = settings.page_colors_for(file_manager.get_cur_file_info().get_page_colors());
+            page_colors.apply(&mut image, settings.get_contrast());
+            match crop_box {
+                Some(crop_box) => {
+                    let crop_box = crop_box.scaled(scale, image.width(), image.height());
+                    render::to_slint_image(&crop::crop_image(&image, crop_box))
+                }
+                None => render::to_slint_image(&image),
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns why the last render showed the placeholder instead of the page
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint RenderError, failed is false if the page rendered
+    */
+    app.global::<BackendPDF>().on_get_render_error({
+        let render_failure = render_failure.clone();
+        move || match render_failure.borrow().as_ref() {
+            Some(failure) => RenderError {
+                failed: true,
+                message: failure.message.clone().into(),
+                transient: failure.transient,
+                offer_external: failure.offers_external(),
+            },
+            None => RenderError {
+                failed: false,
+                message: "".into(),
+                transient: false,
+                offer_external: false,
+            },
+        }
+    });
+
+    /*  CALLBACK:
+        Returns why the last render is shown at a lower resolution than asked for
+
+        # Arguments
+        N/A
+
+        # Return
+        * The notice, empty if the page is shown at full resolution
+    */
+    app.global::<BackendPDF>().on_get_render_notice({
+        let render_notice = render_notice.clone();
+        move || render_notice.borrow().as_str().into()
+    });
+
+    /*  CALLBACK:
+        Opens the current document in the system's default PDF viewer, for documents PDFer can't render
+
+        # Arguments
+        N/A
+
+        # Return
+        * Why it couldn't be opened, empty if the viewer was started
+    */
+    app.global::<BackendPDF>().on_open_externally({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let Ok(file_path) = cloned_file_manager.lock().unwrap().get_cur_path() else {
+                return "No document is open".into();
+            };
+            match platform::open_with_default(file_path.as_str()) {
+                Ok(_) => "".into(),
+                Err(e) => {
+                    eprintln!("Error opening {} externally: {}", file_path, e);
+                    format!("{}. Set a default PDF viewer in the system settings", e).into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the corners the page number can be drawn in
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of corner names
+    */
+    app.global::<BackendPDF>().on_get_page_number_corners(|| {
+        let names: Vec<slint::SharedString> =
+            overlay::Corner::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores how the page number is drawn on the page, the page is re-rendered by the caller
+
+        # Arguments
+        * 'options' - whether it's shown, its corner, size in points, color and whether the total is added
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_number_overlay(store_setting(
+        &settings,
+        |settings, options: PageNumberOptions| {
+            settings.set_page_number_overlay(overlay::PageNumberOverlay {
+                enabled: options.enabled,
+                corner: overlay::Corner::from_name(options.corner.as_str()).unwrap_or_default(),
+                size: options.size as f32,
+                color: options.color.to_string(),
+                show_total: options.show_total,
+            })
+        },
+    ));
+
+    /*  CALLBACK:
+        Returns the current document's manual crop margins
+
+        # Arguments
+        N/A
+
+        # Return
+        * margins as percentages of the page, all 0 when the document isn't cropped by hand
+    */
+    app.global::<BackendPDF>().on_get_crop_margins({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let [left, top, right, bottom] = file_manager.get_cur_file_info().get_crop_margins();
+            CropMargins {
+                left: left as i32,
+                top: top as i32,
+                right: right as i32,
+                bottom: bottom as i32,
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Stores manual crop margins for the current document, the page is re-rendered by the caller
+
+        # Arguments
+        * 'margins' - percentages of the page to cut from each side, all 0 to stop cropping by hand
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_crop_margins({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |margins| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().set_crop_margins(
+                [margins.left, margins.top, margins.right, margins.bottom]
+                    .map(|margin| margin.clamp(0, 45) as u8),
+            );
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Stores whether white margins are cropped automatically and how much of them is kept
+
+        # Arguments
+        * 'enabled' - true to crop to the detected content
+        * 'padding' - pixels of margin kept around the content
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_auto_crop({
+        let cloned_settings = settings.clone();
+        let cloned_crop_cache = crop_cache.clone();
+        move |enabled, padding| {
+            let mut settings = cloned_settings.lock().unwrap();
+            if settings.get_crop_padding() != padding.max(0) as u32 {
+                cloned_crop_cache.lock().unwrap().clear();
+            }
+            settings.set_auto_crop(enabled);
+            settings.set_crop_padding(padding.max(0) as u32);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /*  CALLBACK:
+        Stores how a page narrower than the pane is placed and the space kept around it
+
+        # Arguments
+        * 'centered' - true to center the page horizontally, false to keep it at the left
+        * 'gap' - pixels of space between the page and the pane's edges
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_layout({
+        let cloned_settings = settings.clone();
+        move |centered, gap| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_center_page(centered);
+            settings.set_page_gap(gap.max(0) as u32);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /*  CALLBACK:
+        Stores the largest render in megapixels and shows the page again within it
+
+        # Arguments
+        * 'megapixels' - the cap, at least one megapixel
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_render_cap(store_setting(&settings, |settings, megapixels: i32| {
+        settings.set_render_cap(megapixels.max(1) as u32)
+    }));
+
+    /*  CALLBACK:
+        Stores the longest side a render may have in pixels, kept between MIN_RENDER_SIDE and
+        MAX_RENDER_SIDE
+
+        # Arguments
+        * 'side' - the limit in pixels
+
+        # Return
+        * The limit as stored
+    */
+    app.global::<BackendPDF>().on_set_render_side({
+        let cloned_settings = settings.clone();
+        move |side| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_render_side(side.max(0) as u32);
+            settings.save(settings::SETTINGS_FILE);
+            settings.get_render_side() as i32
+        }
+    });
+
+    /*  CALLBACK:
+        Stores how many pages are rendered ahead when a document opens and how much memory rendered
+        pages may keep, dropping pages from the cache until it fits
+
+        # Arguments
+        * 'warmup' - pages rendered ahead, 0 for none
+        * 'megabytes' - memory budget of the page cache
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_cache({
+        let cloned_settings = settings.clone();
+        let page_cache = page_cache.clone();
+        move |warmup, megabytes| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_warmup_pages(warmup.max(0) as u32);
+            settings.set_page_cache_megabytes(megabytes.max(0) as u32);
+            settings.save(settings::SETTINGS_FILE);
+            page_cache.lock().unwrap().set_budget(megabytes.max(0) as u32);
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the names of the render quality presets
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of preset names
+    */
+    app.global::<BackendPDF>().on_get_render_qualities(|| {
+        let names: Vec<slint::SharedString> =
+            render::RenderQuality::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves the render quality preset, the page is re-rendered by the caller
+
+        # Arguments
+        * 'name' - preset name: Fast, Balanced or Crisp
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_render_quality(store_named_setting(
+        &settings,
+        "render quality",
+        render::RenderQuality::from_name,
+        Settings::set_render_quality,
+    ));
+
+    /*  CALLBACK:
+        Returns the names of the backgrounds transparent parts of the page can be drawn on
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of background names
+    */
+    app.global::<BackendPDF>().on_get_page_backgrounds(|| {
+        let names: Vec<slint::SharedString> =
+            render::PageBackground::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves what transparent parts of the page are drawn on, the page is re-rendered by
+        the caller
+
+        # Arguments
+        * 'name' - background name: White, Theme or Checkerboard
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_background(store_named_setting(
+        &settings,
+        "page background",
+        render::PageBackground::from_name,
+        Settings::set_page_background,
+    ));
+
+    /*  CALLBACK:
+        Returns the names of the colors the page can be shown in
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of page color names
+    */
+    app.global::<BackendPDF>().on_get_page_colors(|| {
+        let names: Vec<slint::SharedString> =
+            render::PageColors::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves the colors the page is shown in and whether highlights follow a grayscale page,
+        the page is re-rendered by the caller. A document with colors of its own changes those, the
+        others the global mode
+
+        # Arguments
+        * 'name' - Normal, Grayscale or High Contrast
+        * 'gray_highlights' - true to show highlights gray too on a grayscale page
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_colors({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move |name, gray_highlights| {
+            let Some(colors) = render::PageColors::from_name(name.as_str()) else {
+                eprintln!("Unknown page colors: {}", name);
+                return;
+            };
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let mut settings = cloned_settings.lock().unwrap();
+            let file = file_manager.get_cur_file_info();
+            if let Some(own) = file.get_page_colors() {
+                file.set_page_colors(settings.change_page_colors(Some(own), colors));
+                schedule_save();
+            } else {
+                settings.change_page_colors(None, colors);
+            }
+            settings.set_gray_highlights(gray_highlights);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the colors the current document is shown in, its own where it has them, otherwise the
+        global mode
+
+        # Arguments
+        N/A
+
+        # Return
+        * The name of the mode
+    */
+    app.global::<BackendPDF>().on_get_shown_page_colors({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move || {
+            let own = cloned_file_manager.lock().unwrap().get_cur_file_info().get_page_colors();
+            cloned_settings.lock().unwrap().page_colors_for(own).name().into()
+        }
+    });
+
+    /*  CALLBACK:
+        Returns whether the current document keeps colors of its own rather than following the global
+        mode
+
+        # Arguments
+        N/A
+
+        # Return
+        * true if it has its own
+    */
+    app.global::<BackendPDF>().on_get_document_colors({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_page_colors().is_some()
+    });
+
+    /*  CALLBACK:
+        Makes the colors now shown the current document's own, kept whatever the global mode, or lets
+        it follow the global mode again. The page is re-rendered by the caller
+
+        # Arguments
+        * 'own' - true to keep the shown colors for this document
+
+        # Return
+        * The name of the mode the document is shown in now
+    */
+    app.global::<BackendPDF>().on_set_document_colors({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move |own| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let settings = cloned_settings.lock().unwrap();
+            let file = file_manager.get_cur_file_info();
+            let shown = settings.page_colors_for(file.get_page_colors());
+            file.set_page_colors(own.then_some(shown));
+            schedule_save();
+            settings.page_colors_for(file.get_page_colors()).name().into()
+        }
+    });
+
+    /*  CALLBACK:
+        Sets and saves how strongly High Contrast pushes the page to black and white, the page is
+        re-rendered by the caller
+
+        # Arguments
+        * 'contrast' - intensity from 1 to 100, 100 leaving only black and white
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_contrast(store_setting(&settings, |settings, contrast: i32| {
+        settings.set_contrast(contrast.max(1) as u32)
+    }));
+
+    /*  CALLBACK:
+        Moves on to the next page colors mode in the cycle and saves it, as the document's own where it
+        has colors of its own. The page is re-rendered by the caller
+
+        # Arguments
+        N/A
+
+        # Return
+        * The name of the mode now in use
+    */
+    app.global::<BackendPDF>().on_cycle_color_mode({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let mut settings = cloned_settings.lock().unwrap();
+            let file = file_manager.get_cur_file_info();
+            let own = file.get_page_colors();
+            let colors = settings.page_colors_for(own).next_in(settings.get_color_cycle());
+            if own.is_some() {
+                file.set_page_colors(settings.change_page_colors(own, colors));
+                schedule_save();
+            } else {
+                settings.change_page_colors(own, colors);
+                settings.save(settings::SETTINGS_FILE);
+            }
+            colors.name().into()
+        }
+    });
+
+    /*  CALLBACK:
+        Returns whether a page colors mode is in the cycle the shortcut steps through
+
+        # Arguments
+        * 'name' - Normal or Grayscale
+
+        # Return
+        * True if the shortcut stops at it
+    */
+    app.global::<BackendPDF>().on_in_color_cycle({
+        let cloned_settings = settings.clone();
+        move |name| {
+            let settings = cloned_settings.lock().unwrap();
+            render::PageColors::from_name(name.as_str()).is_some_and(|colors| settings.get_color_cycle().contains(&colors))
+        }
+    });
+
+    /*  CALLBACK:
+        Adds a page colors mode to the cycle the shortcut steps through or takes it out, and saves it
+
+        # Arguments
+        * 'name' - Normal or Grayscale
+        * 'included' - true to stop at it
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_in_color_cycle({
+        let cloned_settings = settings.clone();
+        move |name, included| {
+            let Some(colors) = render::PageColors::from_name(name.as_str()) else {
+                eprintln!("Unknown page colors: {}", name);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_in_color_cycle(colors, included);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /*  CALLBACK:
+        Sets and saves whether the document's own annotations are drawn on the page, the page is
+        re-rendered by the caller
+
+        # Arguments
+        * 'annotations' - true to draw them
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_render_annotations(store_setting(
+        &settings,
+        Settings::set_render_annotations,
+    ));
+
+    /*  CALLBACK:
+        Returns the page fit modes for the viewer's page fit picker
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of fit mode names
+    */
+    app.global::<BackendPDF>().on_get_page_fits(|| {
+        let names: Vec<slint::SharedString> =
+            render::PageFit::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves how the page is fitted to the pane, the page is re-rendered by the caller
+
+        # Arguments
+        * 'name' - fit mode name: Fixed, Fit page, Fit width or Fit height
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_fit(store_named_setting(
+        &settings,
+        "page fit",
+        render::PageFit::from_name,
+        Settings::set_page_fit,
+    ));
+
+    /*  CALLBACK:
+        Takes the size of the page pane whenever its layout changes. With a fit mode the page is
+        re-rendered for the new size once resizing pauses, keeping the scroll position proportional
+
+        # Arguments
+        * 'width', 'height' - pane size in physical pixels
+
+        # Return
+        * always true, the UI reads it to keep reporting the size
+    */
+    let refit_timer = Rc::new(slint::Timer::default());
+    app.global::<BackendPDF>().on_pane_resized({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let pane_size = pane_size.clone();
+        move |width, height| {
+            if pane_size.get() == (width, height) {
+                return true;
+            }
+            pane_size.set((width, height));
+            // a zoomed page keeps its size whatever the pane
+            let saved_zoom = cloned_file_manager.lock().unwrap().get_cur_file_info().get_zoom();
+            let settings = cloned_settings.lock().unwrap();
+            if settings.get_page_fit() == render::PageFit::Fixed
+                || render::resolve_zoom(saved_zoom, settings.get_default_zoom()).is_some()
+            {
+                return true;
+            }
+            drop(settings);
+            let app_weak = app_weak.clone();
+            refit_timer.start(slint::TimerMode::SingleShot, Duration::from_millis(200), move || {
+                let Some(app) = app_weak.upgrade() else {
+                    return;
+                };
+                let backend = app.global::<BackendPDF>();
+                let before = backend.get_current_image().size();
+                backend.invoke_refresh();
+                let after = backend.get_current_image().size();
+                if before.width > 0 && before.height > 0 {
+                    backend.set_scroll_x(backend.get_scroll_x() * after.width as f32 / before.width as f32);
+                    backend.set_scroll_y(backend.get_scroll_y() * after.height as f32 / before.height as f32);
+                }
+            });
+            true
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the zoom the current page is shown at
+
+        # Arguments
+        N/A
+
+        # Return
+        * The zoom in percent, 0 while the page follows the page fit mode
+    */
+    app.global::<BackendPDF>().on_get_zoom({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move || {
+            let saved = cloned_file_manager.lock().unwrap().get_cur_file_info().get_zoom();
+            let default = cloned_settings.lock().unwrap().get_default_zoom();
+            render::resolve_zoom(saved, default).map_or(0, |zoom| zoom as i32)
+        }
+    });
+
+    /*  CALLBACK:
+        Zooms the current document one level in or out from the zoom it is shown at and saves the zoom
+        with the document, the page is re-rendered by the caller
+
+        # Arguments
+        * 'up' - true to zoom in
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_step_zoom({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        let shown_zoom = shown_zoom.clone();
+        move |up| {
+            let zoom = render::step_zoom(shown_zoom.get(), up);
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_zoom(Some(zoom));
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Toggles the current document between its page width filling the pane and the zoom it had
+        before, for a double tap in touch mode. The zoom is saved with the document, the page is
+        re-rendered by the caller
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    // the document the page width was last fitted in and the zoom it had before
+    let zoom_before_tap = Rc::new(RefCell::new(None::<(String, Option<u32>)>));
+    app.global::<BackendPDF>().on_toggle_tap_zoom({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        let pane_size = pane_size.clone();
+        let shown_area = shown_area.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(path) = file_manager.get_cur_path() else {
+                return;
+            };
+            let file = file_manager.get_cur_file_info();
+            let before = zoom_before_tap.borrow_mut().take();
+            match before {
+                Some((tapped, zoom)) if tapped == path => file.set_zoom(zoom),
+                _ => {
+                    let scale_factor = app_weak.upgrade().map_or(1.0, |app| app.window().scale_factor());
+                    let fit = render::fit_width_zoom(shown_area.get().width, pane_size.get().0, scale_factor);
+                    *zoom_before_tap.borrow_mut() = Some((path, file.get_zoom()));
+                    file.set_zoom(Some(fit));
+                }
+            }
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Stores whether a double tap on the page toggles fitting its width
+
+        # Arguments
+        * 'touch' - true for touch mode
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_touch_mode(store_setting(&settings, Settings::set_touch_mode));
+
+    /*  CALLBACK:
+        Drops the zoom saved with the current document, which goes back to the default zoom or the page
+        fit mode. The page is re-rendered by the caller
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_reset_zoom({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move || {
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_zoom(None);
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the choices for the zoom new documents open at
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of "Fit" and the zoom levels
+    */
+    app.global::<AppService>().on_get_default_zooms(|| {
+        let names: Vec<slint::SharedString> = std::iter::once(None)
+            .chain(render::ZOOM_LEVELS.iter().map(|&zoom| Some(zoom)))
+            .map(|zoom| render::zoom_name(zoom).into())
+            .collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores the zoom documents open at when they have no zoom of their own
+
+        # Arguments
+        * 'name' - "Fit" or a zoom level like "125%"
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_default_zoom({
+        let cloned_settings = settings.clone();
+        move |name| {
+            let Some(zoom) = render::zoom_from_name(name.as_str()) else {
+                eprintln!("Unknown zoom: {}", name);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_default_zoom(zoom);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+       Navigates to the previous page in the pdf file, at the first page what happens depends on the
+       page boundary setting
+
+        # Arguments
+        N/A
+
+        # Return
+        * A notice that the first page was reached, empty if there is none to show
+    */
+    app.global::<BackendPDF>().on_navigate_previous({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move || turn_page(&cloned_file_manager, &cloned_settings, false, &*schedule_save).into()
+    });
+
+    /*  CALLBACK:
+       Navigates to the next page in the pdf file, at the last page what happens depends on the page
+       boundary setting
+
+        # Arguments
+        N/A
+
+        # Return
+        * A notice that the end of the document was reached, empty if there is none to show
+    */
+    app.global::<BackendPDF>().on_navigate_next({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move || turn_page(&cloned_file_manager, &cloned_settings, true, &*schedule_save).into()
+    });
+
+    /*  CALLBACK:
+        Turns to the page on the left or right of the current one, which is the next page or the
+        previous one depending on the document's reading direction
+
+        # Arguments
+        * 'rightward' - true for the page on the right
+
+        # Return
+        * A notice that the first or last page was reached, empty if there is none to show
+    */
+    app.global::<BackendPDF>().on_navigate_side({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move |rightward| {
+            let direction = cloned_file_manager.lock().unwrap().get_cur_file_info().get_reading_direction();
+            turn_page(&cloned_file_manager, &cloned_settings, direction.is_forward(rightward), &*schedule_save).into()
+        }
+    });
+
+    /*  CALLBACK:
+        Jumps to the nearest page after the current one that a bookmark of the document points to.
+        Past the last bookmark what happens depends on the page boundary setting
+
+        # Arguments
+        N/A
+
+        # Return
+        * A notice for the toolbar, empty if there is none to show
+    */
+    app.global::<BackendPDF>().on_next_bookmark({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move || step_bookmark(&cloned_file_manager, &cloned_settings, true, &*schedule_save).into()
+    });
+
+    /*  CALLBACK:
+        Jumps to the nearest page before the current one that a bookmark of the document points to.
+        Before the first bookmark what happens depends on the page boundary setting
+
+        # Arguments
+        N/A
+
+        # Return
+        * A notice for the toolbar, empty if there is none to show
+    */
+    app.global::<BackendPDF>().on_previous_bookmark({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let schedule_save = schedule_save.clone();
+        move || step_bookmark(&cloned_file_manager, &cloned_settings, false, &*schedule_save).into()
+    });
+
+    /*  CALLBACK:
+        Returns the choices of what paging past the first or last page does
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of page boundary names
+    */
+    app.global::<AppService>().on_get_page_boundaries(|| {
+        let names: Vec<slint::SharedString> =
+            pages::PageBoundary::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores what paging past the first or last page does
+
+        # Arguments
+        * 'name' - Stop, Wrap around or Notify
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_page_boundary(store_named_setting(
+        &settings,
+        "page boundary",
+        pages::PageBoundary::from_name,
+        Settings::set_page_boundary,
+    ));
+
+    /*  CALLBACK:
+        Returns where the current page is in the document, both as the page number and as the PDF's own
+        label, for the page indicator
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint PagePosition, its label empty where the PDF has none or it's just the page number
+    */
+    let page_labels = Rc::new(RefCell::new(pages::PageLabels::default()));
+    app.global::<BackendPDF>().on_get_page_position({
+        let cloned_file_manager = file_manager.clone();
+        let page_labels = page_labels.clone();
+        move || {
+            let (cur, total, file_path, modified) = {
+                let mut file_manager = cloned_file_manager.lock().unwrap();
+                let file_path = file_manager.get_cur_path().unwrap_or_default();
+                let file_info = file_manager.get_cur_file_info();
+                (file_info.get_cur_page(), file_info.get_page_count(), file_path, file_info.get_modified())
+            };
+            let mut page_labels = page_labels.borrow_mut();
+            let label = page_labels
+                .get(file_path.as_str(), modified)
+                .and_then(|labels| pages::distinct_label(labels, cur));
+            PagePosition {
+                index: cur as i32,
+                count: total as i32,
+                label: label.unwrap_or_default().into(),
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Jumps to the page the user typed, either the document's page label ("xii", "A-3") or the
+        page number
+
+        # Arguments
+        * 'input' - page label or number starting at 1
+
+        # Return
+        * Empty once on the page, otherwise a notice for the toolbar saying why not
+    */
+    app.global::<BackendPDF>().on_go_to_page({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        let page_labels = page_labels.clone();
+        move |input| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let modified = file_manager.get_cur_file_info().get_modified();
+            let mut page_labels = page_labels.borrow_mut();
+            let Some(labels) = page_labels.get(file_path.as_str(), modified) else {
+                return "The document couldn't be read".into();
+            };
+            match pages::resolve_page(labels, input.as_str()) {
+                Some(index) => {
+                    file_manager.get_cur_file_info().set_cur_page(index);
+                    schedule_save();
+                    "".into()
+                }
+                None => format!("There is no page {}", input.trim()).into(),
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the writing systems a document's text can be extracted for
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of script names, Auto first
+    */
+    app.global::<BackendPDF>().on_get_text_scripts(|| {
+        let names: Vec<slint::SharedString> =
+            text::TextScript::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Returns the script hint of the current document
+
+        # Arguments
+        N/A
+
+        # Return
+        * The script name, Auto unless one was picked
+    */
+    app.global::<BackendPDF>().on_get_text_script({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_text_script().name().into()
+    });
+
+    /*  CALLBACK:
+        Stores the script hint of the current document, kept with it in the recents. Its search index
+        entry is rebuilt with the new hint on the next search
+
+        # Arguments
+        * 'name' - Auto, Latin, CJK or Right to left
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_text_script({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |name| {
+            let Some(script) = text::TextScript::from_name(name.as_str()) else {
+                eprintln!("Unknown script: {}", name);
+                return;
+            };
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_text_script(script);
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the reading directions for the reading direction picker
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of direction names, left to right first
+    */
+    app.global::<BackendPDF>().on_get_reading_directions(|| {
+        let names: Vec<slint::SharedString> =
+            pages::ReadingDirection::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Returns which way the pages of the current document run
+
+        # Arguments
+        N/A
+
+        # Return
+        * The direction name, Left to right unless Right to left was picked
+    */
+    app.global::<BackendPDF>().on_get_reading_direction({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().get_reading_direction().name().into()
+        }
+    });
+
+    /*  CALLBACK:
+        Stores which way the pages of the current document run, kept with it in the recents
+
+        # Arguments
+        * 'name' - Left to right or Right to left
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_reading_direction({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |name| {
+            let Some(direction) = pages::ReadingDirection::from_name(name.as_str()) else {
+                eprintln!("Unknown reading direction: {}", name);
+                return;
+            };
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_reading_direction(direction);
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Returns whether the current document is read-only
+
+        # Arguments
+        N/A
+
+        # Return
+        * true if its highlights can't be edited and exports may not replace existing files
+    */
+    app.global::<BackendPDF>().on_get_read_only({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_read_only()
+    });
+
+    /*  CALLBACK:
+        Returns whether the current document looks like a scan without a text layer
+
+        # Arguments
+        N/A
+
+        # Return
+        * true if its pages have little or no extractable text, so reading aloud and searching it can't work
+    */
+    app.global::<BackendPDF>().on_get_scanned({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_scanned()
+    });
+
+    /*  CALLBACK:
+        Returns whether the current document has text the text features can use: a text layer, or pages
+        OCR recognized. Whether it's a scan is detected once per document and kept with it
+
+        # Arguments
+        N/A
+
+        # Return
+        * true if searching, reading aloud and the text layer can work on it
+    */
+    app.global::<BackendPDF>().on_is_searchable({
+        let cloned_file_manager = file_manager.clone();
+        let ocr_cache = ocr_cache.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(path) = file_manager.get_cur_path() else {
+                return false;
+            };
+            let file = file_manager.get_cur_file_info();
+            !file.classify_scanned() || ocr_cache.lock().unwrap().recognized_pages(&path, file.get_modified()) > 0
+        }
+    });
+
+    ocr::bind(&app, &file_manager, &ocr_cache);
+
+    /*  CALLBACK:
+        Makes the current document read-only or editable again, kept with it in the recents
+
+        # Arguments
+        * 'read_only' - true to protect it
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_read_only({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |read_only| {
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_read_only(read_only);
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the ways the page indicator can number the page
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of indicator names
+    */
+    app.global::<BackendPDF>().on_get_page_indicators(|| {
+        let names: Vec<slint::SharedString> = pages::PageIndicator::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Returns the page indicator's text for a position, the page number where it has no label
+
+        # Arguments
+        * 'name' - Number, Label or Both
+        * 'position' - the position get-page-position returned
+
+        # Return
+        * The text, e.g. "xii (3 of 250)"
+    */
+    app.global::<BackendPDF>().on_format_page_position(|name, position| {
+        let indicator = pages::PageIndicator::from_name(name.as_str()).unwrap_or_default();
+        indicator
+            .text(position.index.max(0) as u32, position.count.max(0) as u32, position.label.as_str())
+            .into()
+    });
+
+    /*  CALLBACK:
+        Stores whether the page indicator shows the page number, the document's page label or both
+
+        # Arguments
+        * 'name' - Number, Label or Both
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_page_indicator(store_named_setting(
+        &settings,
+        "page indicator",
+        pages::PageIndicator::from_name,
+        Settings::set_page_indicator,
+    ));
+
+    /*  CALLBACK:
+        Returns the number of pages in the current PDF
+
+        # Arguments
+        N/A
+
+        # Return
+        * page count as of the last load of the PDF, 0 if it couldn't be opened
+    */
+    app.global::<BackendPDF>().on_get_page_count({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().get_page_count() as i32
+        }
+    });
+
+    /*  CALLBACK:
+        Prints a page range of the current PDF. The range is exported to a temporary PDF and sent to the
+        printer, or opened in the system viewer to print from there if direct printing isn't available
+
+        # Arguments
+        * 'first' - first page to print, starting at 1
+        * 'last' - last page to print
+        * 'copies' - number of copies
+
+        # Return
+        * status message to show in the print panel
+    */
+    app.global::<BackendPDF>().on_print({
+        let cloned_file_manager = file_manager.clone();
+        move |first, last, copies| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let page_count = file_manager.get_cur_file_info().get_page_count() as i32;
+            if page_count == 0 {
+                return "Could not open the PDF to print".into();
+            }
+            let (first, last) = (first.min(last).clamp(1, page_count), first.max(last).clamp(1, page_count));
+
+            let name = file_manager.get_cur_file_info().get_name();
+            let temp_path = env::temp_dir().join(format!("pdfer-print-{}", name));
+            let temp_path = temp_path.to_str().unwrap();
+            if interface::same_file(temp_path, file_path.as_str()) {
+                return "Can't print a PDF opened from PDFer's own print copy".into();
+            }
+            let failed = match export::export_page_range(file_path.as_str(), (first - 1) as u32, (last - 1) as u32, temp_path) {
+                Ok(failed) => failed,
+                Err(e) => return format!("Error preparing pages to print: {}", e).into(),
+            };
+            // broken pages print as a note instead, say which
+            let skipped = match failed.is_empty() {
+                true => String::new(),
+                false => format!(", except unreadable page{} {}", if failed.len() == 1 { "" } else { "s" },
+                    failed.iter().map(|page| (page + 1).to_string()).collect::<Vec<_>>().join(", ")),
+            };
+
+            match platform::print_file(temp_path, copies.max(1) as u32) {
+                Ok(_) => format!("Sent pages {}-{} to the printer{}", first, last, skipped).into(),
+                Err(e) => {
+                    eprintln!("Error printing: {}", e);
+                    match platform::open_with_default(temp_path) {
+                        Ok(_) => "Opened the pages in the system viewer to print".into(),
+                        Err(e) => format!("Printing failed: {}", e).into(),
+                    }
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the index of the current page
+
+        # Arguments
+        N/A
+
+        # Return
+        * current page, starting at 0
+    */
+    app.global::<BackendPDF>().on_get_current_page({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().get_cur_page() as i32
+        }
+    });
+
+    thumbnails::bind(&app, &settings, &file_manager);
+
+    /*  CALLBACK:
+        Copies the page as it is shown, or a region of it, to the clipboard as an image
+
+        # Arguments
+        * 'page' - the displayed page image
+        * 'x0', 'y0', 'x1', 'y1' - corners of the region in pixels of that image, the whole page if
+          the region is empty
+
+        # Return
+        * What was copied, or why nothing could be
+    */
+    app.global::<BackendPDF>().on_copy_page_image(|page, x0, y0, x1, y1| {
+        let Some(buffer) = page.to_rgba8() else {
+            return "There is no page to copy".into();
+        };
+        let Some(image) = image::RgbaImage::from_raw(buffer.width(), buffer.height(), buffer.as_bytes().to_vec()) else {
+            return "There is no page to copy".into();
+        };
+        let left = x0.min(x1).clamp(0.0, image.width() as f32) as u32;
+        let top = y0.min(y1).clamp(0.0, image.height() as f32) as u32;
+        let right = x0.max(x1).clamp(0.0, image.width() as f32) as u32;
+        let bottom = y0.max(y1).clamp(0.0, image.height() as f32) as u32;
+        let (copied, what) = if right > left && bottom > top {
+            let region = image::imageops::crop_imm(&image, left, top, right - left, bottom - top).to_image();
+            (clipboard::copy_image(&region), "Selection")
+        } else {
+            (clipboard::copy_image(&image), "Page")
+        };
+        match copied {
+            Ok(_) => format!("{} copied as an image", what).into(),
+            Err(e) => {
+                eprintln!("{}", e);
+                e.into()
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Saves a region of the current page as a PNG, rendered afresh at the selection resolution rather
+        than cut from the page as it's shown, for grabbing a figure or table. The region is mapped
+        through the crop and zoom to the page, and clamped to it
+
+        # Arguments
+        * 'page' - the displayed page image
+        * 'x0', 'y0', 'x1', 'y1' - corners of the region in pixels of that image
+
+        # Return
+        * Where the image was saved, or why it wasn't, empty if the user cancelled
+    */
+    app.global::<BackendPDF>().on_export_selection({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let shown_area = shown_area.clone();
+        move |page, x0, y0, x1, y1| {
+            let (shown_width, shown_height) = (page.size().width.max(1) as f32, page.size().height.max(1) as f32);
+            let area = shown_area.get();
+            let from = area.page_fraction(x0 / shown_width, y0 / shown_height);
+            let to = area.page_fraction(x1 / shown_width, y1 / shown_height);
+            let (quality, annotations, dpi, cap) = {
+                let settings = cloned_settings.lock().unwrap();
+                let cap = settings.get_render_cap().max(1) as f32 * 1_000_000.0;
+                (settings.get_render_quality(), settings.get_render_annotations(), settings.get_selection_dpi(), cap)
+            };
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(file_path) = file_manager.get_cur_path() else {
+                return "There is no page to export".into();
+            };
+            let current_page = file_manager.get_cur_file_info().get_cur_page();
+            let name = file_manager.get_cur_file_info().get_name();
+            drop(file_manager);
+            let pdfium = Pdfium::default();
+            let rendered = pdfium
+                .load_pdf_from_file(file_path.as_str(), None)
+                .and_then(|document| {
+                    let page = pages::get_page(&document, current_page)?;
+                    render::render_region(&page, quality, annotations, (from, to), dpi, cap)
+                });
+            let region = match rendered {
+                Ok(Some(region)) => region,
+                Ok(None) => return "The selection is outside the page".into(),
+                Err(e) => {
+                    eprintln!("Error rendering the selection: {}", e);
+                    return format!("Couldn't render the selection: {}", e).into();
+                }
+            };
+            let stem = std::path::Path::new(&name).file_stem().and_then(|stem| stem.to_str()).unwrap_or("Document").to_string();
+            let suggested = format!("{} page {} selection.png", stem, current_page + 1);
+            let Some(output) = interface::save_image_dialog(suggested.as_str()) else {
+                return "".into();
+            };
+            match region.save_with_format(&output, image::ImageFormat::Png) {
+                Ok(_) => format!("Saved {}×{} selection to {}", region.width(), region.height(), output).into(),
+                Err(e) => {
+                    eprintln!("Error saving selection: {}", e);
+                    format!("Couldn't save the selection: {}", e).into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Stores the resolution a selection is saved as an image at
+
+        # Arguments
+        * 'dpi' - dots per inch, at least 72
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_selection_dpi(store_setting(&settings, |settings, dpi: i32| {
+        settings.set_selection_dpi(dpi.max(72) as u32)
+    }));
+
+    citation::bind(&app, &settings, &file_manager);
+
+    measure::bind(&app, &file_manager, &shown_area, &schedule_save);
+
+    toc::bind(&app, &settings, &file_manager);
+
+    /*  CALLBACK:
+        Jumps to a page by its index, unlike go-to-page the input isn't matched against page labels
+
+        # Arguments
+        * 'page_index' - 0-based page to show
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_jump_to_page({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.get_cur_file_info().set_cur_page(page_index.max(0) as u32);
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the width and height of a page of the open document in PDF points, independent of the
+        render scale. Sizes are cached per page
+
+        # Arguments
+        * 'page_index' - page to measure, starting at 0
+
+        # Return
+        * A Slint PageDimensions, zeros when there's no document or no such page
+    */
+    let page_sizes = Rc::new(RefCell::new(pages::PageSizes::default()));
+    app.global::<BackendPDF>().on_get_page_dimensions({
+        let cloned_file_manager = file_manager.clone();
+        let page_sizes = page_sizes.clone();
+        move |page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(file_path) = file_manager.get_cur_path() else {
+                return PageDimensions { width: 0.0, height: 0.0 };
+            };
+            if page_index < 0 {
+                return PageDimensions { width: 0.0, height: 0.0 };
+            }
+            let modified = file_manager.get_cur_file_info().get_modified();
+            let pdfium = Pdfium::default();
+            let (width, height) = page_sizes.borrow_mut().get(&pdfium, file_path.as_str(), modified, page_index as u32);
+            PageDimensions { width, height }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns every character of a page with its box in rendered image pixels, used for the
+        selectable text layer over the page
+
+        # Arguments
+        * 'page_index' - page to read, starting at 0
+
+        # Return
+        * A Slint vector type of character boxes
+    */
+    app.global::<BackendPDF>().on_get_text_boxes({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let cloned_crop_cache = crop_cache.clone();
+        let display_scale = display_scale.clone();
+        move |page_index| {
+            let scale = display_scale.get();
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let pdfium = Pdfium::default();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let margins = file_manager.get_cur_file_info().get_crop_margins();
+            let page_highlights = file_manager.get_cur_file_info().get_highlights().clone();
+            let gray_highlights = {
+                let settings = cloned_settings.lock().unwrap();
+                let page_colors = settings.page_colors_for(file_manager.get_cur_file_info().get_page_colors());
+                page_colors == render::PageColors::Grayscale && settings.get_gray_highlights()
+            };
+            let mut boxes = Vec::new();
+            if let Ok(document) = pdfium.load_pdf_from_file(file_path.as_str(), None) {
+                if let Ok(page) = pages::get_page(&document, page_index.max(0) as u32) {
+                    // boxes are shifted by the crop display applied, and dropped if it cut them off
+                    let (width, height) = render::page_render_size(&page);
+                    let crop_box = if margins != [0; 4] {
+                        crop::from_margins(width as u32, height as u32, margins)
+                    } else if cloned_settings.lock().unwrap().get_auto_crop() {
+                        cloned_crop_cache
+                            .lock()
+                            .unwrap()
+                            .get(file_path.as_str(), page_index.max(0) as u32)
+                            .unwrap_or(crop::CropBox::full(width as u32, height as u32))
+                    } else {
+                        crop::CropBox::full(width as u32, height as u32)
+                    };
+                    // indexed before filtering, highlights refer to the page's characters as pdfium lists them
+                    boxes = text::text_boxes(&page)
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(_, text_box)| {
+                            crop_box.contains(
+                                text_box.x + text_box.width / 2.0,
+                                text_box.y + text_box.height / 2.0,
+                            )
+                        })
+                        .map(|(index, text_box)| {
+                            let highlight = highlights::overlay_color(
+                                highlights::color_at(&page_highlights, page_index.max(0) as u32, index as u32),
+                                gray_highlights,
+                            );
+                            TextBox {
+                                char: text_box.text.into(),
+                                index: index as i32,
+                                x: (text_box.x - crop_box.x as f32) * scale,
+                                y: (text_box.y - crop_box.y as f32) * scale,
+                                w: text_box.width * scale,
+                                h: text_box.height * scale,
+                                highlight,
+                            }
+                        })
+                        .collect();
+                }
+            }
+            slint::ModelRc::new(VecModel::from(boxes))
+        }
+    });
+
+    highlights::bind(&app, &settings, &file_manager, &session, &schedule_save);
+
+    pins::bind(&app, &file_manager, &shown_area, &schedule_save);
+
+    links::bind(&app, &settings, &file_manager, &shown_area);
+
+    // Images of the page the images panel last listed, at their own resolution, for saving one
+    let page_images: Rc<RefCell<Vec<image::RgbaImage>>> = Rc::new(RefCell::new(Vec::new()));
+
+    /*  CALLBACK:
+        Lists the images embedded in a page of the current document with a small preview of each
+
+        # Arguments
+        * 'page_index' - 0-based page to take the images from
+
+        # Return
+        * A Slint vector type of the page's images, empty if it has none or can't be read
+    */
+    app.global::<BackendPDF>().on_extract_images({
+        let cloned_file_manager = file_manager.clone();
+        let page_images = page_images.clone();
+        move |page_index| {
+            let Ok(path) = cloned_file_manager.lock().unwrap().get_cur_path() else {
+                return slint::ModelRc::default();
+            };
+            let images = images::page_images(path.as_str(), page_index.max(0) as u32).unwrap_or_else(|e| {
+                eprintln!("Error reading images of page {}: {}", page_index + 1, e);
+                Vec::new()
+            });
+            let entries: Vec<PageImage> = images
+                .iter()
+                .enumerate()
+                .map(|(index, image)| PageImage {
+                    index: index as i32,
+                    preview: render::to_slint_image(&images::preview(image)),
+                    width: image.width() as i32,
+                    height: image.height() as i32,
+                })
+                .collect();
+            *page_images.borrow_mut() = images;
+            slint::ModelRc::new(VecModel::from(entries))
+        }
+    });
+
+    /*  CALLBACK:
+        Saves one of the images extract-images listed as a PNG at its own resolution, asking where
+
+        # Arguments
+        * 'index' - the image, as extract-images numbered it
+        * 'page_index' - 0-based page it's on, for the suggested file name
+
+        # Return
+        * A status message for the images panel, empty if the user cancelled
+    */
+    app.global::<BackendPDF>().on_save_image({
+        let cloned_file_manager = file_manager.clone();
+        let page_images = page_images.clone();
+        move |index, page_index| {
+            let images = page_images.borrow();
+            let Some(image) = usize::try_from(index).ok().and_then(|index| images.get(index)) else {
+                return "That image is no longer listed, open the page again".into();
+            };
+            let name = cloned_file_manager.lock().unwrap().get_cur_file_info().get_name();
+            let stem = std::path::Path::new(&name).file_stem().and_then(|stem| stem.to_str()).unwrap_or("Document").to_string();
+            let suggested = format!("{} page {} image {}.png", stem, page_index + 1, index + 1);
+            let Some(output) = interface::save_image_dialog(suggested.as_str()) else {
+                return "".into();
+            };
+            match image.save_with_format(&output, image::ImageFormat::Png) {
+                Ok(_) => format!("Saved {}×{} image to {}", image.width(), image.height(), output).into(),
+                Err(e) => {
+                    eprintln!("Error saving image: {}", e);
+                    format!("Couldn't save the image: {}", e).into()
+                }
+            }
+        }
+    });
+
+    speech::bind(&app, &settings, &file_manager, &ocr_cache, &schedule_save);
+
+    /*  CALLBACK:
+        Returns the text of a page for accessibility tools
+
+        # Arguments
+        * 'page_index' - page to read, starting at 0
+
+        # Return
+        * text of the page, its OCR text for a scanned page that was recognized
+    */
+    app.global::<BackendPDF>().on_get_page_text({
+        let cloned_file_manager = file_manager.clone();
+        let ocr_cache = ocr_cache.clone();
+        move |page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let script = file_manager.get_cur_file_info().get_text_script();
+            let modified = file_manager.get_cur_file_info().get_modified();
+            let recognized = ocr_cache
+                .lock()
+                .unwrap()
+                .page_text(&file_path, modified, page_index.max(0) as u32)
+                .map(str::to_string);
+            let page_text = pages::with_document(file_path.as_str(), |document| {
+                pages::get_page(document, page_index.max(0) as u32).map(|page| text::page_text(&page, script))
+            });
+            let page_text = page_text.and_then(|page_text| page_text).unwrap_or_default();
+            match recognized {
+                Some(recognized) if page_text.trim().is_empty() => text::normalize(&recognized, script).into(),
+                _ => page_text.into(),
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the fillable text fields and checkboxes of the current PDF
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type with the form fields and their current values
+    */
+    app.global::<BackendPDF>().on_get_form_fields({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let fields = match forms::list_fields(file_path.as_str()) {
+                Ok(fields) => fields,
+                Err(e) => {
+                    eprintln!("Error reading form fields: {}", e);
+                    Vec::new()
+                }
+            };
+            let entries: Vec<FormFieldEntry> = fields
+                .into_iter()
+                .map(|field| FormFieldEntry {
+                    page: field.page as i32,
+                    annotation: field.annotation as i32,
+                    name: field.name.into(),
+                    is_checkbox: field.is_checkbox,
+                    value: field.value.into(),
+                    checked: field.checked,
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(entries))
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the comments the current PDF carries in its own annotations, for the comments panel
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type with each comment, its page and author
+    */
+    app.global::<BackendPDF>().on_get_annotations({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let comments = match annotations::list_comments(file_path.as_str()) {
+                Ok(comments) => comments,
+                Err(e) => {
+                    eprintln!("Error reading annotations: {}", e);
+                    Vec::new()
+                }
+            };
+            let entries: Vec<DocumentComment> = comments
+                .into_iter()
+                .map(|comment| DocumentComment {
+                    page: comment.page as i32,
+                    kind: comment.kind.into(),
+                    author: comment.author.into(),
+                    text: comment.text.into(),
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(entries))
+        }
+    });
+
+    /*  CALLBACK:
+        Saves a copy of the current PDF with the filled in form values, asking the user where
+
+        # Arguments
+        * 'entries' - form fields with the values entered by the user
+
+        # Return
+        * status message to show in the form panel
+    */
+    app.global::<BackendPDF>().on_save_filled_form({
+        let cloned_file_manager = file_manager.clone();
+        move |entries| {
+            if entries.row_count() == 0 {
+                return "Nothing to save".into();
+            }
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let source = std::path::Path::new(&file_path);
+            let stem = source.file_stem().and_then(|stem| stem.to_str()).unwrap_or("form");
+            let suggested = source.with_file_name(format!("{}-filled.pdf", stem));
+            let Some(output) = interface::save_pdf_dialog(&suggested) else {
+                return "".into();
+            };
+            if let Some(reason) = interface::refuse_export(output.as_str(), file_manager.get_cur_file_info()) {
+                return reason.into();
+            }
+
+            let fields: Vec<forms::FormField> = entries
+                .iter()
+                .map(|entry| forms::FormField {
+                    page: entry.page as u32,
+                    annotation: entry.annotation as usize,
+                    name: entry.name.to_string(),
+                    is_checkbox: entry.is_checkbox,
+                    value: entry.value.to_string(),
+                    checked: entry.checked,
+                })
+                .collect();
+            match forms::save_filled(file_path.as_str(), &fields, output.as_str()) {
+                Ok(_) => format!("Saved to {}", output).into(),
+                Err(e) => format!("Error saving form: {}", e).into(),
+            }
+        }
+    });
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    // CALLBACKS USED IN TEXT EDITOR:
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    /*  CALLBACK:
+        Prompt user to select a notes file, text or Markdown, and returns path as String
+
+        # Arguments
+        N/A
+
+        # Return
+        * the path, "err" if the user cancelled
+    */
+    app.global::<BackendTextEditor>().on_open_text_file({
+        let cloned_settings = settings.clone();
+        move || {
+            let format = cloned_settings.lock().unwrap().get_notes_format();
+            txt_file::open_file_txt(format).into()
+        }
+    });
+
+    /*  CALLBACK:
+        Asks where to save notes that have no file yet, suggesting the open document's name with the
+        extension of the notes format
+
+        # Arguments
+        N/A
+
+        # Return
+        * the path, empty if the user cancelled
+    */
+    app.global::<BackendTextEditor>().on_new_notes_file({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move || {
+            let format = cloned_settings.lock().unwrap().get_notes_format();
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let stem = if file_manager.has_document() {
+                let name = file_manager.get_cur_file_info().get_name();
+                std::path::Path::new(&name)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map_or("notes".to_string(), |stem| stem.to_string())
+            } else {
+                "notes".to_string()
+            };
+            let suggested = format!("{}.{}", stem, format.extension());
+            txt_file::save_notes_dialog(&suggested, format).unwrap_or_default().into()
+        }
+    });
+
+    /*  CALLBACK:
+        Tells whether notes are Markdown, by the file's extension, or by the notes format while they
+        have no file yet
+
+        # Arguments
+        * 'file_name' - path of the notes file, may be empty
+
+        # Return
+        * true for Markdown notes
+    */
+    app.global::<BackendTextEditor>().on_is_markdown({
+        let cloned_settings = settings.clone();
+        move |file_name| {
+            let format = if file_name.is_empty() || file_name == "err" {
+                cloned_settings.lock().unwrap().get_notes_format()
+            } else {
+                settings::NotesFormat::of_path(file_name.as_str())
+            };
+            format == settings::NotesFormat::Markdown
+        }
+    });
+
+    /*  CALLBACK:
+        Saves text to specified file path (file_name)
+
+        # Arguments
+        * 'file_name' - file path of txt file
+        * 'text' - data to be stored in txt file
+
+        # Return
+        * Empty once written, otherwise why it failed
+    */
+    app.global::<BackendTextEditor>().on_save_file(
+        |file_name, text| match txt_file::write_to_file(file_name.as_str(), text.as_str()) {
+            Ok(_) => {
+                println!("File Saved");
+                "".into()
+            }
+            Err(e) => {
+                eprintln!("Error saving file: {}", e);
+                format!("The notes couldn't be saved: {}", e).into()
+            }
+        },
+    );
+
+    /*  CALLBACK:
+        Writes a Markdown report of this session, asking the user where: the documents read, the
+        pages covered and time spent in each, the highlights added, and the notes
+
+        # Arguments
+        * 'notes' - the notes buffer, copied to the end of the report
+
+        # Return
+        * A status message for the notes toolbar, empty if the user cancelled
+    */
+    app.global::<BackendTextEditor>().on_export_session_report({
+        let session = session.clone();
+        move |notes| {
+            let suggested = format!("Reading session {}.md", chrono::Local::now().format("%Y-%m-%d"));
+            let Some(path) = interface::save_report_dialog(suggested.as_str()) else {
+                return "".into();
+            };
+            let report = session.borrow().report(notes.as_str());
+            match txt_file::write_to_file(path.as_str(), report.as_str()) {
+                Ok(_) => format!("Session report saved to {}", path).into(),
+                Err(e) => {
+                    eprintln!("Error saving session report: {}", e);
+                    format!("Couldn't save the session report: {}", e).into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Writes the open document's highlights to a Markdown or text file the user picks, the text
+        under each grouped by page in reading order, as a summary to review
+
+        # Arguments
+        N/A
+
+        # Return
+        * A status message for the notes toolbar, empty if the user cancelled
+    */
+    app.global::<BackendTextEditor>().on_export_highlights({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let (path, name, highlights, pins) = {
+                let mut file_manager = cloned_file_manager.lock().unwrap();
+                let Ok(path) = file_manager.get_cur_path() else {
+                    return "Open a document to export its highlights".into();
+                };
+                let file = file_manager.get_cur_file_info();
+                (path, file.get_name(), file.get_highlights().clone(), file.get_pins().clone())
+            };
+            let stem = std::path::Path::new(&name).file_stem().and_then(|stem| stem.to_str()).unwrap_or("Document").to_string();
+            let Some(output) = interface::save_report_dialog(format!("{} highlights.md", stem).as_str()) else {
+                return "".into();
+            };
+            let summary = match export::highlights_summary(path.as_str(), name.as_str(), &highlights, &pins) {
+                Ok(summary) => summary,
+                Err(e) => {
+                    eprintln!("Error reading highlights of {}: {}", path, e);
+                    return format!("Couldn't read the document's text: {}", e).into();
+                }
+            };
+            match txt_file::write_to_file(output.as_str(), summary.as_str()) {
+                Ok(_) => format!("{} highlights and {} comments saved to {}", highlights.len(), pins.len(), output).into(),
+                Err(e) => {
+                    eprintln!("Error saving highlights: {}", e);
+                    format!("Couldn't save the highlights: {}", e).into()
+                }
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the scratchpad's text, the note that belongs to no document
+
+        # Arguments
+        N/A
+
+        # Return
+        * The saved text, empty if nothing was written yet
+    */
+    app.global::<BackendTextEditor>().on_read_scratch(|| {
+        txt_file::read_file(txt_file::SCRATCH_FILE).unwrap_or_default().into()
+    });
+
+    /*  CALLBACK:
+        Saves the scratchpad's text for the next session
+
+        # Arguments
+        * 'text' - the whole scratchpad
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_save_scratch(|text| {
+        if let Err(e) = txt_file::write_to_file(txt_file::SCRATCH_FILE, text.as_str()) {
+            eprintln!("Error saving scratchpad: {}", e);
+        }
+    });
+
+    /*  CALLBACK:
+        Saves the scratchpad once typing has paused for a moment. Every call restarts the wait, so it
+        isn't written on each keystroke. Closing the scratchpad or the window saves it straight away
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    let scratch_timer = Rc::new(slint::Timer::default());
+    app.global::<BackendTextEditor>().on_scratch_edited({
+        let app_weak = app.as_weak();
+        move || {
+            let app_weak = app_weak.clone();
+            scratch_timer.start(slint::TimerMode::SingleShot, Duration::from_millis(1000), move || {
+                if let Some(app) = app_weak.upgrade() {
+                    let editor = app.global::<BackendTextEditor>();
+                    editor.invoke_save_scratch(editor.get_scratch_text());
+                }
+            });
+        }
+    });
+
+    /*  CALLBACK:
+        Returns text at path (file_name) as String
+
+        # Arguments
+        * 'file_name' - file path of txt file
+
+        # Return
+        starting text to be displayed on slint text editor
+    */
+    app.global::<BackendTextEditor>().on_read_file(|file_name| {
+        if file_name == "err".to_string() {
+            eprintln!("Error opening text file");
+            return "".to_string().into();
+        }
+        let mut text = "".to_string();
+        match txt_file::read_text_file(file_name.as_str()) {
+            Ok(txt) => text = txt,
+            Err(e) => eprintln!("Error loading file: {}", e),
+        }
+        return text.to_string().into();
+    });
+
+    /*  CALLBACK:
+        Checks the file the user picked for the notes can be opened as text, the editor refuses a
+        binary file rather than showing garbage, and saving over it later
+
+        # Arguments
+        * 'file_name' - file path of txt file
+
+        # Return
+        why the file can't be opened, empty if it can or no file was picked
+    */
+    app.global::<BackendTextEditor>().on_text_file_problem(|file_name| {
+        if file_name == "err" || file_name.is_empty() {
+            return "".into();
+        }
+        match txt_file::read_text_file(file_name.as_str()) {
+            Ok(_) => "".into(),
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => txt_file::NOT_TEXT.into(),
+            Err(e) => format!("Couldn't open {}: {}", file_name, e).into(),
+        }
+    });
+
+    /*  CALLBACK:
+        Returns new_size as i32 if new_size is a number between 1 & 256, and keeps it as the size the
+        notes open with, for all notes or the open document depending on the font scope
+        
+        # Arguments
+        * 'new_size' - size of display font user desires
+        * 'old_font' - previously displayed font
+
+        # Return
+        return new font size to slint text editor
+    */
+    app.global::<BackendTextEditor>().on_set_font_size({
+        let cloned_settings = settings.clone();
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |new_size, old_font| {
+            let font = parse_font_size(new_size.as_str(), old_font);
+            save_font_size(&cloned_settings, &cloned_file_manager, font, &*schedule_save);
+            font
+        }
+    });
+
+    /*  CALLBACK:
+        Makes the notes text larger by the font step, or by 10 with shift on the button, within the same
+        1 to 256 as a typed size
+
+        # Arguments
+        * 'size' - current font size
+        * 'large' - true to step by 10
+
+        # Return
+        * The new font size
+    */
+    app.global::<BackendTextEditor>().on_increment_font({
+        let cloned_settings = settings.clone();
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |size, large| {
+            let step = if large { 10 } else { cloned_settings.lock().unwrap().get_font_step() };
+            let font = step_font_size(size, step as i32, true);
+            save_font_size(&cloned_settings, &cloned_file_manager, font, &*schedule_save);
+            font
+        }
+    });
+
+    /*  CALLBACK:
+        Makes the notes text smaller by the font step, or by 10 with shift on the button, within the same
+        1 to 256 as a typed size
+
+        # Arguments
+        * 'size' - current font size
+        * 'large' - true to step by 10
+
+        # Return
+        * The new font size
+    */
+    app.global::<BackendTextEditor>().on_decrement_font({
+        let cloned_settings = settings.clone();
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |size, large| {
+            let step = if large { 10 } else { cloned_settings.lock().unwrap().get_font_step() };
+            let font = step_font_size(size, step as i32, false);
+            save_font_size(&cloned_settings, &cloned_file_manager, font, &*schedule_save);
+            font
+        }
+    });
+
+    /*  CALLBACK:
+        Stores how much the font size buttons and shortcuts step the notes text by
+
+        # Arguments
+        * 'step' - points per step, at least 1
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_set_font_step(store_setting(&settings, |settings, step: i32| {
+        settings.set_font_step(step.max(1) as u32)
+    }));
+
+    /*  CALLBACK:
+        Returns the notes font size for the open document
+
+        # Arguments
+        N/A
+
+        # Return
+        * The document's own size while sizes are kept per document and it has one, the global size otherwise
+    */
+    app.global::<BackendTextEditor>().on_get_notes_font_size({
+        let cloned_settings = settings.clone();
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            notes_font_size(&cloned_settings.lock().unwrap(), &mut file_manager)
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the choices of where the notes font size is kept
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of font scope names
+    */
+    app.global::<BackendTextEditor>().on_get_font_scopes(|| {
+        let names: Vec<slint::SharedString> =
+            settings::FontScope::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores whether the notes font size is kept for all notes or per document. Documents keep the
+        sizes they were given either way, and use them again when switched back to per document
+
+        # Arguments
+        * 'name' - Global or Per document
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_set_font_scope(store_named_setting(
+        &settings,
+        "font scope",
+        settings::FontScope::from_name,
+        Settings::set_font_scope,
+    ));
+
+    /*  CALLBACK:
+        Returns the file types new notes can be saved as
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of notes format names
+    */
+    app.global::<BackendTextEditor>().on_get_notes_formats(|| {
+        let names: Vec<slint::SharedString> =
+            settings::NotesFormat::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores the file type new notes are saved as. Notes already saved keep their file
+
+        # Arguments
+        * 'name' - Text or Markdown
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_set_notes_format(store_named_setting(
+        &settings,
+        "notes format",
+        settings::NotesFormat::from_name,
+        Settings::set_notes_format,
+    ));
+
+    /*  CALLBACK:
+        Returns the app themes for the theme picker
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of theme names, Light first
+    */
+    app.global::<AppService>().on_get_themes(|| {
+        let names: Vec<slint::SharedString> = settings::Theme::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Stores the app theme, the UI switches to it as soon as the palette's theme is set
+
+        # Arguments
+        * 'name' - Light, Dark or System
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_set_theme(store_named_setting(
+        &settings,
+        "theme",
+        settings::Theme::from_name,
+        Settings::set_theme,
+    ));
+
+    /*  CALLBACK:
+        Converts Markdown notes into styled blocks for the preview
+
+        # Arguments
+        * 'text' - Markdown source
+
+        # Return
+        * A Slint vector type of preview blocks
+    */
+    app.global::<BackendTextEditor>().on_render_markdown(|text| {
+        let blocks: Vec<MarkdownBlock> = markdown::render(text.as_str())
+            .into_iter()
+            .map(|block| MarkdownBlock {
+                kind: block.kind.into(),
+                text: block.text.into(),
+                level: block.level,
+                bold: block.bold,
+                italic: block.italic,
+                link: block.link.into(),
+            })
+            .collect();
+        slint::ModelRc::new(VecModel::from(blocks))
+    });
+
+    // When the last key press, scroll, page turn or notes edit happened, and whether the idle actions
+    // already ran since. A window minimized for being idle is refreshed once it's shown again
+    let last_activity = Rc::new(Cell::new(std::time::Instant::now()));
+    let idle_handled = Rc::new(Cell::new(false));
+    let idle_minimized = Rc::new(Cell::new(false));
+    let note_activity: Rc<dyn Fn()> = Rc::new({
+        let last_activity = last_activity.clone();
+        let idle_handled = idle_handled.clone();
+        move || {
+            last_activity.set(std::time::Instant::now());
+            idle_handled.set(false);
+        }
+    });
+
+    /*  CALLBACK:
+        Re-renders the Markdown preview once the user pauses typing, so it isn't rebuilt on every key
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    let preview_timer = Rc::new(slint::Timer::default());
+    app.global::<BackendTextEditor>().on_notes_edited({
+        let app_weak = app.as_weak();
+        let note_activity = note_activity.clone();
+        move || {
+            note_activity();
+            let app_weak = app_weak.clone();
+            preview_timer.start(slint::TimerMode::SingleShot, Duration::from_millis(300), move || {
+                if let Some(app) = app_weak.upgrade() {
+                    app.global::<BackendTextEditor>().invoke_refresh_preview();
+                }
+            });
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the note of a page while notes are per page
+
+        # Arguments
+        * 'path' - the document the page is in
+        * 'page_index' - 0-based page
+
+        # Return
+        * The page's note, empty if it has none or the document isn't in the recents
+    */
+    app.global::<BackendTextEditor>().on_read_page_note({
+        let cloned_file_manager = file_manager.clone();
+        move |path, page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager
+                .file_mut(path.as_str())
+                .map_or(String::new(), |file| file.get_page_note(page_index.max(0) as u32))
+                .into()
+        }
+    });
+
+    /*  CALLBACK:
+        Keeps the note of a page in database.json while notes are per page
+
+        # Arguments
+        * 'path' - the document the page is in
+        * 'page_index' - 0-based page
+        * 'text' - the note, empty to remove it
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_save_page_note({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |path, page_index, text| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Some(file) = file_manager.file_mut(path.as_str()) else {
+                eprintln!("Note of page {} not kept, {} is no longer in the recents", page_index + 1, path);
+                return;
+            };
+            file.set_page_note(page_index.max(0) as u32, text.as_str());
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Tells whether any page of a document has a note, so notes brought over from the notes file
+        never overwrite ones kept per page
+
+        # Arguments
+        * 'path' - the document
+
+        # Return
+        * true if a page has a note
+    */
+    app.global::<BackendTextEditor>().on_has_page_notes({
+        let cloned_file_manager = file_manager.clone();
+        move |path| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            file_manager.file_mut(path.as_str()).is_some_and(|file| file.has_page_notes())
+        }
+    });
+
+    /*  CALLBACK:
+        Stores whether the editor shows a note of each page instead of the notes file
+
+        # Arguments
+        * 'page_notes' - true for notes per page
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_set_page_notes(store_setting(&settings, Settings::set_page_notes));
+
+    /*  CALLBACK:
+        Opens a link or image from the Markdown preview with the default application
+
+        # Arguments
+        * 'url' - link target
+
+        # Return
+        N/A
+    */
+    app.global::<BackendTextEditor>().on_open_link({
+        let app_weak = app.as_weak();
+        move |url| {
+            let app = app_weak.unwrap();
+            // relative links, like pasted images, are relative to the notes file
+            let notes_path = app.global::<BackendTextEditor>().get_txt_file_path();
+            let mut target = url.to_string();
+            if !url.contains("://") && std::path::Path::new(url.as_str()).is_relative() {
+                if let Some(dir) = std::path::Path::new(notes_path.as_str()).parent() {
+                    target = dir.join(url.as_str()).display().to_string();
+                }
+            }
+            if let Err(e) = platform::open_with_default(target.as_str()) {
+                eprintln!("Error opening link: {}", e);
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Saves the clipboard image as a PNG next to the notes file (or in the working directory when the
+        notes haven't been saved yet) and returns a Markdown link to it
+
+        # Arguments
+        * 'notes_path' - path of the notes file, may be empty
+
+        # Return
+        * the Markdown image link to insert, empty if there was no image
+    */
+    app.global::<BackendTextEditor>().on_paste_image(|notes_path| {
+        let notes_dir = match std::path::Path::new(notes_path.as_str()).parent() {
+            Some(dir) if !notes_path.is_empty() && notes_path != "err" => dir.to_path_buf(),
+            _ => std::path::PathBuf::from("."),
+        };
+        match clipboard::save_clipboard_image(&notes_dir) {
+            Ok(path) => {
+                // relative to the notes so the link survives moving the folder
+                let link = path.strip_prefix(&notes_dir).unwrap_or(&path);
+                format!("\n![]({})\n", link.display()).into()
+            }
+            Err(e) => {
+                eprintln!("Error pasting image: {}", e);
+                "".into()
+            }
+        }
+    });
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    // CALLBACKS USED FOR KEYBOARD SHORTCUTS:
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    keybindings::bind(&app, &settings);
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    // GENERAL APPLICATION CALLBACKS:
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    
+    // Snapshots the session every half minute so a crash loses at most that much of the notes
+    let recovery_timer = slint::Timer::default();
+    recovery_timer.start(slint::TimerMode::Repeated, Duration::from_secs(30), {
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            if !holds_lock {
+                return;
+            }
+            let Some(app) = app_weak.upgrade() else {
+                return;
+            };
+            let snapshot = session_snapshot(&app, &mut cloned_file_manager.lock().unwrap());
+            recovery::write(recovery::RECOVERY_FILE, &snapshot);
+        }
+    });
+
+    /* CALLBACK:
+        Restores the session found in a leftover recovery snapshot: its document and page, and the
+        notes buffer, which is marked unsaved
+
+        # Arguments
+        N / A
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_restore_session({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let app = app_weak.unwrap();
+            app.global::<AppService>().set_recovery_shown(false);
+            let Some(snapshot) = leftover_snapshot.clone() else {
+                return;
+            };
+            // the page view may already be showing, opening renders the restored page explicitly
+            if !snapshot.document.is_empty() {
+                open_document(&app, &cloned_file_manager, snapshot.document.clone(), Some(snapshot.page));
+            }
+            // with notes per page the notes file comes back set aside, as it was snapshotted
+            let editor = app.global::<BackendTextEditor>();
+            if editor.get_page_notes() {
+                editor.set_file_notes_path(snapshot.notes_path.into());
+                editor.set_file_notes_text(snapshot.notes.into());
+                editor.set_file_notes_dirty(true);
+            } else {
+                editor.set_txt_file_path(snapshot.notes_path.into());
+                editor.set_notes_text(snapshot.notes.into());
+                editor.set_dirty(true);
+                editor.invoke_refresh_preview();
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Drops a leftover recovery snapshot the user didn't want back
+
+        # Arguments
+        N / A
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_discard_session({
+        let app_weak = app.as_weak();
+        move || {
+            app_weak.unwrap().global::<AppService>().set_recovery_shown(false);
+            recovery::clear(recovery::RECOVERY_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Saves local data when application window is closed. With unsaved notes and the confirmation
+        enabled the window stays open and asks to save, discard or cancel instead
+
+        # Arguments
+        N / A
+
+        # Return
+        slint command to close window, or to keep it shown while the prompt is up
+    */
+    app.window().on_close_requested({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let app = app_weak.unwrap();
+            app.global::<BackendTextEditor>().invoke_store_page_note();
+            let editor = app.global::<BackendTextEditor>();
+            if editor.get_scratch_shown() {
+                editor.invoke_save_scratch(editor.get_scratch_text());
+            }
+            // final flush, in case a debounced save is still pending. If it fails the window stays
+            // until the recents are written somewhere or the user discards them
+            if let Err(e) = cloned_file_manager.lock().unwrap().try_save(interface::DATABASE_FILE) {
+                eprintln!("Error saving file: {}", e);
+                app.global::<AppService>()
+                    .set_save_failure(format!("Your reading progress couldn't be saved: {}", e).into());
+                return slint::CloseRequestResponse::KeepWindowShown;
+            }
+            let unsaved = app.global::<BackendTextEditor>().invoke_has_unsaved_notes();
+            if app.global::<AppService>().get_confirm_exit() && unsaved {
+                app.global::<BackendTextEditor>().set_save_error("".into());
+                app.global::<AppService>().set_exit_prompt_shown(true);
+                return slint::CloseRequestResponse::KeepWindowShown;
+            }
+            // notes closed unsaved without asking are left in a recovery snapshot for next time, taken now
+            // so the typing since the last timed one isn't lost
+            if holds_lock && unsaved {
+                let snapshot = session_snapshot(&app, &mut cloned_file_manager.lock().unwrap());
+                recovery::write(recovery::RECOVERY_FILE, &snapshot);
+            } else if holds_lock {
+                recovery::clear(recovery::RECOVERY_FILE);
+            }
+            slint::CloseRequestResponse::HideWindow
+        }
+    });
+
+    /* CALLBACK:
+        Writes the recents again after the save on close failed, to database.json or to a file the
+        user picks
+
+        # Arguments
+        * 'elsewhere' - true to ask for another location instead of retrying database.json
+
+        # Return
+        * Empty once written, otherwise why it failed. Cancelling the file dialog returns the
+          failure that is already shown
+    */
+    app.global::<AppService>().on_retry_save({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move |elsewhere| {
+            let app = app_weak.unwrap();
+            let target = if elsewhere {
+                match interface::save_database_dialog() {
+                    Some(path) => path,
+                    None => return app.global::<AppService>().get_save_failure(),
+                }
+            } else {
+                interface::DATABASE_FILE.to_string()
+            };
+            match cloned_file_manager.lock().unwrap().try_save(target.as_str()) {
+                Ok(_) => "".into(),
+                Err(e) => {
+                    eprintln!("Error saving {}: {}", target, e);
+                    format!("Saving to {} failed too: {}", target, e).into()
+                }
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Closes the window from the exit prompt, once the notes were saved or discarded
+
+        # Arguments
+        N / A
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_quit({
+        let app_weak = app.as_weak();
+        move || {
+            let app = app_weak.unwrap();
+            app.global::<AppService>().set_exit_prompt_shown(false);
+            if holds_lock {
+                recovery::clear(recovery::RECOVERY_FILE);
+            }
+            if let Err(e) = app.hide() {
+                eprintln!("Error closing window: {}", e);
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Stores whether a PDF opened from the file manager goes to the running PDFer instead of a new window
+
+        # Arguments
+        * 'forward' - true to keep to a single window
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_open_in_running_instance(store_setting(
+        &settings,
+        Settings::set_open_in_running_instance,
+    ));
+
+    /* CALLBACK:
+        Turns distraction-free reading on or off: the window goes fullscreen with only the page showing.
+        The mode is kept for the next session
+
+        # Arguments
+        * 'on' - true to hide everything but the page
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_distraction_free({
+        let app_weak = app.as_weak();
+        let cloned_settings = settings.clone();
+        move |on| {
+            app_weak.unwrap().window().set_fullscreen(on);
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_distraction_free(on);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Stores whether the window stays above other apps, the window follows the property itself
+
+        # Arguments
+        * 'on' - true to keep it on top
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_always_on_top(store_setting(&settings, Settings::set_always_on_top));
+
+    /* CALLBACK:
+        Notes the pointer moving over the PDF toolbar, or the strip above the page that brings it back.
+        The toolbar shows, and hides again once the hide delay passed without the pointer on it. Does
+        nothing while auto-hide is off
+
+        # Arguments
+        N / A
+
+        # Return
+        N / A
+    */
+    let toolbar_timer = Rc::new(slint::Timer::default());
+    app.global::<AppService>().on_toolbar_activity({
+        let app_weak = app.as_weak();
+        let cloned_settings = settings.clone();
+        move || {
+            let Some(app) = app_weak.upgrade() else {
+                return;
+            };
+            let service = app.global::<AppService>();
+            if !service.get_toolbar_autohide() {
+                toolbar_timer.stop();
+                return;
+            }
+            service.set_toolbar_hidden(false);
+            let delay = cloned_settings.lock().unwrap().get_toolbar_hide_delay();
+            let app_weak = app_weak.clone();
+            toolbar_timer.start(slint::TimerMode::SingleShot, Duration::from_secs(delay as u64), move || {
+                if let Some(app) = app_weak.upgrade() {
+                    app.global::<AppService>().set_toolbar_hidden(true);
+                }
+            });
+        }
+    });
+
+    /* CALLBACK:
+        Records input, which restarts the idle period
+
+        # Arguments
+        N / A
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_user_active({
+        let note_activity = note_activity.clone();
+        move || note_activity()
+    });
+
+    /* CALLBACK:
+        Records scrolling of the page as input, bound to the scroll position so it runs on every move
+
+        # Arguments
+        * 'x', 'y' - the scroll position, unused
+
+        # Return
+        * Always true
+    */
+    app.global::<AppService>().on_scroll_activity({
+        let note_activity = note_activity.clone();
+        move |_x, _y| {
+            note_activity();
+            true
+        }
+    });
+
+    // Checks for the idle period passing, saving and minimizing once per idle spell as set
+    let idle_timer = slint::Timer::default();
+    idle_timer.start(slint::TimerMode::Repeated, Duration::from_secs(5), {
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let note_activity = note_activity.clone();
+        move || {
+            let Some(app) = app_weak.upgrade() else {
+                return;
+            };
+            if idle_minimized.get() && !app.window().is_minimized() {
+                idle_minimized.set(false);
+                note_activity();
+                app.global::<BackendPDF>().invoke_refresh();
+                return;
+            }
+            let (minutes, save, minimize) = {
+                let settings = cloned_settings.lock().unwrap();
+                (settings.get_idle_minutes(), settings.get_idle_save(), settings.get_idle_minimize())
+            };
+            if idle_handled.get() || (!save && !minimize) || last_activity.get().elapsed() < Duration::from_secs(minutes as u64 * 60) {
+                return;
+            }
+            idle_handled.set(true);
+            if save {
+                app.global::<BackendTextEditor>().invoke_store_page_note();
+                cloned_file_manager.lock().unwrap().save(interface::DATABASE_FILE);
+                if let Err(e) = save_dirty_notes(&app.global::<BackendTextEditor>()) {
+                    eprintln!("Error saving notes while idle: {}", e);
+                }
+            }
+            if minimize {
+                app.window().set_minimized(true);
+                idle_minimized.set(true);
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Stores what happens once PDFer has been idle for a while
+
+        # Arguments
+        * 'minutes' - minutes without input that count as idle
+        * 'save' - true to save the notes and recents
+        * 'minimize' - true to minimize the window
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_idle({
+        let cloned_settings = settings.clone();
+        move |minutes, save, minimize| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_idle_minutes(minutes.max(1) as u32);
+            settings.set_idle_save(save);
+            settings.set_idle_minimize(minimize);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Stores whether turning the page saves notes with unsaved edits
+
+        # Arguments
+        * 'save' - true to save the notes on each page turn
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_save_notes_on_page_turn(store_setting(
+        &settings,
+        Settings::set_save_notes_on_page_turn,
+    ));
+
+    /* CALLBACK:
+        Stores whether the PDF toolbar hides itself and when
+
+        # Arguments
+        * 'on' - true to hide the toolbar while it isn't used
+        * 'delay' - seconds without the pointer on the toolbar before it hides
+        * 'distance' - pixels from the top edge within which the pointer brings it back
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_toolbar_autohide({
+        let cloned_settings = settings.clone();
+        move |on, delay, distance| {
+            let mut settings = cloned_settings.lock().unwrap();
+            settings.set_toolbar_autohide(on);
+            settings.set_toolbar_hide_delay(delay.max(1) as u32);
+            settings.set_toolbar_reveal_distance(distance.max(1) as u32);
+            settings.save(settings::SETTINGS_FILE);
+        }
+    });
+
+    /* CALLBACK:
+        Turns the floating page window on or off: a small window above other apps with only the page
+        showing. Leaving it gives the window back the size it had
+
+        # Arguments
+        * 'on' - true to float the page
+
+        # Return
+        N / A
+    */
+    let size_before_floating: Rc<Cell<Option<slint::PhysicalSize>>> = Rc::new(Cell::new(None));
+    app.global::<AppService>().on_set_floating({
+        let app_weak = app.as_weak();
+        move |on| {
+            let window = app_weak.unwrap();
+            let window = window.window();
+            if on {
+                size_before_floating.set(Some(window.size()));
+                // about a third of a page at its printed size, enough to read beside another app
+                window.set_size(slint::LogicalSize::new(420.0, 560.0));
+            } else if let Some(size) = size_before_floating.take() {
+                window.set_size(size);
+            }
+        }
+    });
+
+    /* CALLBACK:
+        Stores whether closing with unsaved notes asks for confirmation
+
+        # Arguments
+        * 'confirm' - true to ask before closing
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_confirm_exit(store_setting(&settings, Settings::set_confirm_exit));
+
+    /* CALLBACK:
+        Stores whether clearing the recents asks for confirmation
+
+        # Arguments
+        * 'confirm' - true to ask before clearing
+
+        # Return
+        N / A
+    */
+    app.global::<AppService>().on_set_confirm_clear_recents(store_setting(
+        &settings,
+        Settings::set_confirm_clear_recents,
+    ));
+
+    compare::bind(&app, &settings, &file_manager);
+
+    let _ = app.run();
+
+    if holds_lock {
+        instance::release_lock(instance::LOCK_FILE);
+    }
+    Ok(())
+}
+
+// Writes the notes shown, and the notes file set aside while notes are per page, where they have edits
+// that weren't saved. Notes that were never saved to a file stay unsaved, there's nobody to ask where.
+// Returns whether the notes shown were written
+fn save_dirty_notes(editor: &BackendTextEditor) -> std::io::Result<bool> {
+    let path = editor.get_txt_file_path();
+    let saved = editor.get_dirty() && !path.is_empty();
+    if saved {
+        txt_file::write_to_file(path.as_str(), editor.get_notes_text().as_str())?;
+        editor.set_dirty(false);
+    }
+    let path = editor.get_file_notes_path();
+    if editor.get_page_notes() && editor.get_file_notes_dirty() && !path.is_empty() {
+        txt_file::write_to_file(path.as_str(), editor.get_file_notes_text().as_str())?;
+        editor.set_file_notes_dirty(false);
+    }
+    Ok(saved)
+}
+
+// The session as a recovery snapshot: the open document and page, and the notes that could be lost
+fn session_snapshot(app: &App, file_manager: &mut interface::FileManager) -> recovery::Snapshot {
+    let mut snapshot = recovery::Snapshot::default();
+    if file_manager.has_document() {
+        snapshot.document = file_manager.get_cur_path().unwrap_or_default();
+        snapshot.page = file_manager.get_cur_file_info().get_cur_page();
+    }
+    // page notes are in database.json already, what could be lost is the notes file set aside
+    let editor = app.global::<BackendTextEditor>();
+    if editor.get_page_notes() {
+        snapshot.notes_path = editor.get_file_notes_path().into();
+        snapshot.notes = editor.get_file_notes_text().into();
+    } else {
+        snapshot.notes_path = editor.get_txt_file_path().into();
+        snapshot.notes = editor.get_notes_text().into();
+    }
+    snapshot
+}
+
+// Opens the PDF at path, at page if one is given, without holding up the window. The path is resolved
+// and the file read on a thread of their own, see interface::read_document, while the window says
+// which document is opening. Once read it becomes the current document on the UI thread and the page
+// view shows it, a file that couldn't be read leaves the window as it was with why on the opening page
+fn open_document(app: &App, file_manager: &Arc<Mutex<interface::FileManager>>, path: String, page: Option<u32>) {
+    let name = std::path::Path::new(&path)
+        .file_name()
+        .map_or(path.clone(), |name| name.to_string_lossy().into_owned());
+    app.global::<AppService>().set_opening(name.into());
+    let app_weak = app.as_weak();
+    let file_manager = file_manager.clone();
+    thread::spawn(move || {
+        let read = interface::read_document(path.clone(), |resolved| {
+            file_manager.lock().unwrap().entry_to_read(resolved, &path)
+        });
+        let _ = app_weak.upgrade_in_event_loop(move |app| {
+            let service = app.global::<AppService>();
+            service.set_opening("".into());
+            match read {
+                Ok(read) => {
+                    let mut file_manager = file_manager.lock().unwrap();
+                    if let Some(read) = read {
+                        file_manager.open_read(read);
+                    }
+                    if let Some(page) = page {
+                        file_manager.get_cur_file_info().set_cur_page(page);
+                    }
+                    file_manager.save(interface::DATABASE_FILE);
+                    drop(file_manager);
+                    service.set_error_message("".into());
+                    app.set_active_page(1);
+                    app.global::<BackendPDF>().invoke_refresh();
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    service.set_error_message(e.into());
+                }
+            }
+        });
+    });
+}
+
+// Moves the current document to the next or previous page, returning the notice to show. At the
+// first or last page what happens depends on the page boundary setting
+fn turn_page(
+    file_manager: &Mutex<interface::FileManager>,
+    settings: &Mutex<settings::Settings>,
+    forward: bool,
+    schedule_save: &dyn Fn(),
+) -> &'static str {
+    let boundary = settings.lock().unwrap().get_page_boundary();
+    let mut file_manager = file_manager.lock().unwrap();
+    let num = file_manager.get_cur_file_info().get_cur_page();
+    let count = file_manager.get_cur_file_info().get_page_count();
+    match pages::step(num, count, forward, boundary) {
+        Some(page) => {
+            file_manager.get_cur_file_info().set_cur_page(page);
+            drop(file_manager);
+            schedule_save();
+            ""
+        }
+        None if boundary != pages::PageBoundary::Notify => "",
+        None if forward => "You've reached the end of the document",
+        None => "This is the first page",
+    }
+}
+
+// Moves the current document to the next or previous bookmarked page, returning the notice to show
+fn step_bookmark(
+    file_manager: &Mutex<interface::FileManager>,
+    settings: &Mutex<settings::Settings>,
+    forward: bool,
+    schedule_save: &dyn Fn(),
+) -> String {
+    let boundary = settings.lock().unwrap().get_page_boundary();
+    let mut file_manager = file_manager.lock().unwrap();
+    let Ok(path) = file_manager.get_cur_path() else {
+        return "".to_string();
+    };
+    let entries = toc::contents(path.as_str()).unwrap_or_else(|e| {
+        eprintln!("Error reading the contents of {}: {}", path, e);
+        Vec::new()
+    });
+    let bookmarked = toc::bookmarked_pages(&entries);
+    if bookmarked.is_empty() {
+        return "This document has no bookmarks".to_string();
+    }
+    let current = file_manager.get_cur_file_info().get_cur_page();
+    match toc::step_bookmark(&bookmarked, current, forward, boundary) {
+        Some(page) => {
+            file_manager.get_cur_file_info().set_cur_page(page);
+            schedule_save();
+            "".to_string()
+        }
+        None if boundary == pages::PageBoundary::Notify && forward => "No more bookmarks after this page".to_string(),
+        None if boundary == pages::PageBoundary::Notify => "No bookmarks before this page".to_string(),
+        None => "".to_string(),
+    }
+}
+
+// A recent file as the recents views show it, with the time it was added while sorted by that and the
+// time it was last read otherwise
+fn recent_entry(a_file: &interface::FileInfo, sort: interface::RecentsSort, format: timestamps::TimestampFormat) -> RecentFile {
+    let last_read = if sort == interface::RecentsSort::LastAdded {
+        a_file.get_added(format)
+    } else {
+        a_file.get_last_read(format)
+    };
+    RecentFile {
+        name: a_file.get_name().into(),
+        path: a_file.get_filepath().into(),
+        details: a_file.get_details().into(),
+        last_read: last_read.into(),
+        progress: a_file.get_progress(),
+        finished: a_file.get_finished(),
+    }
+}
+
+// Notes font sizes run from 1 to 256, typed or stepped
+fn clamp_font_size(size: i64) -> i32 {
+    size.clamp(1, 256) as i32
+}
+
+// The size typed into the font size box, within 1 to 256. Anything but digits keeps the old size
+fn parse_font_size(input: &str, old_font: i32) -> i32 {
+    let mut numeric = true;
+    let mut font: i64 = 0;
+    for ch in input.chars() {
+        font = font.saturating_mul(10);
+        if !ch.is_numeric() {
+            numeric = false;
+            break;
+        } else {
+            font = font.saturating_add(ch.to_digit(10).unwrap() as i64);
+        }
+    }
+    if !numeric {
+        font = old_font as i64;
+    }
+    clamp_font_size(font)
+}
+
+// The font size a step up or down from size, clamped like a typed size
+fn step_font_size(size: i32, step: i32, up: bool) -> i32 {
+    let step = if up { step as i64 } else { -(step as i64) };
+    clamp_font_size(size as i64 + step)
+}
+
+// Notes font size for the open document: its own while sizes are kept per document and it has one,
+// the global size otherwise
+fn notes_font_size(settings: &settings::Settings, file_manager: &mut interface::FileManager) -> i32 {
+    let own = if file_manager.has_document() {
+        file_manager.get_cur_file_info().get_notes_font_size()
+    } else {
+        None
+    };
+    settings.notes_font_size_for(own) as i32
+}
+
+// Keeps the notes font size for the next session, with the open document while sizes are kept per
+// document and in the settings otherwise. Only a changed size is written
+fn save_font_size(
+    settings: &Mutex<settings::Settings>,
+    file_manager: &Mutex<interface::FileManager>,
+    size: i32,
+    schedule_save: &dyn Fn(),
+) {
+    let mut file_manager = file_manager.lock().unwrap();
+    let mut settings = settings.lock().unwrap();
+    if notes_font_size(&settings, &mut file_manager) == size {
+        return;
+    }
+    if settings.keeps_document_font_size(file_manager.has_document()) {
+        file_manager.get_cur_file_info().set_notes_font_size(size as u32);
+        drop(file_manager);
+        schedule_save();
+    } else {
+        settings.set_notes_font_size(size as u32);
+        settings.save(settings::SETTINGS_FILE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepping_stops_at_the_clamp() {
+        assert_eq!(step_font_size(12, 1, true), 13);
+        assert_eq!(step_font_size(255, 1, true), 256);
+        assert_eq!(step_font_size(256, 1, true), 256);
+        assert_eq!(step_font_size(250, 10, true), 256);
+        assert_eq!(step_font_size(2, 1, false), 1);
+        assert_eq!(step_font_size(1, 1, false), 1);
+        assert_eq!(step_font_size(5, 10, false), 1);
+    }
+
+    #[test]
+    fn typed_sizes_are_clamped_the_same_way() {
+        assert_eq!(parse_font_size("14", 12), 14);
+        assert_eq!(parse_font_size("0", 12), 1);
+        assert_eq!(parse_font_size("999999999999999999999", 12), 256);
+        assert_eq!(parse_font_size("1x", 12), 12);
+    }
 }
@@ -0,0 +1,156 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+// One line of the notes preview. Slint has no rich text, so styling is per block: a block is bold or
+// italic only when all of its text is, and links and images are listed as their own clickable blocks
+// after the block they appear in
+#[derive(Default)]
+pub struct MarkdownBlock {
+    // heading, paragraph, item, quote, code, rule, link or image
+    pub kind: &'static str,
+    pub text: String,
+    // heading level, or list nesting depth for items
+    pub level: i32,
+    pub bold: bool,
+    pub italic: bool,
+    pub link: String,
+}
+
+// Converts Markdown notes to preview blocks. The parser never fails, anything it doesn't style
+// (tables, html) shows up as plain text
+pub fn render(source: &str) -> Vec<MarkdownBlock> {
+    let mut blocks = Vec::new();
+    let mut current = MarkdownBlock {
+        kind: "paragraph",
+        ..Default::default()
+    };
+    let mut links: Vec<MarkdownBlock> = Vec::new();
+    // numbering of each open list, None for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut quote_depth = 0;
+    let mut strong_depth = 0;
+    let mut emphasis_depth = 0;
+    let mut all_bold = true;
+    let mut all_italic = true;
+    let mut link_url: Option<String> = None;
+    let mut link_kind = "link";
+    let mut link_text = String::new();
+
+    for event in Parser::new(source) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current.kind = "heading";
+                current.level = level as i32;
+            }
+            Event::Start(Tag::Paragraph) if current.kind != "item" => {
+                current.kind = if quote_depth > 0 { "quote" } else { "paragraph" };
+                current.level = 0;
+            }
+            Event::Start(Tag::BlockQuote(_)) => quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quote_depth -= 1,
+            Event::Start(Tag::CodeBlock(_)) => {
+                current.kind = "code";
+                current.level = 0;
+            }
+            Event::Start(Tag::List(start)) => {
+                // a nested list ends the text of the item it's in
+                flush(&mut blocks, &mut current, &mut all_bold, &mut all_italic, &mut links);
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                current.kind = "item";
+                current.level = lists.len() as i32;
+                current.text = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+            }
+            Event::Start(Tag::Strong) => strong_depth += 1,
+            Event::End(TagEnd::Strong) => strong_depth -= 1,
+            Event::Start(Tag::Emphasis) => emphasis_depth += 1,
+            Event::End(TagEnd::Emphasis) => emphasis_depth -= 1,
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                link_url = Some(dest_url.to_string());
+                link_kind = "link";
+                link_text.clear();
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                link_url = Some(dest_url.to_string());
+                link_kind = "image";
+                link_text.clear();
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                if let Some(url) = link_url.take() {
+                    links.push(MarkdownBlock {
+                        kind: link_kind,
+                        text: if link_text.is_empty() { url.clone() } else { link_text.clone() },
+                        link: url,
+                        ..Default::default()
+                    });
+                }
+            }
+            Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                if !text.trim().is_empty() {
+                    all_bold &= strong_depth > 0;
+                    all_italic &= emphasis_depth > 0;
+                }
+                if link_url.is_some() {
+                    link_text.push_str(&text);
+                }
+                current.text.push_str(&text);
+            }
+            Event::SoftBreak => current.text.push(' '),
+            Event::HardBreak => current.text.push('\n'),
+            Event::Rule => {
+                flush(&mut blocks, &mut current, &mut all_bold, &mut all_italic, &mut links);
+                blocks.push(MarkdownBlock {
+                    kind: "rule",
+                    ..Default::default()
+                });
+            }
+            // paragraphs of a loose list item stay part of the item
+            Event::End(TagEnd::Paragraph) if current.kind == "item" => current.text.push(' '),
+            Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::Item) => {
+                flush(&mut blocks, &mut current, &mut all_bold, &mut all_italic, &mut links);
+            }
+            _ => {}
+        }
+    }
+    flush(&mut blocks, &mut current, &mut all_bold, &mut all_italic, &mut links);
+    blocks
+}
+
+// Ends the current block, followed by the links found in it
+fn flush(
+    blocks: &mut Vec<MarkdownBlock>,
+    current: &mut MarkdownBlock,
+    all_bold: &mut bool,
+    all_italic: &mut bool,
+    links: &mut Vec<MarkdownBlock>,
+) {
+    let text = current.text.trim_end().to_string();
+    if !text.is_empty() {
+        blocks.push(MarkdownBlock {
+            kind: current.kind,
+            text,
+            level: current.level,
+            bold: *all_bold || current.kind == "heading",
+            italic: *all_italic,
+            link: String::new(),
+        });
+    }
+    blocks.append(links);
+    current.kind = "paragraph";
+    current.level = 0;
+    current.text.clear();
+    *all_bold = true;
+    *all_italic = true;
+}
@@ -0,0 +1,149 @@
+use crate::interface::FileManager;
+use crate::{App, BackendPDF};
+use slint::ComponentHandle;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+// Length of a PDF point in millimetres, a point being 1/72 inch
+const POINT_MM: f32 = 25.4 / 72.0;
+
+// The part of the page the last render shows, in PDF points from the page's top left corner. Points
+// picked on the displayed image are mapped through it, so cropping and zoom don't change the result
+#[derive(Clone, Copy)]
+pub struct ShownArea {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+    // size of the whole page
+    pub page_width: f32,
+    pub page_height: f32,
+}
+
+impl ShownArea {
+    // Point on the page at a position given as fractions of the displayed image's width and height
+    pub fn point_at(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.left + x * self.width, self.top + y * self.height)
+    }
+
+    // A position on the displayed image as fractions of the whole page's size, which a crop or zoom
+    // doesn't change
+    pub fn page_fraction(&self, x: f32, y: f32) -> (f32, f32) {
+        let (px, py) = self.point_at(x, y);
+        (px / self.page_width.max(1.0), py / self.page_height.max(1.0))
+    }
+
+    // Where a position given as fractions of the whole page is on the displayed image, None when it's
+    // cropped away
+    pub fn shown_fraction(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        let sx = (x * self.page_width - self.left) / self.width;
+        let sy = (y * self.page_height - self.top) / self.height;
+        ((0.0..=1.0).contains(&sx) && (0.0..=1.0).contains(&sy)).then_some((sx, sy))
+    }
+
+    // Where a rectangle given as fractions of the whole page is on the displayed image, as its left,
+    // top, width and height in fractions of the image, cut to what's shown. None when it's cropped away
+    pub fn shown_rect(&self, left: f32, top: f32, right: f32, bottom: f32) -> Option<(f32, f32, f32, f32)> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        let sx = |x: f32| ((x * self.page_width - self.left) / self.width).clamp(0.0, 1.0);
+        let sy = |y: f32| ((y * self.page_height - self.top) / self.height).clamp(0.0, 1.0);
+        let (x0, x1) = (sx(left.min(right)), sx(left.max(right)));
+        let (y0, y1) = (sy(top.min(bottom)), sy(top.max(bottom)));
+        (x1 > x0 && y1 > y0).then_some((x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+// Distance in points between two positions on the displayed image, each as fractions of its size
+pub fn distance(area: ShownArea, from: (f32, f32), to: (f32, f32)) -> f32 {
+    let (x0, y0) = area.point_at(from.0, from.1);
+    let (x1, y1) = area.point_at(to.0, to.1);
+    (x1 - x0).hypot(y1 - y0)
+}
+
+// The distance as measured on paper and, for a drawing at 1:scale, in the real world. A scale of 0
+// or 1 means the page is at full size
+pub fn describe(points: f32, scale: u32) -> String {
+    let on_paper = format!("{:.1} pt ({})", points, format_length(points * POINT_MM));
+    if scale <= 1 {
+        return on_paper;
+    }
+    format!("{} · {} at 1:{}", on_paper, format_length(points * POINT_MM * scale as f32), scale)
+}
+
+// Millimetres as mm, cm, m or km, whichever reads best
+fn format_length(mm: f32) -> String {
+    if mm >= 1_000_000.0 {
+        format!("{:.2} km", mm / 1_000_000.0)
+    } else if mm >= 1000.0 {
+        format!("{:.2} m", mm / 1000.0)
+    } else if mm >= 100.0 {
+        format!("{:.1} cm", mm / 10.0)
+    } else {
+        format!("{:.1} mm", mm)
+    }
+}
+
+// Registers the callbacks for measuring between two points and for the document's drawing scale
+pub fn bind(
+    app: &App,
+    file_manager: &Arc<Mutex<FileManager>>,
+    shown_area: &Rc<Cell<ShownArea>>,
+    schedule_save: &Rc<dyn Fn()>,
+) {
+    /*  CALLBACK:
+        Measures the distance between two points picked on the page with the ruler
+
+        # Arguments
+        * 'x0', 'y0' - first point, as fractions of the displayed image's width and height
+        * 'x1', 'y1' - second point, the same way
+
+        # Return
+        * The distance in points and millimetres, and at the document's drawing scale if it has one
+    */
+    app.global::<BackendPDF>().on_measure({
+        let cloned_file_manager = file_manager.clone();
+        let shown_area = shown_area.clone();
+        move |x0, y0, x1, y1| {
+            let points = distance(shown_area.get(), (x0, y0), (x1, y1));
+            let scale = cloned_file_manager.lock().unwrap().get_cur_file_info().get_measure_scale();
+            describe(points, scale).into()
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the drawing scale of the current document
+
+        # Arguments
+        N/A
+
+        # Return
+        * N of a 1:N scale, 1 for a page at full size
+    */
+    app.global::<BackendPDF>().on_get_measure_scale({
+        let cloned_file_manager = file_manager.clone();
+        move || cloned_file_manager.lock().unwrap().get_cur_file_info().get_measure_scale().max(1) as i32
+    });
+
+    /*  CALLBACK:
+        Stores the drawing scale of the current document, kept with it in the recents
+
+        # Arguments
+        * 'scale' - N of a 1:N scale
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_measure_scale({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |scale| {
+            cloned_file_manager.lock().unwrap().get_cur_file_info().set_measure_scale(scale.max(1) as u32);
+            schedule_save();
+        }
+    });
+}
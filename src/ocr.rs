@@ -0,0 +1,286 @@
+use crate::interface::FileManager;
+use crate::pages;
+use crate::render::{self, RenderQuality};
+use crate::txt_file;
+use crate::{App, BackendPDF};
+use serde::{Deserialize, Serialize};
+use slint::ComponentHandle;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Text recognized on scanned pages, next to database.json, so a document is only run through OCR once
+pub const OCR_FILE: &str = "ocr_text.json";
+// Pixels pages are rendered at for recognition, about 300 dpi for a letter page. Tesseract is much
+// less accurate on the smaller renders shown on screen
+const OCR_RENDER_PIXELS: f32 = 8_000_000.0;
+
+// Recognized text of a document's pages by page index, valid while the file's modified time matches
+#[derive(Default)]
+#[derive(Serialize, Deserialize)]
+struct OcrDocument {
+    modified: u64,
+    pages: HashMap<u32, String>,
+}
+
+#[derive(Default)]
+#[derive(Serialize, Deserialize)]
+pub struct OcrCache {
+    documents: HashMap<String, OcrDocument>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl OcrCache {
+    pub fn load(filename: &str) -> Self {
+        match txt_file::read_file(filename) {
+            Ok(data) => serde_json::from_str(data.as_str()).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    // Writes the cache if pages were recognized since it was last written
+    pub fn save(&mut self, filename: &str) {
+        if !self.changed {
+            return;
+        }
+        let json = serde_json::to_string(self).unwrap();
+        match txt_file::write_to_file(filename, json.as_str()) {
+            Ok(_) => self.changed = false,
+            Err(e) => eprintln!("Error saving OCR text: {}", e),
+        }
+    }
+
+    // Recognized text of a page, None if it wasn't recognized since the file last changed
+    pub fn page_text(&self, path: &str, modified: u64, page: u32) -> Option<&str> {
+        self.documents
+            .get(path)
+            .filter(|document| document.modified == modified)
+            .and_then(|document| document.pages.get(&page))
+            .map(String::as_str)
+    }
+
+    // Recognized text of the document's pages by page index, a copy so the cache isn't held while the
+    // text is used. Empty if nothing was recognized since the file last changed
+    pub fn document_text(&self, path: &str, modified: u64) -> HashMap<u32, String> {
+        self.documents
+            .get(path)
+            .filter(|document| document.modified == modified)
+            .map(|document| document.pages.clone())
+            .unwrap_or_default()
+    }
+
+    // How many of the document's pages were recognized since the file last changed
+    pub fn recognized_pages(&self, path: &str, modified: u64) -> usize {
+        self.documents
+            .get(path)
+            .filter(|document| document.modified == modified)
+            .map_or(0, |document| document.pages.len())
+    }
+
+    // The text of an older version of the file is dropped when a page of a newer one comes in
+    fn store(&mut self, path: &str, modified: u64, page: u32, text: String) {
+        let document = self.documents.entry(path.to_string()).or_default();
+        if document.modified != modified {
+            document.modified = modified;
+            document.pages.clear();
+        }
+        document.pages.insert(page, text);
+        self.changed = true;
+    }
+}
+
+// Whether tesseract, the OCR engine PDFer runs, is installed
+pub fn engine_available() -> bool {
+    Command::new("tesseract")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+// Text tesseract reads from the image. The image goes to it through a pipe, so runs overlapping after
+// one was superseded never share a file
+pub fn recognize(image: &image::RgbaImage) -> Result<String, String> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| format!("Error preparing the page for OCR: {}", e))?;
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| "No OCR engine found, install tesseract".to_string())?;
+    // tesseract reads the whole image before it writes anything, so the pipe is closed before waiting
+    let written = child.stdin.take().map(|mut stdin| stdin.write_all(png.get_ref()));
+    let output = child.wait_with_output().map_err(|e| format!("OCR failed: {}", e))?;
+    if let Some(Err(e)) = written {
+        return Err(format!("OCR failed: {}", e));
+    }
+    if !output.status.success() {
+        return Err(format!("OCR failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Recognizes the pages of the document that aren't in the cache yet, storing each as it's done so a
+// cancelled run keeps what it has. Meant for a worker thread: progress is reported before each page,
+// and it stops as soon as cancelled says the run is no longer wanted. Returns how many pages of the
+// document have text now. The document is loaded for each page's render only, see pages::with_document
+pub fn recognize_document(
+    cache: &Mutex<OcrCache>,
+    path: &str,
+    modified: u64,
+    cancelled: impl Fn() -> bool,
+    progress: impl Fn(u32, u32),
+) -> Result<usize, String> {
+    let count = pages::with_document(path, pages::page_count).map_err(|e| format!("Couldn't open {}: {}", path, e))?;
+    let result = (0..count).try_for_each(|index| {
+        if cancelled() || cache.lock().unwrap().page_text(path, modified, index).is_some() {
+            return Ok(());
+        }
+        progress(index, count);
+        let image = render_for_recognition(path, index)?;
+        let text = recognize(&image)?;
+        // a run superseded while it read the page leaves the cache to the one that replaced it
+        if cancelled() {
+            return Ok(());
+        }
+        cache.lock().unwrap().store(path, modified, index, text);
+        Ok(())
+    });
+    let mut cache = cache.lock().unwrap();
+    cache.save(OCR_FILE);
+    result.map(|_| cache.recognized_pages(path, modified))
+}
+
+// The page rendered at the resolution tesseract reads best
+fn render_for_recognition(path: &str, index: u32) -> Result<image::RgbaImage, String> {
+    pages::with_document(path, |document| {
+        let page = pages::get_page(document, index).map_err(|e| format!("Error loading page {}: {}", index + 1, e))?;
+        let (width, height) = render::render_target(page.width().value, page.height().value, OCR_RENDER_PIXELS);
+        render::render_page_at(&page, RenderQuality::default(), false, width, height)
+            .map_err(|e| format!("Error rendering page {}: {}", index + 1, e))
+    })
+    .map_err(|e| format!("Couldn't open {}: {}", path, e))?
+}
+
+// Registers the callbacks that recognize the text of scanned pages
+pub fn bind(app: &App, file_manager: &Arc<Mutex<FileManager>>, ocr_cache: &Arc<Mutex<OcrCache>>) {
+    // Each recognition takes the next generation, so one still running sees it was superseded and stops
+    let ocr_generation = Arc::new(AtomicUsize::new(0));
+
+    /*  CALLBACK:
+        Returns how many pages of the current document have text from OCR
+
+        # Arguments
+        N/A
+
+        # Return
+        * number of pages recognized since the file last changed, 0 if OCR never ran on it
+    */
+    app.global::<BackendPDF>().on_get_ocr_pages({
+        let cloned_file_manager = file_manager.clone();
+        let ocr_cache = ocr_cache.clone();
+        move || {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let Ok(path) = file_manager.get_cur_path() else {
+                return 0;
+            };
+            let modified = file_manager.get_cur_file_info().get_modified();
+            ocr_cache.lock().unwrap().recognized_pages(&path, modified) as i32
+        }
+    });
+
+    /*  CALLBACK:
+        Runs OCR over the pages of the current document that have no OCR text yet, on a worker thread.
+        Progress and the outcome are set on BackendPDF as they come in, and the text is cached on disk
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_run_ocr({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let ocr_cache = ocr_cache.clone();
+        let ocr_generation = ocr_generation.clone();
+        move || {
+            let app = app_weak.unwrap();
+            let backend = app.global::<BackendPDF>();
+            let (path, modified) = {
+                let mut file_manager = cloned_file_manager.lock().unwrap();
+                let Ok(path) = file_manager.get_cur_path() else {
+                    return;
+                };
+                (path, file_manager.get_cur_file_info().get_modified())
+            };
+            backend.set_ocr_document(path.as_str().into());
+            if !engine_available() {
+                backend.set_ocr_status("No OCR engine found, install tesseract to recognize scanned pages".into());
+                return;
+            }
+            let generation = ocr_generation.fetch_add(1, Ordering::SeqCst) + 1;
+            backend.set_ocr_running(true);
+            backend.set_ocr_status("Starting OCR…".into());
+
+            let app_weak = app_weak.clone();
+            let ocr_cache = ocr_cache.clone();
+            let ocr_generation = ocr_generation.clone();
+            thread::spawn(move || {
+                let is_current = || ocr_generation.load(Ordering::SeqCst) == generation;
+                let result = recognize_document(&ocr_cache, &path, modified, || !is_current(), |page, count| {
+                    let status = format!("Recognizing page {} of {}…", page + 1, count);
+                    let _ = app_weak.upgrade_in_event_loop(move |app| {
+                        app.global::<BackendPDF>().set_ocr_status(status.into());
+                    });
+                });
+                if !is_current() {
+                    return;
+                }
+                let _ = app_weak.upgrade_in_event_loop(move |app| {
+                    let backend = app.global::<BackendPDF>();
+                    let status = match result {
+                        Ok(1) => "OCR done, 1 page has text".to_string(),
+                        Ok(pages) => format!("OCR done, {} pages have text", pages),
+                        Err(e) => e,
+                    };
+                    backend.set_ocr_status(status.into());
+                    backend.set_ocr_running(false);
+                    backend.set_ocr_pages(backend.invoke_get_ocr_pages());
+                    backend.set_searchable(backend.invoke_is_searchable());
+                    backend.set_page_text(backend.invoke_get_page_text(backend.get_current_page()));
+                });
+            });
+        }
+    });
+
+    /*  CALLBACK:
+        Stops the running OCR, pages already recognized stay in the cache
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_cancel_ocr({
+        let app_weak = app.as_weak();
+        let ocr_generation = ocr_generation.clone();
+        move || {
+            ocr_generation.fetch_add(1, Ordering::SeqCst);
+            let app = app_weak.unwrap();
+            let backend = app.global::<BackendPDF>();
+            backend.set_ocr_running(false);
+            backend.set_ocr_status("OCR cancelled, recognized pages are kept".into());
+            backend.set_ocr_pages(backend.invoke_get_ocr_pages());
+            backend.set_searchable(backend.invoke_is_searchable());
+        }
+    });
+}
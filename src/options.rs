@@ -0,0 +1,35 @@
+// Declares an enum of choices the UI offers by name, e.g. in a ComboBox, each variant with the name it
+// is shown as. The enum gets NAMES, the names in variant order, and name and from_name to go between
+// the two. Attributes on the enum and its variants are kept
+macro_rules! named_options {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $enum:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $name:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $enum {
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $enum {
+            pub const NAMES: [&'static str; [$($name),+].len()] = [$($name),+];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($enum::$variant => $name,)+
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some($enum::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use named_options;
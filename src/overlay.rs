@@ -0,0 +1,87 @@
+use crate::options::named_options;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Corner of the page the number is drawn in
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum Corner {
+        TopLeft => "Top left",
+        TopRight => "Top right",
+        BottomLeft => "Bottom left",
+        #[default]
+        BottomRight => "Bottom right",
+    }
+}
+
+// Page number drawn onto the page itself. The size is in points of the page, so the number grows and
+// shrinks with the render like the rest of the page
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageNumberOverlay {
+    pub enabled: bool,
+    pub corner: Corner,
+    pub size: f32,
+    // "#RRGGBB"
+    pub color: String,
+    pub show_total: bool,
+}
+
+impl Default for PageNumberOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            corner: Corner::default(),
+            size: 12.0,
+            color: "#808080".to_string(),
+            show_total: false,
+        }
+    }
+}
+
+impl PageNumberOverlay {
+    // Black for anything that isn't a "#RRGGBB" color
+    fn pdf_color(&self) -> PdfColor {
+        let hex = self.color.trim_start_matches('#');
+        match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => {
+                PdfColor::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+            }
+            _ => PdfColor::new(0, 0, 0, 255),
+        }
+    }
+}
+
+// Adds the page number as a text object to the loaded page, inset from the chosen corner by the text
+// size so it sits in the page margin. Only the in-memory document changes, nothing is saved
+pub fn draw_page_number(
+    document: &mut PdfDocument,
+    page: &mut PdfPage,
+    text: &str,
+    overlay: &PageNumberOverlay,
+) -> Result<(), PdfiumError> {
+    let font = document.fonts_mut().helvetica();
+    let size = PdfPoints::new(overlay.size.max(1.0));
+    let text_width = PdfPageTextObject::new(document, text, font, size)?.width()?;
+
+    let inset = size.value;
+    let x = match overlay.corner {
+        Corner::TopLeft | Corner::BottomLeft => inset,
+        Corner::TopRight | Corner::BottomRight => page.width().value - inset - text_width.value,
+    };
+    let y = match overlay.corner {
+        Corner::BottomLeft | Corner::BottomRight => inset,
+        Corner::TopLeft | Corner::TopRight => page.height().value - inset - size.value,
+    };
+
+    let mut object = page.objects_mut().create_text_object(
+        PdfPoints::new(x),
+        PdfPoints::new(y),
+        text,
+        font,
+        size,
+    )?;
+    object.set_fill_color(overlay.pdf_color())
+}
@@ -0,0 +1,309 @@
+use crate::pages;
+use crate::render::{self, PageBackground, PageFit, RenderQuality};
+use pdfium_render::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Mutex;
+
+// Default memory the rendered pages kept for instant page turns may take up, in megabytes
+pub const DEFAULT_CACHE_MEGABYTES: u32 = 256;
+// Default number of pages rendered ahead in the background when a document is opened
+pub const DEFAULT_WARMUP_PAGES: u32 = 3;
+
+// What a cached render was made from. A page is only reused when all of it matches, so a changed
+// file, size or render option renders afresh. The size stands for the zoom, fit and display scale the
+// page was sized by. Page colors and crops are applied to a copy of the cached render, so they aren't
+// part of it
+#[derive(Clone, PartialEq, Debug)]
+pub struct PageKey {
+    pub path: String,
+    pub modified: u64,
+    pub page: u32,
+    pub width: i32,
+    pub height: i32,
+    pub quality: RenderQuality,
+    pub annotations: bool,
+    pub background: PageBackground,
+}
+
+impl PageKey {
+    // Whether a render keyed like this could still be asked for alongside the other one: made with the
+    // same render options, and of the same version of the file if it's the same document. A render
+    // that couldn't is stale until the options are changed back
+    fn current_with(&self, other: &PageKey) -> bool {
+        self.quality == other.quality
+            && self.annotations == other.annotations
+            && self.background == other.background
+            && (self.path != other.path || self.modified == other.modified)
+    }
+}
+
+// Rendered pages, before any recoloring or cropping, within a memory budget. The page used longest
+// ago is dropped first
+pub struct PageCache {
+    pages: VecDeque<(PageKey, image::RgbaImage)>,
+    bytes: usize,
+    budget: usize,
+}
+
+impl PageCache {
+    pub fn new(megabytes: u32) -> Self {
+        Self {
+            pages: VecDeque::new(),
+            bytes: 0,
+            budget: megabytes as usize * 1024 * 1024,
+        }
+    }
+
+    // Changes the budget, dropping pages until the cache fits it
+    pub fn set_budget(&mut self, megabytes: u32) {
+        self.budget = megabytes as usize * 1024 * 1024;
+        self.evict(0, None);
+    }
+
+    pub fn contains(&self, key: &PageKey) -> bool {
+        self.pages.iter().any(|(cached, _)| cached == key)
+    }
+
+    // A copy of the cached render, marked as the most recently used
+    pub fn get(&mut self, key: &PageKey) -> Option<image::RgbaImage> {
+        let index = self.pages.iter().position(|(cached, _)| cached == key)?;
+        let entry = self.pages.remove(index)?;
+        let image = entry.1.clone();
+        self.pages.push_back(entry);
+        Some(image)
+    }
+
+    // Whether a render of the given size fits without dropping anything, which background warmup
+    // checks so it never pushes out pages that were actually viewed
+    pub fn has_room(&self, width: i32, height: i32) -> bool {
+        self.bytes + image_bytes(width as u32, height as u32) <= self.budget
+    }
+
+    // Keeps a render, making room by dropping first the pages rendered with other options than it,
+    // then the least recently used ones. A render larger than the whole budget isn't kept
+    pub fn insert(&mut self, key: PageKey, image: image::RgbaImage) {
+        let size = image_bytes(image.width(), image.height());
+        if size > self.budget {
+            return;
+        }
+        if let Some(index) = self.pages.iter().position(|(cached, _)| *cached == key) {
+            if let Some((_, old)) = self.pages.remove(index) {
+                self.bytes -= image_bytes(old.width(), old.height());
+            }
+        }
+        self.evict(size, Some(&key));
+        self.bytes += size;
+        self.pages.push_back((key, image));
+    }
+
+    fn evict(&mut self, incoming: usize, current: Option<&PageKey>) {
+        if let Some(current) = current {
+            while self.bytes + incoming > self.budget {
+                let Some(index) = self.pages.iter().position(|(cached, _)| !cached.current_with(current)) else {
+                    break;
+                };
+                if let Some((_, old)) = self.pages.remove(index) {
+                    self.bytes -= image_bytes(old.width(), old.height());
+                }
+            }
+        }
+        while self.bytes + incoming > self.budget {
+            let Some((_, old)) = self.pages.pop_front() else {
+                break;
+            };
+            self.bytes -= image_bytes(old.width(), old.height());
+        }
+    }
+}
+
+fn image_bytes(width: u32, height: u32) -> usize {
+    width as usize * height as usize * 4
+}
+
+// Pages to render ahead in the background, with what the viewer sizes them by
+pub struct Warmup {
+    pub path: String,
+    pub modified: u64,
+    pub pages: Range<u32>,
+    pub quality: RenderQuality,
+    pub annotations: bool,
+    pub background: PageBackground,
+    pub zoom: Option<u32>,
+    pub scale_factor: f32,
+    pub pane: (f32, f32),
+    pub fit: PageFit,
+    // fractions of the page's width and height left after cropping
+    pub shown: (f32, f32),
+    // the render cap in pixels, and the longest side a render may have
+    pub cap: f32,
+    pub max_side: f32,
+}
+
+// What warming up one page came to
+enum Ahead {
+    Rendered(PageKey, Result<image::RgbaImage, PdfiumError>),
+    // the page couldn't be read, or is in the cache already
+    Skipped,
+    // the page wouldn't fit in the cache without dropping one that was viewed
+    Full,
+}
+
+// Renders the warmup's pages into the cache, skipping those already in it. Meant for a worker thread:
+// it stops as soon as cancelled says the pages are no longer wanted, or once the next page wouldn't
+// fit in the cache without dropping one that was viewed. The document is loaded for each page only,
+// see pages::with_document
+pub fn warm_up(cache: &Mutex<PageCache>, warmup: &Warmup, cancelled: impl Fn() -> bool) {
+    for index in warmup.pages.clone() {
+        if cancelled() {
+            return;
+        }
+        let Ok(ahead) = pages::with_document(&warmup.path, |document| render_ahead(cache, warmup, document, index)) else {
+            return;
+        };
+        match ahead {
+            Ahead::Rendered(key, Ok(image)) => cache.lock().unwrap().insert(key, image),
+            Ahead::Rendered(_, Err(e)) => eprintln!("Error rendering page {} ahead: {}", index + 1, e),
+            Ahead::Skipped => {}
+            Ahead::Full => return,
+        }
+    }
+}
+
+// Renders one page of the warmup unless the cache has it already or has no room for it
+fn render_ahead(cache: &Mutex<PageCache>, warmup: &Warmup, document: &PdfDocument, index: u32) -> Ahead {
+    let Ok(page) = pages::get_page(document, index) else {
+        return Ahead::Skipped;
+    };
+    let (width, height) = render::display_size(
+        page.width().value,
+        page.height().value,
+        warmup.zoom,
+        warmup.scale_factor,
+        warmup.pane,
+        warmup.fit,
+        warmup.shown,
+        (warmup.cap, warmup.max_side),
+    );
+    let (width, height) = render::clamp_render_size(width, height, warmup.cap, warmup.max_side).unwrap_or((width, height));
+    let key = PageKey {
+        path: warmup.path.clone(),
+        modified: warmup.modified,
+        page: index,
+        width,
+        height,
+        quality: warmup.quality,
+        annotations: warmup.annotations,
+        background: warmup.background,
+    };
+    {
+        let cache = cache.lock().unwrap();
+        if cache.contains(&key) {
+            return Ahead::Skipped;
+        }
+        if !cache.has_room(width, height) {
+            return Ahead::Full;
+        }
+    }
+    let rendered = render::render_page_on(&page, warmup.quality, warmup.annotations, warmup.background, width, height);
+    Ahead::Rendered(key, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A quarter of a megabyte, so a one megabyte cache holds four
+    const SIDE: i32 = 256;
+
+    fn key(page: u32) -> PageKey {
+        PageKey {
+            path: "a.pdf".to_string(),
+            modified: 100,
+            page,
+            width: SIDE,
+            height: SIDE,
+            quality: RenderQuality::Balanced,
+            annotations: true,
+            background: PageBackground::White,
+        }
+    }
+
+    fn render() -> image::RgbaImage {
+        image::RgbaImage::new(SIDE as u32, SIDE as u32)
+    }
+
+    #[test]
+    fn keys_differing_in_any_part_are_cached_apart() {
+        let variants = [
+            key(0),
+            PageKey { path: "b.pdf".to_string(), ..key(0) },
+            PageKey { modified: 200, ..key(0) },
+            PageKey { page: 1, ..key(0) },
+            PageKey { width: SIDE / 2, height: SIDE * 2, ..key(0) },
+            PageKey { quality: RenderQuality::Crisp, ..key(0) },
+            PageKey { annotations: false, ..key(0) },
+            PageKey { background: PageBackground::Checkerboard, ..key(0) },
+        ];
+        let mut cache = PageCache::new(8);
+        for (index, variant) in variants.iter().enumerate() {
+            let mut image = render();
+            image.put_pixel(0, 0, image::Rgba([index as u8, 0, 0, 255]));
+            cache.insert(variant.clone(), image);
+        }
+        for (index, variant) in variants.iter().enumerate() {
+            assert_eq!(cache.get(variant).unwrap().get_pixel(0, 0).0[0], index as u8);
+        }
+        assert!(!cache.contains(&PageKey { page: 2, ..key(0) }));
+    }
+
+    #[test]
+    fn the_page_used_longest_ago_is_dropped_first() {
+        let mut cache = PageCache::new(1);
+        for page in 0..4 {
+            cache.insert(key(page), render());
+        }
+        // page 0 is viewed again, so page 1 is now the oldest
+        cache.get(&key(0));
+        cache.insert(key(4), render());
+        assert!(cache.contains(&key(0)));
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.contains(&key(4)));
+    }
+
+    #[test]
+    fn renders_with_other_options_are_dropped_before_older_ones() {
+        let mut cache = PageCache::new(1);
+        cache.insert(key(0), render());
+        cache.insert(key(1), render());
+        cache.insert(PageKey { quality: RenderQuality::Fast, ..key(2) }, render());
+        // the file was changed since this render
+        cache.insert(PageKey { modified: 50, ..key(3) }, render());
+
+        cache.insert(key(4), render());
+        cache.insert(key(5), render());
+        assert!(cache.contains(&key(0)) && cache.contains(&key(1)));
+        assert!(!cache.contains(&PageKey { quality: RenderQuality::Fast, ..key(2) }));
+        assert!(!cache.contains(&PageKey { modified: 50, ..key(3) }));
+    }
+
+    #[test]
+    fn renders_larger_than_the_budget_are_not_kept() {
+        let mut cache = PageCache::new(1);
+        cache.insert(key(0), render());
+        cache.insert(key(1), image::RgbaImage::new(1024, 512));
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.contains(&key(0)));
+    }
+
+    #[test]
+    fn a_smaller_budget_drops_pages_until_it_fits() {
+        let mut cache = PageCache::new(1);
+        for page in 0..4 {
+            cache.insert(key(page), render());
+        }
+        assert!(cache.has_room(0, 0) && !cache.has_room(1, 1));
+        cache.set_budget(0);
+        assert!((0..4).all(|page| !cache.contains(&key(page))));
+    }
+}
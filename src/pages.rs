@@ -0,0 +1,349 @@
+use crate::options::named_options;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Page numbers are u32 everywhere in PDFer. pdfium-render addresses pages with a u16, so an index past
+// that is reported as out of bounds here rather than wrapping around to some other page
+pub fn pdfium_index(index: u32) -> Result<PdfPageIndex, PdfiumError> {
+    PdfPageIndex::try_from(index).map_err(|_| PdfiumError::PageIndexOutOfBounds)
+}
+
+pub fn get_page<'a>(document: &PdfDocument<'a>, index: u32) -> Result<PdfPage<'a>, PdfiumError> {
+    document.pages().get(pdfium_index(index)?)
+}
+
+// Runs f on the document at path with pdfium loaded for the call only. Pdfium holds its one
+// process-wide lock for as long as it's loaded, so work on a worker thread goes through here a page or
+// a step at a time, letting the page shown be rendered in between. Locks the window also takes, such
+// as the OCR cache or the text index, aren't held when calling it: what f needs from them is copied
+// out first
+pub fn with_document<T>(path: &str, f: impl FnOnce(&PdfDocument) -> T) -> Result<T, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    Ok(f(&document))
+}
+
+pub fn page_count(document: &PdfDocument) -> u32 {
+    u32::from(document.pages().len())
+}
+
+// Sizes in points of the pages looked up so far, by document and page, valid while the file's
+// modified time matches. Independent of how the page is rendered
+#[derive(Default)]
+pub struct PageSizes {
+    sizes: HashMap<(String, u32), (u64, f32, f32)>,
+}
+
+impl PageSizes {
+    // Width and height of the page in points, loading the document the first time. Zeros when the
+    // document or page can't be read
+    pub fn get(&mut self, pdfium: &Pdfium, path: &str, modified: u64, index: u32) -> (f32, f32) {
+        let key = (path.to_string(), index);
+        if let Some(&(cached, width, height)) = self.sizes.get(&key) {
+            if cached == modified {
+                return (width, height);
+            }
+        }
+        let Ok(document) = pdfium.load_pdf_from_file(path, None) else {
+            return (0.0, 0.0);
+        };
+        let Ok(page) = get_page(&document, index) else {
+            return (0.0, 0.0);
+        };
+        let (width, height) = (page.width().value, page.height().value);
+        self.sizes.insert(key, (modified, width, height));
+        (width, height)
+    }
+}
+
+// The document's own label for a page, e.g. "xii" in the front matter. None when the PDF has no
+// labels for it
+pub fn page_label(document: &PdfDocument, index: u32) -> Option<String> {
+    let page = get_page(document, index).ok()?;
+    page.label().map(|label| label.to_string())
+}
+
+// Labels of the pages of the document last looked up, one for each page, valid while the file's
+// modified time matches. Looked up once, rather than loading the document on every page turn
+#[derive(Default)]
+pub struct PageLabels {
+    document: Option<(String, u64)>,
+    labels: Option<Vec<Option<String>>>,
+}
+
+impl PageLabels {
+    // The labels of the document's pages, loading it the first time. None when it can't be read
+    pub fn get(&mut self, path: &str, modified: u64) -> Option<&[Option<String>]> {
+        let key = (path.to_string(), modified);
+        if self.document.as_ref() != Some(&key) {
+            let pdfium = Pdfium::default();
+            self.labels = pdfium
+                .load_pdf_from_file(path, None)
+                .ok()
+                .map(|document| (0..page_count(&document)).map(|index| page_label(&document, index)).collect());
+            self.document = Some(key);
+        }
+        self.labels.as_deref()
+    }
+}
+
+// The page's label where it says more than the page number, e.g. "xii" but not "3" for the third page,
+// from the labels of the document's pages
+pub fn distinct_label(labels: &[Option<String>], index: u32) -> Option<String> {
+    let label = labels.get(index as usize)?.as_ref()?;
+    says_more(label, index).then(|| label.clone())
+}
+
+fn says_more(label: &str, index: u32) -> bool {
+    label.trim() != (index + 1).to_string()
+}
+
+// Finds the physical page index for what the user typed. A matching page label wins, since labeled
+// documents number their pages by label, otherwise the input is read as a page number starting at 1.
+// labels has one for each page of the document
+pub fn resolve_page(labels: &[Option<String>], input: &str) -> Option<u32> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    let by_label = labels
+        .iter()
+        .position(|label| label.as_deref().is_some_and(|label| label.eq_ignore_ascii_case(input)));
+    if let Some(index) = by_label {
+        return Some(index as u32);
+    }
+    match input.parse::<u32>() {
+        Ok(number) if number >= 1 && number as usize <= labels.len() => Some(number - 1),
+        _ => None,
+    }
+}
+
+// What paging on from the last page, or back from the first, does
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum PageBoundary {
+        // stay on the page
+        #[default]
+        Stop => "Stop",
+        // carry on at the other end of the document
+        Wrap => "Wrap around",
+        // stay on the page and say the end was reached
+        Notify => "Notify",
+    }
+}
+
+// How the page indicator numbers the page. Where the PDF has no label for the page, or the label is just
+// its number, it shows the number whatever the choice
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum PageIndicator {
+        // "3 of 250"
+        Number => "Number",
+        // "xii"
+        Label => "Label",
+        // "xii (3 of 250)"
+        #[default]
+        Both => "Both",
+    }
+}
+
+impl PageIndicator {
+    // The indicator's text for the page at index of count, label empty where distinct_label has none
+    pub fn text(&self, index: u32, count: u32, label: &str) -> String {
+        let number = format!("{} of {}", index + 1, count);
+        match self {
+            _ if label.is_empty() => number,
+            PageIndicator::Number => number,
+            PageIndicator::Label => label.to_string(),
+            PageIndicator::Both => format!("{} ({})", label, number),
+        }
+    }
+}
+
+// Which way a document's pages run. Right to left, for Arabic, Hebrew and manga, the page after the
+// current one is to its left, so the left arrow and button turn forward
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum ReadingDirection {
+        #[default]
+        LeftToRight => "Left to right",
+        RightToLeft => "Right to left",
+    }
+}
+
+impl ReadingDirection {
+    // Whether turning toward the right of the screen goes forward through the document, or turning
+    // toward the left when rightward is false
+    pub fn is_forward(&self, rightward: bool) -> bool {
+        rightward == (*self == ReadingDirection::LeftToRight)
+    }
+}
+
+// The page next to current in a document of count pages, forward or back, or None to stay where it
+// is. At either end the boundary decides, a one page document never moves
+pub fn step(current: u32, count: u32, forward: bool, boundary: PageBoundary) -> Option<u32> {
+    let next = if forward {
+        current.checked_add(1).filter(|&next| next < count)
+    } else {
+        current.checked_sub(1)
+    };
+    match next {
+        Some(page) => Some(page),
+        None if boundary == PageBoundary::Wrap && count > 1 => Some(if forward { 0 } else { count - 1 }),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Roman numeral front matter, a body numbered from 1 and an appendix
+    fn labeled() -> Vec<Option<String>> {
+        ["i", "ii", "iii", "1", "2", "3", "A-1", "A-2"].iter().map(|label| Some(label.to_string())).collect()
+    }
+
+    #[test]
+    fn labels_find_their_page() {
+        assert_eq!(resolve_page(&labeled(), "iii"), Some(2));
+        assert_eq!(resolve_page(&labeled(), "XII"), None);
+        assert_eq!(resolve_page(&labeled(), " a-2 "), Some(7));
+    }
+
+    #[test]
+    fn numbers_that_are_labels_go_to_the_labeled_page() {
+        // "1" is the first page of the body, not the cover
+        assert_eq!(resolve_page(&labeled(), "1"), Some(3));
+    }
+
+    #[test]
+    fn other_numbers_are_physical_pages() {
+        assert_eq!(resolve_page(&labeled(), "8"), Some(7));
+        assert_eq!(resolve_page(&labeled(), "9"), None);
+        assert_eq!(resolve_page(&labeled(), "0"), None);
+    }
+
+    #[test]
+    fn documents_without_labels_use_page_numbers() {
+        let unlabeled = vec![None; 5];
+        assert_eq!(resolve_page(&unlabeled, "1"), Some(0));
+        assert_eq!(resolve_page(&unlabeled, "5"), Some(4));
+        assert_eq!(resolve_page(&unlabeled, "ii"), None);
+        assert_eq!(resolve_page(&unlabeled, ""), None);
+    }
+
+    #[test]
+    fn indices_past_the_pdfium_range_are_out_of_bounds() {
+        assert_eq!(pdfium_index(0).unwrap(), 0);
+        assert_eq!(pdfium_index(u32::from(u16::MAX)).unwrap(), u16::MAX);
+        assert!(matches!(pdfium_index(u32::from(u16::MAX) + 1), Err(PdfiumError::PageIndexOutOfBounds)));
+    }
+
+    #[test]
+    fn paging_crosses_the_u16_boundary() {
+        let last_u16 = u32::from(u16::MAX);
+        assert_eq!(step(last_u16, 70_000, true, PageBoundary::Stop), Some(last_u16 + 1));
+        assert_eq!(step(last_u16 + 1, 70_000, false, PageBoundary::Stop), Some(last_u16));
+        assert_eq!(resolve_page(&vec![None; 70_000], "70000"), Some(69_999));
+    }
+
+    #[test]
+    fn paging_never_overflows() {
+        assert_eq!(step(u32::MAX, u32::MAX, true, PageBoundary::Stop), None);
+        assert_eq!(step(0, u32::MAX, false, PageBoundary::Stop), None);
+    }
+
+    #[test]
+    fn stop_stays_at_either_end() {
+        assert_eq!(step(9, 10, true, PageBoundary::Stop), None);
+        assert_eq!(step(0, 10, false, PageBoundary::Stop), None);
+        assert_eq!(step(4, 10, true, PageBoundary::Stop), Some(5));
+    }
+
+    #[test]
+    fn wrap_carries_on_at_the_other_end() {
+        assert_eq!(step(9, 10, true, PageBoundary::Wrap), Some(0));
+        assert_eq!(step(0, 10, false, PageBoundary::Wrap), Some(9));
+        assert_eq!(step(4, 10, false, PageBoundary::Wrap), Some(3));
+    }
+
+    #[test]
+    fn notify_stays_for_the_caller_to_say_so() {
+        assert_eq!(step(9, 10, true, PageBoundary::Notify), None);
+        assert_eq!(step(0, 10, false, PageBoundary::Notify), None);
+        assert_eq!(step(0, 10, true, PageBoundary::Notify), Some(1));
+    }
+
+    #[test]
+    fn a_single_page_never_moves() {
+        for boundary in [PageBoundary::Stop, PageBoundary::Wrap, PageBoundary::Notify] {
+            assert_eq!(step(0, 1, true, boundary), None);
+            assert_eq!(step(0, 1, false, boundary), None);
+        }
+    }
+
+    #[test]
+    fn right_to_left_turns_forward_to_the_left() {
+        let direction = ReadingDirection::RightToLeft;
+        assert!(direction.is_forward(false));
+        assert!(!direction.is_forward(true));
+        assert_eq!(step(3, 10, direction.is_forward(false), PageBoundary::Stop), Some(4));
+        assert_eq!(step(3, 10, direction.is_forward(true), PageBoundary::Stop), Some(2));
+    }
+
+    #[test]
+    fn left_to_right_turns_forward_to_the_right() {
+        let direction = ReadingDirection::LeftToRight;
+        assert!(direction.is_forward(true));
+        assert_eq!(step(3, 10, direction.is_forward(false), PageBoundary::Stop), Some(2));
+    }
+
+    #[test]
+    fn right_to_left_ends_on_the_left() {
+        let direction = ReadingDirection::RightToLeft;
+        // the last page is reached turning left, the first turning right
+        assert_eq!(step(9, 10, direction.is_forward(false), PageBoundary::Stop), None);
+        assert_eq!(step(9, 10, direction.is_forward(false), PageBoundary::Wrap), Some(0));
+        assert_eq!(step(0, 10, direction.is_forward(true), PageBoundary::Wrap), Some(9));
+    }
+
+    #[test]
+    fn labels_that_are_the_page_number_say_nothing_more() {
+        let distinct: Vec<bool> = labeled()
+            .iter()
+            .enumerate()
+            .map(|(index, label)| says_more(label.as_deref().unwrap(), index as u32))
+            .collect();
+        // the body's "1" is the fourth page, so it still tells the reader something
+        assert_eq!(distinct, [true; 8]);
+        assert!(!says_more("3", 2));
+        assert!(!says_more(" 3 ", 2));
+    }
+
+    #[test]
+    fn distinct_labels_are_looked_up_by_page() {
+        let labels = vec![Some("ii".to_string()), Some("2".to_string()), None];
+        assert_eq!(distinct_label(&labels, 0), Some("ii".to_string()));
+        assert_eq!(distinct_label(&labels, 1), None);
+        assert_eq!(distinct_label(&labels, 2), None);
+        assert_eq!(distinct_label(&labels, 3), None);
+    }
+
+    #[test]
+    fn the_indicator_shows_what_the_setting_picks() {
+        assert_eq!(PageIndicator::Number.text(2, 250, "xii"), "3 of 250");
+        assert_eq!(PageIndicator::Label.text(2, 250, "xii"), "xii");
+        assert_eq!(PageIndicator::Both.text(2, 250, "xii"), "xii (3 of 250)");
+    }
+
+    #[test]
+    fn pages_without_a_label_show_their_number() {
+        for indicator in [PageIndicator::Number, PageIndicator::Label, PageIndicator::Both] {
+            assert_eq!(indicator.text(2, 250, ""), "3 of 250");
+        }
+    }
+}
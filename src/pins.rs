@@ -0,0 +1,120 @@
+use crate::interface::FileManager;
+use crate::measure::ShownArea;
+use crate::{App, BackendPDF, PagePin};
+use serde::{Deserialize, Serialize};
+use slint::{ComponentHandle, VecModel};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+// A text comment pinned to a point of a page, kept in database.json apart from the highlights. x and y
+// are fractions of the whole page's width and height from its top left corner, so the pin stays on
+// the same spot at any zoom and with any crop
+#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize)]
+pub struct CommentPin {
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+}
+
+// The pins of a page along with their positions among all of the document's pins, which is how the
+// UI refers back to one
+pub fn on_page(pins: &[CommentPin], page: u32) -> Vec<(usize, &CommentPin)> {
+    pins.iter().enumerate().filter(|(_, pin)| pin.page == page).collect()
+}
+
+// Registers the callbacks for the comments pinned to points of the page
+pub fn bind(
+    app: &App,
+    file_manager: &Arc<Mutex<FileManager>>,
+    shown_area: &Rc<Cell<ShownArea>>,
+    schedule_save: &Rc<dyn Fn()>,
+) {
+    /*  CALLBACK:
+        Returns the comment pins of a page where the last render shows them. Pins cropped away are
+        left out
+
+        # Arguments
+        * 'page_index' - 0-based page the pins are on
+
+        # Return
+        * A Slint vector type of pins, each at fractions of the displayed image's size
+    */
+    app.global::<BackendPDF>().on_get_pins({
+        let cloned_file_manager = file_manager.clone();
+        let shown_area = shown_area.clone();
+        move |page_index| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let area = shown_area.get();
+            let pins: Vec<PagePin> = on_page(file_manager.get_cur_file_info().get_pins(), page_index.max(0) as u32)
+                .into_iter()
+                .filter_map(|(index, pin)| {
+                    let (x, y) = area.shown_fraction(pin.x, pin.y)?;
+                    Some(PagePin {
+                        index: index as i32,
+                        x,
+                        y,
+                        text: pin.text.as_str().into(),
+                    })
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(pins))
+        }
+    });
+
+    /*  CALLBACK:
+        Pins a comment to a point of a page. Nothing is pinned without text or to a read-only document
+
+        # Arguments
+        * 'page_index' - 0-based page the pin goes on
+        * 'x', 'y' - the point as fractions of the displayed image's width and height
+        * 'text' - the comment
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_add_comment({
+        let cloned_file_manager = file_manager.clone();
+        let shown_area = shown_area.clone();
+        let schedule_save = schedule_save.clone();
+        move |page_index, x, y, text| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            if file_manager.get_cur_file_info().get_read_only() || text.trim().is_empty() {
+                return;
+            }
+            let (x, y) = shown_area.get().page_fraction(x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+            file_manager.get_cur_file_info().add_pin(CommentPin {
+                page: page_index.max(0) as u32,
+                x,
+                y,
+                text: text.trim().to_string(),
+            });
+            schedule_save();
+        }
+    });
+
+    /*  CALLBACK:
+        Changes the text of a comment pin, empty text removes the pin
+
+        # Arguments
+        * 'index' - the pin, as get-pins numbered it
+        * 'text' - the new comment
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_edit_comment({
+        let cloned_file_manager = file_manager.clone();
+        let schedule_save = schedule_save.clone();
+        move |index, text| {
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            if file_manager.get_cur_file_info().get_read_only() || index < 0 {
+                return;
+            }
+            file_manager.get_cur_file_info().set_pin_text(index as usize, text.trim());
+            schedule_save();
+        }
+    });
+}
@@ -0,0 +1,165 @@
+use std::process::Command;
+
+// Opens a file or web address with the operating system's default application for it. On Windows it
+// goes to the shell directly rather than through cmd's start, which would run anything after a & or |
+// in the path or address as another command
+pub fn open_with_default(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    return shell_open(path);
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        // a relative path starting with - would be read as an option
+        let path = if path.starts_with('-') { format!("./{}", path) } else { path.to_string() };
+        let program = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+        match Command::new(program).arg(path).status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("No application could open the file ({})", status)),
+            Err(e) => Err(format!("Could not launch the default application: {}", e)),
+        }
+    }
+}
+
+// ShellExecuteW with the open verb, what double clicking the file in Explorer does
+#[cfg(target_os = "windows")]
+fn shell_open(path: &str) -> Result<(), String> {
+    use std::ffi::{c_void, OsStr};
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn ShellExecuteW(
+            window: *mut c_void,
+            operation: *const u16,
+            file: *const u16,
+            parameters: *const u16,
+            directory: *const u16,
+            show: i32,
+        ) -> *mut c_void;
+    }
+    const SW_SHOWNORMAL: i32 = 1;
+
+    let wide = |text: &str| OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let operation = wide("open");
+    let file = wide(path);
+    let result = unsafe {
+        ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // anything above 32 means it started, smaller values are error codes
+    match result as isize {
+        code if code > 32 => Ok(()),
+        code => Err(format!("No application could open the file (error {})", code)),
+    }
+}
+
+// Sends a PDF to the default printer through CUPS' lp, which exists on Linux and macOS. Windows has no
+// equivalent command, so callers fall back to open_with_default there
+pub fn print_file(path: &str, copies: u32) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        return Err("Printing directly is not supported on this platform".to_string());
+    }
+    let output = Command::new("lp")
+        .args(["-n", copies.to_string().as_str(), path])
+        .output()
+        .map_err(|e| format!("Could not run lp: {}", e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+}
+
+// Whether a process with the id is alive, used to tell a held lock from one left by a crash
+pub fn process_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return std::path::Path::new(&format!("/proc/{}", pid)).exists();
+    }
+    if cfg!(target_os = "windows") {
+        return match Command::new("tasklist").args(["/FI", format!("PID eq {}", pid).as_str()]).output() {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()),
+            Err(_) => false,
+        };
+    }
+    match Command::new("kill").args(["-0", pid.to_string().as_str()]).status() {
+        Ok(status) => status.success(),
+        Err(_) => false,
+    }
+}
+
+// Whether file lists on this desktop open an item on double click. Windows, macOS and most Linux
+// desktops do, KDE Plasma before version 6 opened on a single click
+pub fn double_click_opens() -> bool {
+    if cfg!(target_os = "linux") {
+        let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        let version = std::env::var("KDE_SESSION_VERSION").unwrap_or_default();
+        return !(desktop.contains("KDE") && version.parse::<u32>().is_ok_and(|version| version < 6));
+    }
+    true
+}
+
+// Whether the window can be kept above other apps. Wayland leaves stacking to the compositor and
+// ignores the request, X11, Windows and macOS honour it
+pub fn always_on_top_supported() -> bool {
+    if cfg!(target_os = "linux") {
+        let wayland = std::env::var("WAYLAND_DISPLAY").is_ok_and(|display| !display.is_empty());
+        let forced_x11 = std::env::var("WINIT_UNIX_BACKEND").is_ok_and(|backend| backend == "x11");
+        return !wayland || forced_x11;
+    }
+    true
+}
+
+// Starts the system voice reading text at about rate words per minute and returns without waiting,
+// the caller polls or kills the process. macOS has say, Windows the .NET speech synthesizer, and
+// Linux whichever of espeak-ng, espeak or speech-dispatcher is installed. The text comes from the
+// PDF, so it's passed on stdin where the program can read it there, never as an argument it could
+// take for an option like espeak's -w or say's -o, which write files
+pub fn speak(text: &str, rate: u32) -> Result<std::process::Child, String> {
+    if cfg!(target_os = "macos") {
+        let mut say = Command::new("say");
+        say.args(["-r", rate.to_string().as_str(), "-f", "-"]);
+        return spawn_reading(say, text).map_err(|e| format!("Could not run say: {}", e));
+    }
+    if cfg!(target_os = "windows") {
+        // SpeechSynthesizer.Rate runs from -10 to 10 around roughly 175 words per minute
+        let windows_rate = ((rate as i32 - 175) / 20).clamp(-10, 10);
+        let script = format!(
+            "Add-Type -AssemblyName System.Speech; $voice = New-Object System.Speech.Synthesis.SpeechSynthesizer; $voice.Rate = {}; $voice.Speak([Console]::In.ReadToEnd())",
+            windows_rate
+        );
+        let mut powershell = Command::new("powershell");
+        powershell.args(["-NoProfile", "-Command", script.as_str()]);
+        return spawn_reading(powershell, text).map_err(|e| format!("Could not run PowerShell: {}", e));
+    }
+    for program in ["espeak-ng", "espeak"] {
+        let mut espeak = Command::new(program);
+        espeak.args(["-s", rate.to_string().as_str(), "--stdin"]);
+        if let Ok(child) = spawn_reading(espeak, text) {
+            return Ok(child);
+        }
+    }
+    // spd-say only takes the text as an argument, so it goes after -- with any leading dashes dropped
+    // for good measure. It takes a rate from -100 to 100 instead, and -w makes it wait until the text
+    // is read
+    let dispatcher_rate = ((rate as i32 - 175) / 2).clamp(-100, 100);
+    Command::new("spd-say")
+        .args(["-w", "-r", dispatcher_rate.to_string().as_str(), "--", text.trim_start_matches('-')])
+        .spawn()
+        .map_err(|_| "No speech program found, install espeak-ng or speech-dispatcher".to_string())
+}
+
+// Starts the program and writes the text to its stdin, closing it so the program reads to the end
+fn spawn_reading(mut command: Command, text: &str) -> std::io::Result<std::process::Child> {
+    use std::io::Write;
+
+    let mut child = command.stdin(std::process::Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    Ok(child)
+}
@@ -0,0 +1,94 @@
+use crate::txt_file;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// Written while PDFer runs and removed on a clean exit, so finding it on startup means a crash
+pub const RECOVERY_FILE: &str = "recovery.json";
+
+// What is needed to put the session back: the open document and page, plus the notes buffer, which
+// database.json doesn't hold
+#[derive(Default, Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshot {
+    pub document: String,
+    pub page: u32,
+    pub notes_path: String,
+    pub notes: String,
+}
+
+impl Snapshot {
+    // Short description for the restore prompt
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.document.is_empty() {
+            parts.push(format!("{} at page {}", self.document, self.page + 1));
+        }
+        if !self.notes.is_empty() {
+            parts.push(format!("{} characters of notes", self.notes.chars().count()));
+        }
+        parts.join(", ")
+    }
+}
+
+pub fn write(filename: &str, snapshot: &Snapshot) {
+    let json = serde_json::to_string(snapshot).unwrap();
+    if let Err(e) = txt_file::write_to_file(filename, json.as_str()) {
+        eprintln!("Error writing recovery snapshot: {}", e);
+    }
+}
+
+// The snapshot left by a session that didn't exit cleanly, if any
+pub fn load_leftover(filename: &str) -> Option<Snapshot> {
+    let data = txt_file::read_file(filename).ok()?;
+    serde_json::from_str(data.as_str()).ok()
+}
+
+pub fn clear(filename: &str) {
+    let _ = fs::remove_file(filename);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::txt_file::temp_path;
+
+    #[test]
+    fn a_leftover_snapshot_is_offered_with_its_contents() {
+        let filename = temp_path("recovery.json");
+        write(
+            &filename,
+            &Snapshot {
+                document: "/books/a.pdf".to_string(),
+                page: 11,
+                notes_path: "/books/a.md".to_string(),
+                notes: "unsaved".to_string(),
+            },
+        );
+
+        let leftover = load_leftover(&filename).unwrap();
+        clear(&filename);
+        assert_eq!(leftover.document, "/books/a.pdf");
+        assert_eq!(leftover.page, 11);
+        assert_eq!(leftover.notes_path, "/books/a.md");
+        assert_eq!(leftover.describe(), "/books/a.pdf at page 12, 7 characters of notes");
+    }
+
+    #[test]
+    fn a_clean_exit_leaves_nothing_to_restore() {
+        let filename = temp_path("clean.json");
+        write(&filename, &Snapshot::default());
+        clear(&filename);
+        assert!(load_leftover(&filename).is_none());
+    }
+
+    #[test]
+    fn snapshots_from_older_versions_fill_in_missing_fields() {
+        let filename = temp_path("older.json");
+        txt_file::write_to_file(&filename, r#"{"document":"/books/a.pdf"}"#).unwrap();
+        let leftover = load_leftover(&filename).unwrap();
+        clear(&filename);
+        assert_eq!(leftover.page, 0);
+        assert_eq!(leftover.describe(), "/books/a.pdf at page 1");
+    }
+}
@@ -0,0 +1,764 @@
+use crate::options::named_options;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+
+// Pixel budget for one rendered page, the same area as the old fixed 2000x2000 box
+pub const MAX_RENDER_PIXELS: f32 = 4_000_000.0;
+// Neither side may exceed this, however thin the page is
+pub const MAX_RENDER_SIDE: f32 = 16384.0;
+// Lowest limit on a render's longest side the user may set. Memory grows with the square of the side:
+// a square page at 16384 pixels a side would be a 1 GB buffer, at 4096 it's 64 MB, which is why the
+// megapixel cap applies as well
+pub const MIN_RENDER_SIDE: u32 = 256;
+
+// Default cap in megapixels on a single page render, whatever the zoom, fit and crop ask for. At 4
+// bytes a pixel 64 megapixels is a 256 MB buffer
+pub const DEFAULT_RENDER_CAP: u32 = 64;
+// Highest High Contrast intensity, and the one it starts at
+pub const MAX_CONTRAST: u32 = 100;
+pub const DEFAULT_CONTRAST: u32 = 60;
+// Side in pixels of a checkerboard square, and its light and dark shade
+const CHECKER_SIZE: u32 = 8;
+const CHECKER_SHADES: [u8; 2] = [255, 204];
+
+// Shrinks a render size to within max_pixels and a longest side of max_side, which is never more than
+// MAX_RENDER_SIDE, keeping its aspect ratio. None when the size already fits, so the caller can tell
+// the page when it's shown at less than it asked for
+pub fn clamp_render_size(width: i32, height: i32, max_pixels: f32, max_side: f32) -> Option<(i32, i32)> {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+    let scale = (max_pixels / (width * height))
+        .sqrt()
+        .min(max_side.min(MAX_RENDER_SIDE) / width.max(height));
+    if scale >= 1.0 {
+        return None;
+    }
+    Some(((width * scale).floor().max(1.0) as i32, (height * scale).floor().max(1.0) as i32))
+}
+
+// Computes the pixel size to render a page of the given size (in points) at. The page's aspect ratio
+// is kept and the total pixel count is capped instead of each side, so tall scrolls and wide spreads
+// aren't shrunk just because one side is long.
+pub fn render_target(page_width: f32, page_height: f32, max_pixels: f32) -> (i32, i32) {
+    if page_width <= 0.0 || page_height <= 0.0 {
+        return (1, 1);
+    }
+    let scale = (max_pixels / (page_width * page_height))
+        .sqrt()
+        .min(MAX_RENDER_SIDE / page_width.max(page_height));
+    let width = (page_width * scale).round().max(1.0) as i32;
+    let height = (page_height * scale).round().max(1.0) as i32;
+    (width, height)
+}
+
+// Anti-aliasing presets trading sharpness for render speed
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum RenderQuality {
+        // no smoothing at all, fastest
+        Fast => "Fast",
+        // smoothed text, images and paths
+        #[default]
+        Balanced => "Balanced",
+        // smoothing plus LCD subpixel text for sharper text on most displays
+        Crisp => "Crisp",
+    }
+}
+
+impl RenderQuality {
+    // Applies the preset's flags to a render config
+    fn apply(&self, config: PdfRenderConfig) -> PdfRenderConfig {
+        let smooth = *self != RenderQuality::Fast;
+        config
+            .set_text_smoothing(smooth)
+            .set_image_smoothing(smooth)
+            .set_path_smoothing(smooth)
+            .use_lcd_text_rendering(*self == RenderQuality::Crisp)
+    }
+}
+
+// Colors the rendered page is shown in. Grayscale suits e-ink like reading and checking a page before
+// printing it in black and white, High Contrast pushes the page toward black text on white paper for
+// low vision reading
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum PageColors {
+        #[default]
+        Normal => "Normal",
+        Grayscale => "Grayscale",
+        HighContrast => "High Contrast",
+    }
+}
+
+impl PageColors {
+    // The mode after this one among those in the cycle, wrapping around. A mode left out of the cycle
+    // goes to its first, an empty cycle keeps the mode
+    pub fn next_in(&self, cycle: &[PageColors]) -> PageColors {
+        match cycle.iter().position(|colors| colors == self) {
+            Some(index) => cycle[(index + 1) % cycle.len()],
+            None => cycle.first().copied().unwrap_or(*self),
+        }
+    }
+
+    // Recolors a rendered page in place. contrast is the High Contrast intensity, see high_contrast
+    pub fn apply(&self, image: &mut image::RgbaImage, contrast: u32) {
+        match self {
+            PageColors::Normal => {}
+            PageColors::Grayscale => {
+                for pixel in image.pixels_mut() {
+                    let gray = luminance(pixel.0[0], pixel.0[1], pixel.0[2]);
+                    pixel.0 = [gray, gray, gray, pixel.0[3]];
+                }
+            }
+            PageColors::HighContrast => {
+                for pixel in image.pixels_mut() {
+                    let gray = high_contrast(luminance(pixel.0[0], pixel.0[1], pixel.0[2]), contrast);
+                    pixel.0 = [gray, gray, gray, pixel.0[3]];
+                }
+            }
+        }
+    }
+}
+
+// A gray value pushed away from mid gray, the further the higher the intensity (1 to 100). Around 50
+// it doubles the contrast, at 100 every pixel is black or white
+pub fn high_contrast(gray: u8, intensity: u32) -> u8 {
+    let intensity = intensity.clamp(1, MAX_CONTRAST);
+    let gain = MAX_CONTRAST as f32 / (MAX_CONTRAST + 1 - intensity) as f32;
+    (128.0 + (gray as f32 - 128.0) * gain).round().clamp(0.0, 255.0) as u8
+}
+
+// Perceived brightness of a color, by the Rec. 709 weights: green looks far brighter than blue
+pub fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((2126 * r as u32 + 7152 * g as u32 + 722 * b as u32 + 5000) / 10000) as u8
+}
+
+// What the transparent parts of a page are drawn on. Most pages are opaque and look the same in all
+// three, Theme lets the viewer's background show through and Checkerboard makes the transparency
+// itself visible
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum PageBackground {
+        #[default]
+        White => "White",
+        Theme => "Theme",
+        Checkerboard => "Checkerboard",
+    }
+}
+
+impl PageBackground {
+    // pdfium clears the page to this before drawing it, anything but white is left transparent
+    fn clear_color(&self) -> PdfColor {
+        match self {
+            PageBackground::White => PdfColor::WHITE,
+            _ => PdfColor::new(0, 0, 0, 0),
+        }
+    }
+
+    // Draws a page rendered on a transparent background onto the checkerboard, other backgrounds are
+    // left as they are
+    fn fill(&self, image: &mut image::RgbaImage) {
+        if *self != PageBackground::Checkerboard {
+            return;
+        }
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let shade = CHECKER_SHADES[((x / CHECKER_SIZE + y / CHECKER_SIZE) % 2) as usize] as u32;
+            let [r, g, b, a] = pixel.0.map(u32::from);
+            let over = |channel: u32| ((channel * a + shade * (255 - a) + 127) / 255) as u8;
+            pixel.0 = [over(r), over(g), over(b), 255];
+        }
+    }
+}
+
+// How the page is sized to the viewer. Fixed renders at the pixel budget whatever the window size,
+// the others re-render to fill the pane as it is resized
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum PageFit {
+        #[default]
+        Fixed => "Fixed",
+        // the whole page visible
+        Page => "Fit page",
+        // page as wide as the pane, scrolling down
+        Width => "Fit width",
+        // page as tall as the pane, scrolling across
+        Height => "Fit height",
+    }
+}
+
+// Largest scale from points to pixels a page of the given size may be rendered at within limit, the
+// render cap in pixels and the longest side a render may have
+fn limit_scale(page_width: f32, page_height: f32, limit: (f32, f32)) -> f32 {
+    let (max_pixels, max_side) = limit;
+    (max_pixels / (page_width * page_height).max(1.0))
+        .sqrt()
+        .min(max_side.min(MAX_RENDER_SIDE) / page_width.max(page_height).max(1.0))
+}
+
+// Pixel size that fits a page of the given size (in points) to a pane of the given size (in physical
+// pixels). The render limit still applies, so a huge pane can't make a huge render.
+// None for Fixed, or while the pane has no size yet
+pub fn fit_target(page_width: f32, page_height: f32, pane: (f32, f32), fit: PageFit, limit: (f32, f32)) -> Option<(i32, i32)> {
+    let (pane_width, pane_height) = pane;
+    if page_width <= 0.0 || page_height <= 0.0 || pane_width < 1.0 || pane_height < 1.0 {
+        return None;
+    }
+    let scale = match fit {
+        PageFit::Fixed => return None,
+        PageFit::Page => (pane_width / page_width).min(pane_height / page_height),
+        PageFit::Width => pane_width / page_width,
+        PageFit::Height => pane_height / page_height,
+    };
+    let scale = scale.min(limit_scale(page_width, page_height, limit));
+    let width = (page_width * scale).round().max(1.0) as i32;
+    let height = (page_height * scale).round().max(1.0) as i32;
+    Some((width, height))
+}
+
+// Pixel size a page is shown at: by the zoom when there is one, otherwise by the fit mode, with the
+// part of the page that is shown, as fractions of its width and height after cropping, filling the
+// pane. Fixed, or a pane not measured yet, renders at the pixel budget. limit is the user's render cap
+// in pixels and longest side, which a zoom or fit may go up to
+#[allow(clippy::too_many_arguments)]
+pub fn display_size(
+    page_width: f32,
+    page_height: f32,
+    zoom: Option<u32>,
+    scale_factor: f32,
+    pane: (f32, f32),
+    fit: PageFit,
+    shown: (f32, f32),
+    limit: (f32, f32),
+) -> (i32, i32) {
+    if let Some(zoom) = zoom {
+        return zoom_target(page_width, page_height, zoom, scale_factor, limit);
+    }
+    let (shown_width, shown_height) = shown;
+    match fit_target(page_width * shown_width, page_height * shown_height, pane, fit, limit) {
+        Some((width, height)) => (
+            (width as f32 / shown_width).round() as i32,
+            (height as f32 / shown_height).round() as i32,
+        ),
+        None => render_target(page_width, page_height, MAX_RENDER_PIXELS.min(limit.0)),
+    }
+}
+
+// Zoom levels in percent the zoom buttons step through. The render limit caps the render of a large
+// page before the top level is reached
+pub const ZOOM_LEVELS: [u32; 7] = [50, 75, 100, 125, 150, 175, 200];
+
+// Which zoom a document is shown at, in percent. The zoom saved for the document wins, then the
+// default zoom for documents without one. None when neither is set, then the page fit mode sizes the
+// page, so the order is: saved zoom > default zoom > fit
+pub fn resolve_zoom(saved: Option<u32>, default: Option<u32>) -> Option<u32> {
+    saved.or(default)
+}
+
+// Pixel size of a page of the given size (in points) at a zoom in percent, 100% being its printed size
+// on a screen at the window's scale factor. The render limit still applies
+pub fn zoom_target(page_width: f32, page_height: f32, zoom: u32, scale_factor: f32, limit: (f32, f32)) -> (i32, i32) {
+    let scale = points_to_pixels(zoom, scale_factor).min(limit_scale(page_width, page_height, limit));
+    let width = (page_width * scale).round().max(1.0) as i32;
+    let height = (page_height * scale).round().max(1.0) as i32;
+    (width, height)
+}
+
+// Physical pixels per point at a zoom: a point is 1/72 inch and logical pixels are 1/96 inch
+fn points_to_pixels(zoom: u32, scale_factor: f32) -> f32 {
+    zoom as f32 / 100.0 * 96.0 / 72.0 * scale_factor
+}
+
+// The zoom in percent a page rendered at the given width (in physical pixels) is shown at
+pub fn zoom_of(page_width: f32, rendered_width: i32, scale_factor: f32) -> f32 {
+    if page_width <= 0.0 {
+        return 100.0;
+    }
+    rendered_width as f32 / (page_width * points_to_pixels(1, scale_factor))
+}
+
+// The zoom in percent at which the shown part of a page, its width in points after cropping, fills a
+// pane the given width in physical pixels
+pub fn fit_width_zoom(shown_width: f32, pane_width: f32, scale_factor: f32) -> u32 {
+    if shown_width <= 0.0 || pane_width < 1.0 {
+        return 100;
+    }
+    (pane_width / (shown_width * points_to_pixels(1, scale_factor))).round().max(1.0) as u32
+}
+
+// "Fit" for no zoom, otherwise e.g. "125%", as the default zoom picker lists them
+pub fn zoom_name(zoom: Option<u32>) -> String {
+    match zoom {
+        Some(zoom) => format!("{}%", zoom),
+        None => "Fit".to_string(),
+    }
+}
+
+// Reverse of zoom_name, None for a name that isn't "Fit" or a zoom level
+pub fn zoom_from_name(name: &str) -> Option<Option<u32>> {
+    if name == "Fit" {
+        return Some(None);
+    }
+    let zoom = name.strip_suffix('%')?.parse().ok()?;
+    ZOOM_LEVELS.contains(&zoom).then_some(Some(zoom))
+}
+
+// The next zoom level above or below the zoom in percent, staying at the ends
+pub fn step_zoom(zoom: f32, up: bool) -> u32 {
+    if up {
+        ZOOM_LEVELS.iter().copied().find(|&level| level as f32 > zoom + 0.5).unwrap_or(ZOOM_LEVELS[ZOOM_LEVELS.len() - 1])
+    } else {
+        ZOOM_LEVELS.iter().rev().copied().find(|&level| (level as f32) < zoom - 0.5).unwrap_or(ZOOM_LEVELS[0])
+    }
+}
+
+// Loads the document once to count its pages, None if it can't be opened
+pub fn document_page_count(path: &str) -> Option<u32> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None).ok()?;
+    Some(u32::from(document.pages().len()))
+}
+
+// Pixel size render_page renders the page at
+pub fn page_render_size(page: &PdfPage) -> (i32, i32) {
+    render_target(page.width().value, page.height().value, MAX_RENDER_PIXELS)
+}
+
+// Renders a page to an image sized by render_target, with the given anti-aliasing preset, and with
+// or without the annotations the document carries (notes, stamps, its own highlights). Fails on
+// pages pdfium can't draw, e.g. corrupt content streams
+pub fn render_page(page: &PdfPage, quality: RenderQuality, annotations: bool) -> Result<image::RgbaImage, PdfiumError> {
+    let (width, height) = page_render_size(page);
+    render_page_at(page, quality, annotations, width, height)
+}
+
+// Renders a page to an image of the given size on white, see render_page
+pub fn render_page_at(
+    page: &PdfPage,
+    quality: RenderQuality,
+    annotations: bool,
+    width: i32,
+    height: i32,
+) -> Result<image::RgbaImage, PdfiumError> {
+    render_page_on(page, quality, annotations, PageBackground::White, width, height)
+}
+
+// Renders a page to an image of the given size with its transparent parts on the given background
+pub fn render_page_on(
+    page: &PdfPage,
+    quality: RenderQuality,
+    annotations: bool,
+    background: PageBackground,
+    width: i32,
+    height: i32,
+) -> Result<image::RgbaImage, PdfiumError> {
+    let render_config = quality.apply(
+        PdfRenderConfig::new()
+            .set_target_size(width, height)
+            .set_clear_color(background.clear_color())
+            .render_form_data(true)
+            .render_annotations(annotations),
+    );
+
+    let mut image = page.render_with_config(&render_config)?.as_image().into_rgba8();
+    background.fill(&mut image);
+    Ok(image)
+}
+
+// Renders the part of a page between two corners, given as fractions of the page's width and height
+// and clamped to the page, at dpi dots per inch. The whole page is rendered and cut down, kept within
+// max_pixels, so a high dpi on a large page may come out at less. None for a region with no area
+pub fn render_region(
+    page: &PdfPage,
+    quality: RenderQuality,
+    annotations: bool,
+    corners: ((f32, f32), (f32, f32)),
+    dpi: u32,
+    max_pixels: f32,
+) -> Result<Option<image::RgbaImage>, PdfiumError> {
+    let ((x0, y0), (x1, y1)) = corners;
+    let (left, right) = (x0.min(x1).clamp(0.0, 1.0), x0.max(x1).clamp(0.0, 1.0));
+    let (top, bottom) = (y0.min(y1).clamp(0.0, 1.0), y0.max(y1).clamp(0.0, 1.0));
+    let wanted_width = (page.width().value / 72.0 * dpi as f32).round() as i32;
+    let wanted_height = (page.height().value / 72.0 * dpi as f32).round() as i32;
+    let (width, height) = clamp_render_size(wanted_width, wanted_height, max_pixels, MAX_RENDER_SIDE).unwrap_or((wanted_width, wanted_height));
+    let (left, right) = ((left * width as f32) as u32, (right * width as f32) as u32);
+    let (top, bottom) = ((top * height as f32) as u32, (bottom * height as f32) as u32);
+    if right <= left || bottom <= top {
+        return Ok(None);
+    }
+    let image = render_page_at(page, quality, annotations, width.max(1), height.max(1))?;
+    let (right, bottom) = (right.min(image.width()), bottom.min(image.height()));
+    Ok(Some(image::imageops::crop_imm(&image, left, top, right - left, bottom - top).to_image()))
+}
+
+// Why the current page couldn't be shown, whether showing it again might work, and how many renders
+// in a row failed this way
+#[derive(Clone)]
+pub struct RenderFailure {
+    pub message: String,
+    pub transient: bool,
+    pub attempts: u32,
+}
+
+impl RenderFailure {
+    pub fn new(message: String, error: &PdfiumError) -> Self {
+        Self {
+            message,
+            transient: is_transient(error),
+            attempts: 1,
+        }
+    }
+
+    // Counts on from the failure of the previous render if it failed the same way
+    pub fn after(mut self, previous: Option<&RenderFailure>) -> Self {
+        if let Some(previous) = previous.filter(|previous| previous.message == self.message) {
+            self.attempts = previous.attempts + 1;
+        }
+        self
+    }
+
+    // Whether to suggest the system's PDF viewer instead: the page fails the same way every time, or
+    // retrying didn't help
+    pub fn offers_external(&self) -> bool {
+        !self.transient || self.attempts >= 2
+    }
+}
+
+// A file that went away or a share that dropped can come back, and a bitmap that couldn't be
+// allocated may fit later. A page pdfium can't parse fails the same way every time
+pub fn is_transient(error: &PdfiumError) -> bool {
+    matches!(
+        error,
+        PdfiumError::IoError(_)
+            | PdfiumError::ImageError
+            | PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::FileError | PdfiumInternalError::Unknown)
+    )
+}
+
+// Stands in for a page that couldn't be rendered: grey paper crossed out in red, so a batch (thumbnails,
+// the mini-map) can carry on and still show where the broken page is
+pub fn error_placeholder(width: u32, height: u32) -> image::RgbaImage {
+    let (width, height) = (width.max(1), height.max(1));
+    let thickness = (width.min(height) / 40).max(1) as i64;
+    image::RgbaImage::from_fn(width, height, |x, y| {
+        // distance from each diagonal, in pixels along x
+        let across = (x as i64 * height as i64 / width as i64 - y as i64).abs();
+        let down = ((width - 1 - x) as i64 * height as i64 / width as i64 - y as i64).abs();
+        if across <= thickness || down <= thickness {
+            image::Rgba([204, 51, 51, 255])
+        } else {
+            image::Rgba([225, 225, 225, 255])
+        }
+    })
+}
+
+pub fn to_slint_image(image: &image::RgbaImage) -> Image {
+    let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+    );
+
+    Image::from_rgba8(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_keeps_aspect(page: (f32, f32), target: (i32, i32)) {
+        let (width, height) = (target.0 as f32, target.1 as f32);
+        // each side is rounded, so the ratio is off by at most one pixel of the shorter side
+        let tolerance = 1.0 / width.min(height);
+        let ratio = page.0 / page.1;
+        assert!((width / height - ratio).abs() / ratio <= tolerance, "{:?} for {:?}", target, page);
+    }
+
+    #[test]
+    fn ordinary_pages_fill_the_pixel_budget() {
+        let target = render_target(612.0, 792.0, MAX_RENDER_PIXELS);
+        assert_keeps_aspect((612.0, 792.0), target);
+        let pixels = (target.0 * target.1) as f32;
+        assert!(pixels <= MAX_RENDER_PIXELS * 1.001 && pixels > MAX_RENDER_PIXELS * 0.99);
+    }
+
+    #[test]
+    fn wide_spreads_are_not_clamped_to_a_square_box() {
+        let target = render_target(3000.0, 300.0, MAX_RENDER_PIXELS);
+        assert_keeps_aspect((3000.0, 300.0), target);
+        assert!(target.0 > 2000);
+    }
+
+    #[test]
+    fn tall_scrolls_stop_at_the_longest_side() {
+        let target = render_target(100.0, 20000.0, MAX_RENDER_PIXELS);
+        assert_keeps_aspect((100.0, 20000.0), target);
+        assert_eq!(target.1, MAX_RENDER_SIDE as i32);
+    }
+
+    #[test]
+    fn huge_pages_stay_within_the_budget() {
+        let target = render_target(1.0e6, 1.0e6, MAX_RENDER_PIXELS);
+        assert_eq!(target, (2000, 2000));
+        let target = render_target(1.0e7, 1.0, MAX_RENDER_PIXELS);
+        assert!(target.0 as f32 <= MAX_RENDER_SIDE);
+        assert_eq!(target.1, 1);
+    }
+
+    #[test]
+    fn empty_pages_render_a_single_pixel() {
+        assert_eq!(render_target(0.0, 792.0, MAX_RENDER_PIXELS), (1, 1));
+        assert_eq!(render_target(612.0, -1.0, MAX_RENDER_PIXELS), (1, 1));
+    }
+
+    #[test]
+    fn placeholders_stand_in_at_the_page_size() {
+        let placeholder = error_placeholder(300, 400);
+        assert_eq!(placeholder.dimensions(), (300, 400));
+        // crossed out corner to corner
+        assert_eq!(placeholder.get_pixel(0, 0), placeholder.get_pixel(150, 200));
+        assert_ne!(placeholder.get_pixel(0, 0), placeholder.get_pixel(150, 0));
+        assert_eq!(error_placeholder(0, 0).dimensions(), (1, 1));
+    }
+
+    fn corrupt_page() -> PdfiumError {
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::FormatError)
+    }
+
+    fn dropped_share() -> PdfiumError {
+        PdfiumError::IoError(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+    }
+
+    #[test]
+    fn corrupt_pages_fail_for_good() {
+        assert!(!is_transient(&corrupt_page()));
+        assert!(!is_transient(&PdfiumError::PageIndexOutOfBounds));
+        assert!(RenderFailure::new("bad page".to_string(), &corrupt_page()).offers_external());
+    }
+
+    #[test]
+    fn unreadable_files_are_worth_a_retry() {
+        assert!(is_transient(&dropped_share()));
+        assert!(is_transient(&PdfiumError::ImageError));
+        let failure = RenderFailure::new("share dropped".to_string(), &dropped_share());
+        assert!(!failure.offers_external());
+    }
+
+    #[test]
+    fn retries_that_fail_the_same_way_are_counted() {
+        let first = RenderFailure::new("share dropped".to_string(), &dropped_share());
+        let second = RenderFailure::new("share dropped".to_string(), &dropped_share()).after(Some(&first));
+        assert_eq!(second.attempts, 2);
+        assert!(second.offers_external());
+        // failing differently starts over
+        let other = RenderFailure::new("out of memory".to_string(), &PdfiumError::ImageError).after(Some(&second));
+        assert_eq!(other.attempts, 1);
+    }
+
+    #[test]
+    fn a_saved_zoom_beats_the_default() {
+        assert_eq!(resolve_zoom(Some(150), Some(125)), Some(150));
+        assert_eq!(resolve_zoom(Some(150), None), Some(150));
+    }
+
+    #[test]
+    fn new_documents_open_at_the_default_zoom() {
+        assert_eq!(resolve_zoom(None, Some(125)), Some(125));
+    }
+
+    #[test]
+    fn without_either_the_page_is_fit() {
+        assert_eq!(resolve_zoom(None, None), None);
+    }
+
+    #[test]
+    fn default_zoom_names_round_trip() {
+        for zoom in ZOOM_LEVELS.iter().copied().map(Some).chain([None]) {
+            assert_eq!(zoom_from_name(&zoom_name(zoom)), Some(zoom));
+        }
+        assert_eq!(zoom_from_name("130%"), None);
+    }
+
+    #[test]
+    fn zoom_steps_stop_at_the_ends() {
+        assert_eq!(step_zoom(100.0, true), 125);
+        assert_eq!(step_zoom(110.0, false), 100);
+        assert_eq!(step_zoom(200.0, true), 200);
+        assert_eq!(step_zoom(50.0, false), 50);
+    }
+
+    #[test]
+    fn luminance_weighs_green_over_red_over_blue() {
+        assert_eq!(luminance(0, 0, 0), 0);
+        assert_eq!(luminance(255, 255, 255), 255);
+        assert_eq!(luminance(255, 0, 0), 54);
+        assert_eq!(luminance(0, 255, 0), 182);
+        assert_eq!(luminance(0, 0, 255), 18);
+    }
+
+    #[test]
+    fn grayscale_keeps_brightness_and_transparency() {
+        let mut image = image::RgbaImage::from_pixel(2, 1, image::Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, image::Rgba([0, 255, 0, 100]));
+        PageColors::Grayscale.apply(&mut image, DEFAULT_CONTRAST);
+        assert_eq!(image.get_pixel(0, 0).0, [54, 54, 54, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [182, 182, 182, 100]);
+    }
+
+    #[test]
+    fn normal_colors_leave_the_page_alone() {
+        let mut image = image::RgbaImage::from_pixel(1, 1, image::Rgba([10, 200, 30, 255]));
+        PageColors::Normal.apply(&mut image, DEFAULT_CONTRAST);
+        assert_eq!(image.get_pixel(0, 0).0, [10, 200, 30, 255]);
+    }
+
+    #[test]
+    fn the_colors_button_cycles_through_its_modes() {
+        let cycle = [PageColors::Normal, PageColors::Grayscale];
+        assert_eq!(PageColors::Normal.next_in(&cycle), PageColors::Grayscale);
+        assert_eq!(PageColors::Grayscale.next_in(&cycle), PageColors::Normal);
+        assert_eq!(PageColors::HighContrast.next_in(&cycle), PageColors::Normal);
+        assert_eq!(PageColors::Grayscale.next_in(&[]), PageColors::Grayscale);
+    }
+
+    const CAP: f32 = DEFAULT_RENDER_CAP as f32 * 1_000_000.0;
+
+    // Size a page is rendered at as the viewer works it out: sized for the pane, then clamped
+    fn shown_size(page: (f32, f32), zoom: Option<u32>, shown: (f32, f32)) -> (i32, i32) {
+        let (width, height) = display_size(page.0, page.1, zoom, 2.0, (1920.0, 1080.0), PageFit::Width, shown, (CAP, MAX_RENDER_SIDE));
+        clamp_render_size(width, height, CAP, MAX_RENDER_SIDE).unwrap_or((width, height))
+    }
+
+    #[test]
+    fn absurd_zooms_are_clamped_to_the_cap() {
+        // an A0 poster at 1000% on a 2x display asks for a 23 GB buffer, cropped to a corner
+        let (width, height) = shown_size((2384.0, 3370.0), Some(1000), (0.05, 0.05));
+        assert!((width as f32) * (height as f32) <= CAP);
+        assert!(width as f32 <= MAX_RENDER_SIDE && height as f32 <= MAX_RENDER_SIDE);
+    }
+
+    #[test]
+    fn clamping_keeps_the_aspect_ratio() {
+        // fitting a sliver of a wide page to the pane's width scales the whole page far past the cap
+        let (width, height) = shown_size((1200.0, 600.0), None, (0.01, 0.5));
+        assert!((width as f32 / height as f32 - 2.0).abs() < 0.01);
+        assert!((width as f32) * (height as f32) <= CAP);
+    }
+
+    #[test]
+    fn a_raised_render_limit_renders_larger() {
+        // a US Letter page at 400% on a 2x display is about 17 megapixels
+        let at = |limit| display_size(612.0, 792.0, Some(400), 2.0, (800.0, 600.0), PageFit::Page, (1.0, 1.0), limit);
+        let (low_width, low_height) = at((4_000_000.0, MAX_RENDER_SIDE));
+        let (width, height) = at((CAP, MAX_RENDER_SIDE));
+        assert!((low_width as f32) * (low_height as f32) <= 4_000_000.0);
+        assert!(width > low_width && height > low_height);
+        assert_eq!((width, height), (6528, 8448));
+    }
+
+    #[test]
+    fn the_side_limit_never_exceeds_the_largest_side() {
+        let (width, _) = clamp_render_size(100_000, 10, f32::MAX, f32::MAX).unwrap();
+        assert_eq!(width, MAX_RENDER_SIDE as i32);
+        let (_, height) = clamp_render_size(10, 5000, f32::MAX, MIN_RENDER_SIDE as f32).unwrap();
+        assert_eq!(height, MIN_RENDER_SIDE as i32);
+    }
+
+    #[test]
+    fn a_lower_render_limit_is_respected() {
+        // a US Letter page at 400% on a 2x display, on a machine limited to 2048 pixels a side
+        let scale = points_to_pixels(400, 2.0);
+        let (width, height) = ((612.0 * scale) as i32, (792.0 * scale) as i32);
+        let (width, height) = clamp_render_size(width, height, CAP, 2048.0).unwrap();
+        assert_eq!(height, 2048);
+        assert!(width < height);
+        assert!((width as f32 / height as f32 - 612.0 / 792.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn sizes_within_the_cap_are_left_alone() {
+        assert_eq!(clamp_render_size(1224, 1584, CAP, MAX_RENDER_SIDE), None);
+        // a degenerate size is treated as a single pixel rather than dividing by zero
+        assert_eq!(clamp_render_size(0, -5, CAP, MAX_RENDER_SIDE), None);
+    }
+
+    // A page as pdfium renders it onto a transparent background: clear, black ink, and a half
+    // transparent red wash
+    fn transparent_page() -> image::RgbaImage {
+        let mut image = image::RgbaImage::new(16, 1);
+        image.put_pixel(1, 0, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(9, 0, image::Rgba([255, 0, 0, 128]));
+        image
+    }
+
+    #[test]
+    fn white_is_drawn_by_pdfium() {
+        let white = PageBackground::White.clear_color();
+        assert_eq!((white.red(), white.green(), white.blue(), white.alpha()), (255, 255, 255, 255));
+        let mut image = transparent_page();
+        PageBackground::White.fill(&mut image);
+        assert_eq!(image, transparent_page());
+    }
+
+    #[test]
+    fn theme_leaves_the_page_transparent() {
+        assert_eq!(PageBackground::Theme.clear_color().alpha(), 0);
+        let mut image = transparent_page();
+        PageBackground::Theme.fill(&mut image);
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn checkerboard_shows_through_the_transparency() {
+        assert_eq!(PageBackground::Checkerboard.clear_color().alpha(), 0);
+        let mut image = transparent_page();
+        PageBackground::Checkerboard.fill(&mut image);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(8, 0).0, [204, 204, 204, 255]);
+        // opaque ink stays as it is, the wash is blended over the dark square
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(9, 0).0, [230, 102, 102, 255]);
+    }
+
+    #[test]
+    fn mid_gray_stays_put_at_any_intensity() {
+        for intensity in [1, 50, 100] {
+            assert_eq!(high_contrast(128, intensity), 128);
+        }
+    }
+
+    #[test]
+    fn the_lowest_intensity_changes_nothing() {
+        for gray in [0, 64, 160, 255] {
+            assert_eq!(high_contrast(gray, 1), gray);
+        }
+    }
+
+    #[test]
+    fn half_intensity_about_doubles_the_contrast() {
+        assert_eq!(high_contrast(64, 50), 3);
+        assert_eq!(high_contrast(160, 50), 191);
+        assert_eq!(high_contrast(0, 50), 0);
+        assert_eq!(high_contrast(255, 50), 255);
+    }
+
+    #[test]
+    fn full_intensity_leaves_black_and_white() {
+        assert_eq!(high_contrast(126, MAX_CONTRAST), 0);
+        assert_eq!(high_contrast(130, MAX_CONTRAST), 255);
+        // out of range intensities are clamped rather than overflowing
+        assert_eq!(high_contrast(130, 500), 255);
+        assert_eq!(high_contrast(160, 0), 160);
+    }
+
+    #[test]
+    fn high_contrast_pages_keep_their_transparency() {
+        let mut image = image::RgbaImage::from_pixel(1, 1, image::Rgba([200, 200, 200, 90]));
+        PageColors::HighContrast.apply(&mut image, MAX_CONTRAST);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 90]);
+    }
+}
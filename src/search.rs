@@ -0,0 +1,400 @@
+use crate::interface::{FileInfo, FileManager};
+use crate::ocr::OcrCache;
+use crate::pages;
+use crate::text::{self, TextScript};
+use crate::txt_file;
+use crate::{App, AppService, SearchResult};
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use slint::{ComponentHandle, VecModel};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Extracted text of the recents, next to database.json, so a repeat search doesn't reopen every PDF
+pub const INDEX_FILE: &str = "text_index.json";
+// Characters shown either side of a hit
+const SNIPPET_CONTEXT: usize = 40;
+// A common word across a large library would otherwise flood the results
+const MAX_HITS: usize = 500;
+
+// A page containing the query, with the text around its first occurrence
+#[derive(Clone)]
+pub struct SearchHit {
+    pub name: String,
+    pub path: String,
+    pub page: u32,
+    pub count: usize,
+    pub snippet: String,
+}
+
+// Page text of a document, valid while the file's modified time, script hint and number of pages
+// recognized by OCR match. Pages whose text couldn't be extracted are empty and listed in failed
+#[derive(Serialize, Deserialize)]
+struct IndexedDocument {
+    modified: u64,
+    #[serde(default)]
+    script: TextScript,
+    #[serde(default)]
+    ocr_pages: usize,
+    pages: Vec<String>,
+    #[serde(default)]
+    failed: Vec<u32>,
+}
+
+#[derive(Default)]
+#[derive(Serialize, Deserialize)]
+pub struct TextIndex {
+    documents: HashMap<String, IndexedDocument>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl TextIndex {
+    pub fn load(filename: &str) -> Self {
+        match txt_file::read_file(filename) {
+            Ok(data) => serde_json::from_str(data.as_str()).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    // Writes the index if a search extracted anything new
+    pub fn save(&mut self, filename: &str) {
+        if !self.changed {
+            return;
+        }
+        let json = serde_json::to_string(self).unwrap();
+        match txt_file::write_to_file(filename, json.as_str()) {
+            Ok(_) => self.changed = false,
+            Err(e) => eprintln!("Error saving text index: {}", e),
+        }
+    }
+
+    // Whether the document's text needs extracting: it isn't indexed, or the file, its script hint or
+    // the number of its pages recognized by OCR changed since
+    fn is_stale(&self, file: &FileInfo, ocr_pages: usize) -> bool {
+        match self.documents.get(&file.get_filepath()) {
+            Some(indexed) => {
+                indexed.modified != file.get_modified()
+                    || indexed.script != file.get_text_script()
+                    || indexed.ocr_pages != ocr_pages
+            }
+            None => true,
+        }
+    }
+
+    fn insert(&mut self, path: String, indexed: IndexedDocument) {
+        self.documents.insert(path, indexed);
+        self.changed = true;
+    }
+}
+
+// Page text of a document normalized for its script. Pages without extractable text are taken from
+// the OCR text where it recognized them. Pdfium is loaded for one page at a time, so the page shown
+// can be rendered between them while a large library is indexed. None if the document can't be read
+fn extract(file: &FileInfo, ocr_text: &HashMap<u32, String>) -> Option<IndexedDocument> {
+    let path = file.get_filepath();
+    let count = pages::with_document(&path, pages::page_count).ok()?;
+    let (pages, failed) = read_pages(&path, count, file.get_text_script(), ocr_text, |index| page_text(&path, index));
+    Some(IndexedDocument {
+        modified: file.get_modified(),
+        script: file.get_text_script(),
+        ocr_pages: ocr_text.len(),
+        pages,
+        failed,
+    })
+}
+
+// The normalized text of each of the count pages of the document at path as read gives it, with OCR
+// text standing in for pages without any. A page that fails to read is left empty and listed, zero
+// based, in the second value
+fn read_pages(
+    path: &str,
+    count: u32,
+    script: TextScript,
+    ocr_text: &HashMap<u32, String>,
+    mut read: impl FnMut(u32) -> Result<String, PdfiumError>,
+) -> (Vec<String>, Vec<u32>) {
+    let mut failed = Vec::new();
+    let pages = (0..count)
+        .map(|index| {
+            let page_text = match read(index) {
+                Ok(page_text) => text::normalize(&page_text, script),
+                Err(e) => {
+                    eprintln!("Error reading text of page {} of {}: {}", index + 1, path, e);
+                    failed.push(index);
+                    String::new()
+                }
+            };
+            match ocr_text.get(&index) {
+                Some(recognized) if page_text.trim().is_empty() => {
+                    failed.retain(|&page| page != index);
+                    text::normalize(recognized, script)
+                }
+                _ => page_text,
+            }
+        })
+        .collect();
+    (pages, failed)
+}
+
+// The text of one page, with pdfium loaded just for it
+fn page_text(path: &str, index: u32) -> Result<String, PdfiumError> {
+    pages::with_document(path, |document| {
+        let page = pages::get_page(document, index)?;
+        let text = page.text()?.all();
+        Ok(text)
+    })?
+}
+
+// Case-insensitive search of every document, in the order given, one hit per matching page, along
+// with how many pages couldn't be searched because their text didn't extract. Documents not indexed
+// yet, or changed since, are extracted and stored in the index first. Scanned pages are searched in
+// their OCR text. Meant for a worker thread: progress is reported after each document, and None is
+// returned as soon as cancelled says the search is no longer wanted
+pub fn search(
+    index: &Mutex<TextIndex>,
+    files: &[FileInfo],
+    ocr: &Mutex<OcrCache>,
+    query: &str,
+    cancelled: impl Fn() -> bool,
+    progress: impl Fn(usize, usize),
+) -> Option<(Vec<SearchHit>, usize)> {
+    let query = query.trim().to_lowercase();
+    let mut hits = Vec::new();
+    let mut unreadable = 0;
+    if query.is_empty() {
+        return Some((hits, unreadable));
+    }
+
+    for (done, file) in files.iter().enumerate() {
+        if cancelled() {
+            return None;
+        }
+        progress(done, files.len());
+        let path = file.get_filepath();
+        let ocr_text = ocr.lock().unwrap().document_text(&path, file.get_modified());
+        let stale = index.lock().unwrap().is_stale(file, ocr_text.len());
+        if stale {
+            let Some(indexed) = extract(file, &ocr_text) else {
+                eprintln!("Error indexing {}", path);
+                continue;
+            };
+            index.lock().unwrap().insert(path.clone(), indexed);
+        }
+        let index = index.lock().unwrap();
+        let Some(indexed) = index.documents.get(&path) else {
+            continue;
+        };
+        unreadable += indexed.failed.len();
+        // the query is cleaned up the same way as the text, e.g. full width letters typed in a
+        // CJK input method match the ASCII ones
+        let query = text::normalize(&query, file.get_text_script());
+        for (page, page_text) in indexed.pages.iter().enumerate() {
+            if hits.len() >= MAX_HITS {
+                return Some((hits, unreadable));
+            }
+            let lower = page_text.to_lowercase();
+            let Some(position) = lower.find(&query) else {
+                continue;
+            };
+            hits.push(SearchHit {
+                name: file.get_name(),
+                path: path.clone(),
+                page: page as u32,
+                count: lower.matches(&query).count(),
+                snippet: snippet(&lower, page_text, position, query.len()),
+            });
+        }
+    }
+    progress(files.len(), files.len());
+    Some((hits, unreadable))
+}
+
+// The text around a hit on one line. Lowercasing can change byte lengths, in which case the
+// positions only hold for the lowercase text and the snippet is taken from that instead
+fn snippet(lower: &str, original: &str, position: usize, length: usize) -> String {
+    let source = if lower.len() == original.len() { original } else { lower };
+    let mut start = position.saturating_sub(SNIPPET_CONTEXT);
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (position + length + SNIPPET_CONTEXT).min(source.len());
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+    let text: String = source[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < source.len() { "…" } else { "" };
+    format!("{}{}{}", prefix, text, suffix)
+}
+
+// Registers the callbacks of the search across all recent documents
+pub fn bind(
+    app: &App,
+    file_manager: &Arc<Mutex<FileManager>>,
+    ocr_cache: &Arc<Mutex<OcrCache>>,
+) {
+    // Shared with the search worker, which locks it while it reads or stores a document. Each search takes the next
+    // generation, so any older search still running sees it was superseded and stops
+    let text_index = Arc::new(Mutex::new(TextIndex::load(INDEX_FILE)));
+    let search_generation = Arc::new(AtomicUsize::new(0));
+
+    /* CALLBACK:
+        Searches the text of every recent PDF on a worker thread. Progress and then the results, grouped
+        by document in recents order, are set on AppService as they come in. Text is extracted once per
+        document and kept in the index on disk
+
+        # Arguments
+        * 'query' - text to find, case-insensitive
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_search_all({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let text_index = text_index.clone();
+        let ocr_cache = ocr_cache.clone();
+        let search_generation = search_generation.clone();
+        move |query| {
+            // scans have no text to find until OCR read them, extracting it would only slow the search down
+            let ocr = ocr_cache.lock().unwrap();
+            let (scanned, files): (Vec<FileInfo>, Vec<FileInfo>) = cloned_file_manager
+                .lock()
+                .unwrap()
+                .get_files()
+                .iter()
+                .cloned()
+                .partition(|file| {
+                    file.get_scanned() && ocr.recognized_pages(&file.get_filepath(), file.get_modified()) == 0
+                });
+            drop(ocr);
+            let scanned = scanned.len();
+            let generation = search_generation.fetch_add(1, Ordering::SeqCst) + 1;
+            let app = app_weak.unwrap();
+            app.global::<AppService>().set_search_running(true);
+            app.global::<AppService>().set_search_progress("Searching…".into());
+            app.global::<AppService>().set_search_results(slint::ModelRc::default());
+
+            let app_weak = app_weak.clone();
+            let text_index = text_index.clone();
+            let ocr_cache = ocr_cache.clone();
+            let search_generation = search_generation.clone();
+            let query = query.to_string();
+            thread::spawn(move || {
+                let is_current = || search_generation.load(Ordering::SeqCst) == generation;
+                let hits = search(&text_index, &files, &ocr_cache, query.as_str(), || !is_current(), |done, total| {
+                    let status = format!("Searching {} of {} documents…", done + 1, total);
+                    let _ = app_weak.upgrade_in_event_loop(move |app| {
+                        app.global::<AppService>().set_search_progress(status.into());
+                    });
+                });
+                text_index.lock().unwrap().save(INDEX_FILE);
+
+                let Some((hits, unreadable)) = hits else {
+                    return;
+                };
+                if !is_current() {
+                    return;
+                }
+                let _ = app_weak.upgrade_in_event_loop(move |app| {
+                    let mut status = match hits.len() {
+                        0 => "No matches".to_string(),
+                        1 => "1 page found".to_string(),
+                        count => format!("{} pages found", count),
+                    };
+                    if unreadable > 0 {
+                        status += &format!(", {} unreadable page{} skipped", unreadable, if unreadable == 1 { "" } else { "s" });
+                    }
+                    if scanned > 0 {
+                        status += &format!(", {} scanned document{} not searched", scanned, if scanned == 1 { "" } else { "s" });
+                    }
+                    let results: Vec<SearchResult> = hits
+                        .iter()
+                        .enumerate()
+                        .map(|(i, hit)| SearchResult {
+                            name: hit.name.clone().into(),
+                            path: hit.path.clone().into(),
+                            page: hit.page as i32,
+                            count: hit.count as i32,
+                            snippet: hit.snippet.clone().into(),
+                            first_in_document: i == 0 || hits[i - 1].path != hit.path,
+                        })
+                        .collect();
+                    app.global::<AppService>().set_search_results(slint::ModelRc::new(VecModel::from(results)));
+                    app.global::<AppService>().set_search_progress(status.into());
+                    app.global::<AppService>().set_search_running(false);
+                });
+            });
+        }
+    });
+
+    /* CALLBACK:
+        Stops the running search across recent PDFs, text already extracted stays in the index
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_cancel_search({
+        let app_weak = app.as_weak();
+        let search_generation = search_generation.clone();
+        move || {
+            search_generation.fetch_add(1, Ordering::SeqCst);
+            let app = app_weak.unwrap();
+            app.global::<AppService>().set_search_running(false);
+            app.global::<AppService>().set_search_progress("Search cancelled".into());
+        }
+    });
+
+    /* CALLBACK:
+        Opens a search result, the PDF at the page the hit is on
+
+        # Arguments
+        * 'file_path' - path of the recent PDF the hit is in
+        * 'page' - 0-based page of the hit
+
+        # Return
+        N/A
+    */
+    app.global::<AppService>().on_open_search_result({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        move |file_path, page| {
+            let app = app_weak.unwrap();
+            crate::open_document(&app, &cloned_file_manager, file_path.to_string(), Some(page.max(0) as u32));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A three page document whose second page pdfium can't parse
+    fn read_with_a_broken_page(index: u32) -> Result<String, PdfiumError> {
+        match index {
+            1 => Err(PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::FormatError)),
+            index => Ok(format!("text of page {}", index + 1)),
+        }
+    }
+
+    #[test]
+    fn a_broken_page_doesnt_stop_the_others() {
+        let (pages, failed) = read_pages("a.pdf", 3, TextScript::Latin, &HashMap::new(), read_with_a_broken_page);
+        assert_eq!(pages, ["text of page 1", "", "text of page 3"]);
+        assert_eq!(failed, [1]);
+    }
+
+    #[test]
+    fn ocr_text_recovers_a_broken_page() {
+        let ocr_text = HashMap::from([(1, "recognized".to_string())]);
+        let (pages, failed) = read_pages("a.pdf", 3, TextScript::Latin, &ocr_text, read_with_a_broken_page);
+        assert_eq!(pages[1], "recognized");
+        assert!(failed.is_empty());
+    }
+}
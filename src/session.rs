@@ -0,0 +1,150 @@
+use chrono::{DateTime, Local};
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+// A gap between two page views longer than this is taken as the reader being away, it isn't counted
+const IDLE_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+// What was read of one document this session
+struct SessionDocument {
+    name: String,
+    path: String,
+    first_opened: DateTime<Local>,
+    pages: BTreeSet<u32>,
+    time_spent: Duration,
+    highlights_added: u32,
+    // pages whose turn saved the notes
+    notes_saved_on: BTreeSet<u32>,
+}
+
+// The documents read since PDFer started, in the order they were first opened, for the session report.
+// Time spent is measured between page views, so the time on a document runs from showing one of its
+// pages until the next page view of any document
+pub struct SessionLog {
+    started: DateTime<Local>,
+    documents: Vec<SessionDocument>,
+    // document and moment of the last page view
+    last_view: Option<(usize, Instant)>,
+}
+
+impl SessionLog {
+    pub fn new() -> Self {
+        Self {
+            started: Local::now(),
+            documents: Vec::new(),
+            last_view: None,
+        }
+    }
+
+    // Records that a page of the document was shown
+    pub fn visit(&mut self, path: &str, name: &str, page: u32) {
+        let now = Instant::now();
+        if let Some((index, since)) = self.last_view {
+            let elapsed = now.duration_since(since);
+            if elapsed <= IDLE_LIMIT {
+                self.documents[index].time_spent += elapsed;
+            }
+        }
+        let index = self.document(path, name);
+        self.documents[index].pages.insert(page);
+        self.last_view = Some((index, now));
+    }
+
+    pub fn highlight_added(&mut self, path: &str, name: &str) {
+        let index = self.document(path, name);
+        self.documents[index].highlights_added += 1;
+    }
+
+    // Records that leaving the page saved the notes
+    pub fn notes_saved(&mut self, path: &str, name: &str, page: u32) {
+        let index = self.document(path, name);
+        self.documents[index].notes_saved_on.insert(page);
+    }
+
+    fn document(&mut self, path: &str, name: &str) -> usize {
+        if let Some(index) = self.documents.iter().position(|document| document.path == path) {
+            return index;
+        }
+        self.documents.push(SessionDocument {
+            name: name.to_string(),
+            path: path.to_string(),
+            first_opened: Local::now(),
+            pages: BTreeSet::new(),
+            time_spent: Duration::ZERO,
+            highlights_added: 0,
+            notes_saved_on: BTreeSet::new(),
+        });
+        self.documents.len() - 1
+    }
+
+    // The session as Markdown, followed by a copy of the notes
+    pub fn report(&self, notes: &str) -> String {
+        let now = Local::now();
+        let mut report = String::from("# Reading session\n\n");
+        report.push_str(&format!(
+            "Started {}, report written {}\n\n",
+            self.started.format("%Y-%m-%d %H:%M"),
+            now.format("%Y-%m-%d %H:%M")
+        ));
+
+        report.push_str("## Documents\n\n");
+        if self.documents.is_empty() {
+            report.push_str("No documents were read.\n\n");
+        }
+        for document in &self.documents {
+            report.push_str(&format!("### {}\n\n", document.name));
+            report.push_str(&format!("- Path: {}\n", document.path));
+            report.push_str(&format!("- First opened: {}\n", document.first_opened.format("%H:%M")));
+            report.push_str(&format!(
+                "- Pages covered: {} ({} pages)\n",
+                page_ranges(&document.pages),
+                document.pages.len()
+            ));
+            report.push_str(&format!("- Time spent: {}\n", format_duration(document.time_spent)));
+            report.push_str(&format!("- Highlights added: {}\n", document.highlights_added));
+            if !document.notes_saved_on.is_empty() {
+                report.push_str(&format!("- Notes saved leaving pages: {}\n", page_ranges(&document.notes_saved_on)));
+            }
+            report.push('\n');
+        }
+
+        report.push_str("## Notes\n\n");
+        if notes.trim().is_empty() {
+            report.push_str("No notes.\n");
+        } else {
+            report.push_str(notes.trim_end());
+            report.push('\n');
+        }
+        report
+    }
+}
+
+// 1-based pages as runs, e.g. "1–4, 7"
+fn page_ranges(pages: &BTreeSet<u32>) -> String {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &page in pages {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == page => *last = page,
+            _ => runs.push((page, page)),
+        }
+    }
+    runs.iter()
+        .map(|&(first, last)| {
+            if first == last {
+                (first + 1).to_string()
+            } else {
+                format!("{}–{}", first + 1, last + 1)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match minutes {
+        0 => "under a minute".to_string(),
+        1..=59 => format!("{} min", minutes),
+        _ => format!("{} h {} min", minutes / 60, minutes % 60),
+    }
+}
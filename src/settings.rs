@@ -0,0 +1,1026 @@
+use crate::citation::CitationStyle;
+use crate::highlights;
+use crate::interface::{RecentsFilter, RecentsSort};
+use crate::keybindings::KeyBindings;
+use crate::options::named_options;
+use crate::overlay::PageNumberOverlay;
+use crate::page_cache;
+use crate::pages::{PageBoundary, PageIndicator};
+use crate::platform;
+use crate::render::{self, PageBackground, PageColors, PageFit, RenderQuality};
+use crate::speech;
+use crate::thumbnails::{ThumbnailSize, ThumbnailWheel};
+use crate::timestamps::TimestampFormat;
+use crate::txt_file;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+// Where the notes font size is kept: one size for all notes, or a size per document that starts out
+// at the global one
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum FontScope {
+        #[default]
+        Global => "Global",
+        PerDocument => "Per document",
+    }
+}
+
+// File type new notes are saved as. Markdown notes open with the preview shown. Notes of either type
+// can still be opened whatever the choice
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum NotesFormat {
+        #[default]
+        Text => "Text",
+        Markdown => "Markdown",
+    }
+}
+
+impl NotesFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            NotesFormat::Text => "txt",
+            NotesFormat::Markdown => "md",
+        }
+    }
+
+    // The format of a notes file by its extension, text unless it's .md or .markdown
+    pub fn of_path(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => NotesFormat::Markdown,
+            _ => NotesFormat::Text,
+        }
+    }
+}
+
+// Colors of the app around the page. System follows the OS light or dark scheme where the UI style
+// can tell it, and is light elsewhere
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum Theme {
+        #[default]
+        Light => "Light",
+        Dark => "Dark",
+        System => "System",
+    }
+}
+
+// What PDFer shows when it starts without a PDF on the command line
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum Startup {
+        #[default]
+        OpeningPage => "Opening Page",
+        // the last read document, or the opening page if it's gone
+        LastDocument => "Last Document",
+        // the scratchpad over the opening page, ready for a note
+        BlankNote => "Blank Note",
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    hide_extensions: bool,
+    theme: Theme,
+    keybindings: KeyBindings,
+    render_quality: RenderQuality,
+    // colors the page is shown in, and whether highlights go gray with a grayscale page or keep theirs
+    page_colors: PageColors,
+    gray_highlights: bool,
+    // the modes the cycle shortcut steps through, in order
+    color_cycle: Vec<PageColors>,
+    // intensity of High Contrast, 1 to 100
+    contrast: u32,
+    render_annotations: bool,
+    // what the transparent parts of pages are drawn on
+    page_background: PageBackground,
+    startup: Startup,
+    // the resume toggle of settings files from before the startup choice, read into startup on load
+    #[serde(skip_serializing)]
+    resume_last_document: bool,
+    // whether the first launch opens the file dialog while there are no recents, and whether PDFer
+    // hasn't been launched before. Settings files from before the flag count as a first launch, but
+    // with recents nothing is prompted
+    first_run_prompt: bool,
+    first_run: bool,
+    ui_scale: f32,
+    recents_as_list: bool,
+    // the list of recents grouped by folder, and the folders whose group is collapsed
+    recents_grouped: bool,
+    collapsed_folders: Vec<String>,
+    recents_sort: RecentsSort,
+    open_on_double_click: bool,
+    timestamp_format: TimestampFormat,
+    recents_filter: RecentsFilter,
+    auto_finish: bool,
+    confirm_exit: bool,
+    // whether clearing the recents asks first, off once the user said not to ask again
+    confirm_clear_recents: bool,
+    // whether a link on the page to a web or mail address asks before opening it
+    confirm_links: bool,
+    page_indicator: PageIndicator,
+    // the labels toggle of settings files from before the page indicator choice, read into
+    // page_indicator on load
+    #[serde(skip_serializing)]
+    show_page_labels: bool,
+    auto_crop: bool,
+    crop_padding: u32,
+    // horizontal placement of a page narrower than the pane, and the space kept around it in pixels
+    center_page: bool,
+    page_gap: u32,
+    // whether a double tap on the page toggles between fitting its width and the zoom before
+    touch_mode: bool,
+    // largest render in megapixels, a zoom or crop asking for more shows the page at less
+    render_cap: u32,
+    // longest side of a render in pixels, a lower limit keeps renders small however the page is shown
+    render_side: u32,
+    // resolution a selection is saved as an image at
+    selection_dpi: u32,
+    // how a selection copied with a citation credits the document
+    citation_style: CitationStyle,
+    // pages rendered ahead when a document opens, and the memory rendered pages may keep in megabytes
+    warmup_pages: u32,
+    page_cache_megabytes: u32,
+    page_number_overlay: PageNumberOverlay,
+    highlight_color: String,
+    open_in_running_instance: bool,
+    distraction_free: bool,
+    always_on_top: bool,
+    // the PDF toolbar hides after toolbar_hide_delay seconds without the pointer on it, and shows again
+    // within toolbar_reveal_distance pixels of the top edge
+    toolbar_autohide: bool,
+    toolbar_hide_delay: u32,
+    toolbar_reveal_distance: u32,
+    // after idle_minutes without input, the notes and recents are saved and the window minimized, each
+    // only if turned on
+    idle_minutes: u32,
+    idle_save: bool,
+    idle_minimize: bool,
+    // whether turning the page saves notes with unsaved edits, apart from the idle save
+    save_notes_on_page_turn: bool,
+    thumbnails_shown: bool,
+    thumbnail_size: ThumbnailSize,
+    // whether the wheel over the thumbnail column scrolls it or turns pages
+    thumbnail_wheel: ThumbnailWheel,
+    toc_shown: bool,
+    page_fit: PageFit,
+    // percent, None to size new documents by the page fit mode
+    default_zoom: Option<u32>,
+    page_boundary: PageBoundary,
+    speech_rate: u32,
+    // notes editor text size the +/- buttons and Ctrl+Shift+= / Ctrl+Shift+- step by, and the size last
+    // used
+    font_step: u32,
+    notes_font_size: u32,
+    font_scope: FontScope,
+    notes_format: NotesFormat,
+    // whether the editor shows a note of each page, kept in database.json, instead of the notes file
+    page_notes: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            hide_extensions: false,
+            theme: Theme::default(),
+            keybindings: KeyBindings::default(),
+            render_quality: RenderQuality::default(),
+            page_colors: PageColors::default(),
+            gray_highlights: false,
+            color_cycle: vec![PageColors::Normal, PageColors::Grayscale],
+            contrast: render::DEFAULT_CONTRAST,
+            render_annotations: true,
+            page_background: PageBackground::default(),
+            startup: Startup::default(),
+            resume_last_document: false,
+            first_run_prompt: true,
+            first_run: true,
+            ui_scale: 1.0,
+            recents_as_list: false,
+            recents_grouped: false,
+            collapsed_folders: Vec::new(),
+            recents_sort: RecentsSort::default(),
+            open_on_double_click: platform::double_click_opens(),
+            timestamp_format: TimestampFormat::default(),
+            recents_filter: RecentsFilter::default(),
+            auto_finish: true,
+            confirm_exit: true,
+            confirm_clear_recents: true,
+            confirm_links: true,
+            page_indicator: PageIndicator::default(),
+            show_page_labels: true,
+            auto_crop: false,
+            crop_padding: 10,
+            center_page: true,
+            touch_mode: false,
+            page_gap: 0,
+            render_cap: render::DEFAULT_RENDER_CAP,
+            render_side: render::MAX_RENDER_SIDE as u32,
+            selection_dpi: 300,
+            citation_style: CitationStyle::default(),
+            warmup_pages: page_cache::DEFAULT_WARMUP_PAGES,
+            page_cache_megabytes: page_cache::DEFAULT_CACHE_MEGABYTES,
+            page_number_overlay: PageNumberOverlay::default(),
+            highlight_color: highlights::PALETTE[0].1.to_string(),
+            open_in_running_instance: true,
+            distraction_free: false,
+            always_on_top: false,
+            toolbar_autohide: false,
+            toolbar_hide_delay: 3,
+            toolbar_reveal_distance: 40,
+            idle_minutes: 15,
+            idle_save: false,
+            idle_minimize: false,
+            save_notes_on_page_turn: false,
+            thumbnails_shown: false,
+            thumbnail_size: ThumbnailSize::default(),
+            thumbnail_wheel: ThumbnailWheel::default(),
+            toc_shown: false,
+            page_fit: PageFit::default(),
+            default_zoom: None,
+            page_boundary: PageBoundary::default(),
+            speech_rate: 175,
+            font_step: 1,
+            notes_font_size: 12,
+            font_scope: FontScope::default(),
+            notes_format: NotesFormat::default(),
+            page_notes: false,
+        }
+    }
+}
+
+// Reads the settings in data, and whether all of it was understood. Fields that don't parse are
+// reported and left at their defaults, settings that aren't JSON at all give the defaults
+fn parse(data: &str) -> (Settings, bool) {
+    let fields = match serde_json::from_str::<Value>(data) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => {
+            eprintln!("Error reading settings: not a JSON object");
+            return (Settings::default(), false);
+        }
+        Err(e) => {
+            eprintln!("Error reading settings: {}", e);
+            return (Settings::default(), false);
+        }
+    };
+    match serde_json::from_value(Value::Object(fields.clone())) {
+        Ok(settings) => return (settings, true),
+        Err(e) => eprintln!("Error reading settings: {}", e),
+    }
+    // each field is taken on its own, over the defaults, so one bad value doesn't cost the rest
+    let mut kept = match serde_json::to_value(Settings::default()) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => Map::new(),
+    };
+    for (name, value) in fields {
+        let mut tried = kept.clone();
+        tried.insert(name.clone(), value);
+        match serde_json::from_value::<Settings>(Value::Object(tried.clone())) {
+            Ok(_) => kept = tried,
+            Err(e) => eprintln!("Setting {} left at its default: {}", name, e),
+        }
+    }
+    (serde_json::from_value(Value::Object(kept)).unwrap_or_default(), false)
+}
+
+impl Settings {
+    // Loads the settings file, falling back to defaults for a missing file or missing fields. A field
+    // that doesn't parse, e.g. a choice a newer PDFer added or a hand edit, falls back on its own and the
+    // others are kept. The file as it was is then copied to a .bak beside it before a save overwrites it
+    pub fn load(filename: &str) -> Self {
+        match txt_file::read_file(filename) {
+            Ok(data) => {
+                let (mut settings, complete) = parse(data.as_str());
+                if !complete {
+                    let backup = format!("{}.bak", filename);
+                    match fs::write(&backup, data.as_bytes()) {
+                        Ok(_) => eprintln!("{} wasn't read in full, it was kept as {}", filename, backup),
+                        Err(e) => eprintln!("{} wasn't read in full and couldn't be kept: {}", filename, e),
+                    }
+                }
+                if settings.resume_last_document {
+                    settings.startup = Startup::LastDocument;
+                    settings.resume_last_document = false;
+                }
+                if !settings.show_page_labels {
+                    settings.page_indicator = PageIndicator::Number;
+                    settings.show_page_labels = true;
+                }
+                settings
+            }
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self, filename: &str) {
+        let json = serde_json::to_string(self).unwrap();
+        match txt_file::write_to_file(filename, json.as_str()) {
+            Ok(_) => println!("Settings Saved"),
+            Err(e) => eprintln!("Error saving settings: {}", e),
+        }
+    }
+
+    pub fn get_hide_extensions(&self) -> bool {
+        self.hide_extensions
+    }
+
+    pub fn set_hide_extensions(&mut self, hide: bool) {
+        self.hide_extensions = hide;
+    }
+
+    pub fn get_keybindings(&self) -> &KeyBindings {
+        &self.keybindings
+    }
+
+    pub fn get_keybindings_mut(&mut self) -> &mut KeyBindings {
+        &mut self.keybindings
+    }
+
+    pub fn get_render_quality(&self) -> RenderQuality {
+        self.render_quality
+    }
+
+    pub fn set_render_quality(&mut self, quality: RenderQuality) {
+        self.render_quality = quality;
+    }
+
+    pub fn get_page_colors(&self) -> PageColors {
+        self.page_colors
+    }
+
+    pub fn set_page_colors(&mut self, colors: PageColors) {
+        self.page_colors = colors;
+    }
+
+    pub fn get_gray_highlights(&self) -> bool {
+        self.gray_highlights
+    }
+
+    pub fn set_gray_highlights(&mut self, gray: bool) {
+        self.gray_highlights = gray;
+    }
+
+    pub fn get_render_annotations(&self) -> bool {
+        self.render_annotations
+    }
+
+    pub fn set_render_annotations(&mut self, annotations: bool) {
+        self.render_annotations = annotations;
+    }
+
+    pub fn get_page_background(&self) -> PageBackground {
+        self.page_background
+    }
+
+    pub fn set_page_background(&mut self, background: PageBackground) {
+        self.page_background = background;
+    }
+
+    pub fn get_startup(&self) -> Startup {
+        self.startup
+    }
+
+    pub fn set_startup(&mut self, startup: Startup) {
+        self.startup = startup;
+    }
+
+    pub fn get_first_run_prompt(&self) -> bool {
+        self.first_run_prompt
+    }
+
+    pub fn set_first_run_prompt(&mut self, prompt: bool) {
+        self.first_run_prompt = prompt;
+    }
+
+    pub fn get_first_run(&self) -> bool {
+        self.first_run
+    }
+
+    pub fn set_first_run(&mut self, first_run: bool) {
+        self.first_run = first_run;
+    }
+
+    pub fn get_ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale.clamp(0.5, 2.0);
+    }
+
+    pub fn get_recents_as_list(&self) -> bool {
+        self.recents_as_list
+    }
+
+    pub fn set_recents_as_list(&mut self, as_list: bool) {
+        self.recents_as_list = as_list;
+    }
+
+    pub fn get_recents_grouped(&self) -> bool {
+        self.recents_grouped
+    }
+
+    pub fn set_recents_grouped(&mut self, grouped: bool) {
+        self.recents_grouped = grouped;
+    }
+
+    pub fn is_folder_collapsed(&self, folder: &str) -> bool {
+        self.collapsed_folders.iter().any(|collapsed| collapsed == folder)
+    }
+
+    // Collapses the folder's group of recents, or expands it if it was collapsed
+    pub fn toggle_folder_collapsed(&mut self, folder: &str) {
+        if self.is_folder_collapsed(folder) {
+            self.collapsed_folders.retain(|collapsed| collapsed != folder);
+        } else {
+            self.collapsed_folders.push(folder.to_string());
+        }
+    }
+
+    pub fn get_recents_sort(&self) -> RecentsSort {
+        self.recents_sort
+    }
+
+    pub fn set_recents_sort(&mut self, sort: RecentsSort) {
+        self.recents_sort = sort;
+    }
+
+    pub fn get_open_on_double_click(&self) -> bool {
+        self.open_on_double_click
+    }
+
+    pub fn set_open_on_double_click(&mut self, double_click: bool) {
+        self.open_on_double_click = double_click;
+    }
+
+    pub fn get_timestamp_format(&self) -> TimestampFormat {
+        self.timestamp_format
+    }
+
+    pub fn set_timestamp_format(&mut self, format: TimestampFormat) {
+        self.timestamp_format = format;
+    }
+
+    pub fn get_recents_filter(&self) -> RecentsFilter {
+        self.recents_filter
+    }
+
+    pub fn set_recents_filter(&mut self, filter: RecentsFilter) {
+        self.recents_filter = filter;
+    }
+
+    pub fn get_auto_finish(&self) -> bool {
+        self.auto_finish
+    }
+
+    pub fn set_auto_finish(&mut self, auto_finish: bool) {
+        self.auto_finish = auto_finish;
+    }
+
+    pub fn get_confirm_exit(&self) -> bool {
+        self.confirm_exit
+    }
+
+    pub fn set_confirm_exit(&mut self, confirm: bool) {
+        self.confirm_exit = confirm;
+    }
+
+    pub fn get_confirm_clear_recents(&self) -> bool {
+        self.confirm_clear_recents
+    }
+
+    pub fn set_confirm_clear_recents(&mut self, confirm: bool) {
+        self.confirm_clear_recents = confirm;
+    }
+
+    pub fn get_confirm_links(&self) -> bool {
+        self.confirm_links
+    }
+
+    pub fn set_confirm_links(&mut self, confirm: bool) {
+        self.confirm_links = confirm;
+    }
+
+    pub fn get_page_indicator(&self) -> PageIndicator {
+        self.page_indicator
+    }
+
+    pub fn set_page_indicator(&mut self, indicator: PageIndicator) {
+        self.page_indicator = indicator;
+    }
+
+    pub fn get_auto_crop(&self) -> bool {
+        self.auto_crop
+    }
+
+    pub fn set_auto_crop(&mut self, enabled: bool) {
+        self.auto_crop = enabled;
+    }
+
+    pub fn get_crop_padding(&self) -> u32 {
+        self.crop_padding
+    }
+
+    pub fn set_crop_padding(&mut self, padding: u32) {
+        self.crop_padding = padding;
+    }
+
+    pub fn get_center_page(&self) -> bool {
+        self.center_page
+    }
+
+    pub fn set_center_page(&mut self, centered: bool) {
+        self.center_page = centered;
+    }
+
+    pub fn get_page_gap(&self) -> u32 {
+        self.page_gap
+    }
+
+    pub fn set_page_gap(&mut self, gap: u32) {
+        self.page_gap = gap;
+    }
+
+    pub fn get_touch_mode(&self) -> bool {
+        self.touch_mode
+    }
+
+    pub fn set_touch_mode(&mut self, touch: bool) {
+        self.touch_mode = touch;
+    }
+
+    pub fn get_warmup_pages(&self) -> u32 {
+        self.warmup_pages
+    }
+
+    pub fn set_warmup_pages(&mut self, pages: u32) {
+        self.warmup_pages = pages;
+    }
+
+    pub fn get_page_cache_megabytes(&self) -> u32 {
+        self.page_cache_megabytes
+    }
+
+    pub fn set_page_cache_megabytes(&mut self, megabytes: u32) {
+        self.page_cache_megabytes = megabytes;
+    }
+
+    pub fn get_color_cycle(&self) -> &Vec<PageColors> {
+        &self.color_cycle
+    }
+
+    // Adds the mode to the cycle or takes it out, the modes keep the order they are offered in
+    pub fn set_in_color_cycle(&mut self, colors: PageColors, included: bool) {
+        self.color_cycle.retain(|&mode| mode != colors);
+        if included {
+            self.color_cycle.push(colors);
+            self.color_cycle
+                .sort_by_key(|mode| PageColors::NAMES.iter().position(|&name| name == mode.name()));
+        }
+    }
+
+    pub fn get_contrast(&self) -> u32 {
+        self.contrast
+    }
+
+    pub fn set_contrast(&mut self, contrast: u32) {
+        self.contrast = contrast.clamp(1, render::MAX_CONTRAST);
+    }
+
+    pub fn get_render_cap(&self) -> u32 {
+        self.render_cap
+    }
+
+    pub fn set_render_cap(&mut self, megapixels: u32) {
+        self.render_cap = megapixels;
+    }
+
+    pub fn get_render_side(&self) -> u32 {
+        self.render_side.clamp(render::MIN_RENDER_SIDE, render::MAX_RENDER_SIDE as u32)
+    }
+
+    pub fn set_render_side(&mut self, side: u32) {
+        self.render_side = side.clamp(render::MIN_RENDER_SIDE, render::MAX_RENDER_SIDE as u32);
+    }
+
+    pub fn get_selection_dpi(&self) -> u32 {
+        self.selection_dpi
+    }
+
+    pub fn set_selection_dpi(&mut self, dpi: u32) {
+        self.selection_dpi = dpi;
+    }
+
+    pub fn get_citation_style(&self) -> CitationStyle {
+        self.citation_style
+    }
+
+    pub fn set_citation_style(&mut self, style: CitationStyle) {
+        self.citation_style = style;
+    }
+
+    pub fn get_page_number_overlay(&self) -> &PageNumberOverlay {
+        &self.page_number_overlay
+    }
+
+    pub fn set_page_number_overlay(&mut self, overlay: PageNumberOverlay) {
+        self.page_number_overlay = overlay;
+    }
+
+    pub fn get_highlight_color(&self) -> &str {
+        &self.highlight_color
+    }
+
+    pub fn set_highlight_color(&mut self, color: &str) {
+        self.highlight_color = color.to_string();
+    }
+
+    pub fn get_open_in_running_instance(&self) -> bool {
+        self.open_in_running_instance
+    }
+
+    pub fn set_open_in_running_instance(&mut self, forward: bool) {
+        self.open_in_running_instance = forward;
+    }
+
+    pub fn get_distraction_free(&self) -> bool {
+        self.distraction_free
+    }
+
+    pub fn set_distraction_free(&mut self, on: bool) {
+        self.distraction_free = on;
+    }
+
+    pub fn get_always_on_top(&self) -> bool {
+        self.always_on_top
+    }
+
+    pub fn set_always_on_top(&mut self, on: bool) {
+        self.always_on_top = on;
+    }
+
+    pub fn get_toolbar_autohide(&self) -> bool {
+        self.toolbar_autohide
+    }
+
+    pub fn set_toolbar_autohide(&mut self, on: bool) {
+        self.toolbar_autohide = on;
+    }
+
+    pub fn get_toolbar_hide_delay(&self) -> u32 {
+        self.toolbar_hide_delay
+    }
+
+    pub fn set_toolbar_hide_delay(&mut self, seconds: u32) {
+        self.toolbar_hide_delay = seconds.max(1);
+    }
+
+    pub fn get_toolbar_reveal_distance(&self) -> u32 {
+        self.toolbar_reveal_distance
+    }
+
+    pub fn set_toolbar_reveal_distance(&mut self, pixels: u32) {
+        self.toolbar_reveal_distance = pixels.max(1);
+    }
+
+    pub fn get_idle_minutes(&self) -> u32 {
+        self.idle_minutes
+    }
+
+    pub fn set_idle_minutes(&mut self, minutes: u32) {
+        self.idle_minutes = minutes.max(1);
+    }
+
+    pub fn get_idle_save(&self) -> bool {
+        self.idle_save
+    }
+
+    pub fn set_idle_save(&mut self, save: bool) {
+        self.idle_save = save;
+    }
+
+    pub fn get_idle_minimize(&self) -> bool {
+        self.idle_minimize
+    }
+
+    pub fn set_idle_minimize(&mut self, minimize: bool) {
+        self.idle_minimize = minimize;
+    }
+
+    pub fn get_save_notes_on_page_turn(&self) -> bool {
+        self.save_notes_on_page_turn
+    }
+
+    pub fn set_save_notes_on_page_turn(&mut self, save: bool) {
+        self.save_notes_on_page_turn = save;
+    }
+
+    pub fn get_thumbnails_shown(&self) -> bool {
+        self.thumbnails_shown
+    }
+
+    pub fn set_thumbnails_shown(&mut self, shown: bool) {
+        self.thumbnails_shown = shown;
+    }
+
+    pub fn get_thumbnail_size(&self) -> ThumbnailSize {
+        self.thumbnail_size
+    }
+
+    pub fn set_thumbnail_size(&mut self, size: ThumbnailSize) {
+        self.thumbnail_size = size;
+    }
+
+    pub fn get_thumbnail_wheel(&self) -> ThumbnailWheel {
+        self.thumbnail_wheel
+    }
+
+    pub fn set_thumbnail_wheel(&mut self, wheel: ThumbnailWheel) {
+        self.thumbnail_wheel = wheel;
+    }
+
+    pub fn get_toc_shown(&self) -> bool {
+        self.toc_shown
+    }
+
+    pub fn set_toc_shown(&mut self, shown: bool) {
+        self.toc_shown = shown;
+    }
+
+    pub fn get_page_fit(&self) -> PageFit {
+        self.page_fit
+    }
+
+    pub fn set_page_fit(&mut self, fit: PageFit) {
+        self.page_fit = fit;
+    }
+
+    pub fn get_default_zoom(&self) -> Option<u32> {
+        self.default_zoom
+    }
+
+    pub fn set_default_zoom(&mut self, zoom: Option<u32>) {
+        self.default_zoom = zoom;
+    }
+
+    pub fn get_page_boundary(&self) -> PageBoundary {
+        self.page_boundary
+    }
+
+    pub fn set_page_boundary(&mut self, boundary: PageBoundary) {
+        self.page_boundary = boundary;
+    }
+
+    pub fn get_speech_rate(&self) -> u32 {
+        self.speech_rate
+    }
+
+    pub fn set_speech_rate(&mut self, rate: u32) {
+        self.speech_rate = rate.clamp(speech::MIN_RATE, speech::MAX_RATE);
+    }
+
+    pub fn get_font_step(&self) -> u32 {
+        self.font_step
+    }
+
+    pub fn set_font_step(&mut self, step: u32) {
+        self.font_step = step.max(1);
+    }
+
+    pub fn get_notes_font_size(&self) -> u32 {
+        self.notes_font_size
+    }
+
+    pub fn set_notes_font_size(&mut self, size: u32) {
+        self.notes_font_size = size;
+    }
+
+    pub fn get_font_scope(&self) -> FontScope {
+        self.font_scope
+    }
+
+    pub fn set_font_scope(&mut self, scope: FontScope) {
+        self.font_scope = scope;
+    }
+
+    // Whether a changed notes font size is kept with the open document rather than in the settings
+    pub fn keeps_document_font_size(&self, has_document: bool) -> bool {
+        self.font_scope == FontScope::PerDocument && has_document
+    }
+
+    // The colors a document keeping the given colors of its own is shown in, None if it has none: its
+    // own where it keeps some, the global mode otherwise
+    pub fn page_colors_for(&self, own: Option<PageColors>) -> PageColors {
+        own.unwrap_or(self.page_colors)
+    }
+
+    // Changes the colors of a document keeping the given colors of its own, or the global mode where
+    // it has none. Returns the document's own colors after the change
+    pub fn change_page_colors(&mut self, own: Option<PageColors>, colors: PageColors) -> Option<PageColors> {
+        if own.is_some() {
+            return Some(colors);
+        }
+        self.set_page_colors(colors);
+        None
+    }
+
+    // Notes font size for a document with the given size of its own, None if it has none or no
+    // document is open: its own while sizes are kept per document, the global size otherwise
+    pub fn notes_font_size_for(&self, own: Option<u32>) -> u32 {
+        match self.font_scope {
+            FontScope::PerDocument => own.unwrap_or(self.notes_font_size),
+            FontScope::Global => self.notes_font_size,
+        }
+    }
+
+    pub fn get_notes_format(&self) -> NotesFormat {
+        self.notes_format
+    }
+
+    pub fn set_notes_format(&mut self, format: NotesFormat) {
+        self.notes_format = format;
+    }
+
+    pub fn get_page_notes(&self) -> bool {
+        self.page_notes
+    }
+
+    pub fn set_page_notes(&mut self, page_notes: bool) {
+        self.page_notes = page_notes;
+    }
+
+    pub fn get_theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+}
+
+// A settings callback that stores the value it's given with set and saves the settings, for the many
+// settings the backend only keeps
+pub fn store_setting<T>(
+    settings: &Arc<Mutex<Settings>>,
+    set: impl Fn(&mut Settings, T) + 'static,
+) -> impl Fn(T) + 'static {
+    let settings = settings.clone();
+    move |value| {
+        let mut settings = settings.lock().unwrap();
+        set(&mut settings, value);
+        settings.save(SETTINGS_FILE);
+    }
+}
+
+// Like store_setting, for a choice the UI gives by its name. A name from_name doesn't know is reported
+// as an unknown what and nothing is stored
+pub fn store_named_setting<T: 'static>(
+    settings: &Arc<Mutex<Settings>>,
+    what: &'static str,
+    from_name: fn(&str) -> Option<T>,
+    set: impl Fn(&mut Settings, T) + 'static,
+) -> impl Fn(slint::SharedString) + 'static {
+    let store = store_setting(settings, set);
+    move |name| match from_name(name.as_str()) {
+        Some(value) => store(value),
+        None => eprintln!("Unknown {}: {}", what, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_font_scope(scope: FontScope) -> Settings {
+        let mut settings = Settings::default();
+        settings.set_font_scope(scope);
+        settings.set_notes_font_size(14);
+        settings
+    }
+
+    #[test]
+    fn the_global_scope_uses_one_size_for_all_notes() {
+        let settings = with_font_scope(FontScope::Global);
+        assert_eq!(settings.notes_font_size_for(Some(20)), 14);
+        assert_eq!(settings.notes_font_size_for(None), 14);
+        assert!(!settings.keeps_document_font_size(true));
+    }
+
+    #[test]
+    fn the_per_document_scope_uses_the_document_size() {
+        let settings = with_font_scope(FontScope::PerDocument);
+        assert_eq!(settings.notes_font_size_for(Some(20)), 20);
+        assert!(settings.keeps_document_font_size(true));
+    }
+
+    #[test]
+    fn documents_without_a_size_use_the_global_one() {
+        let settings = with_font_scope(FontScope::PerDocument);
+        assert_eq!(settings.notes_font_size_for(None), 14);
+        // without a document a change goes to the settings
+        assert!(!settings.keeps_document_font_size(false));
+    }
+
+    #[test]
+    fn older_settings_keep_the_global_size() {
+        let settings: Settings = serde_json::from_str(r#"{"notes_font_size": 18}"#).unwrap();
+        assert_eq!(settings.get_font_scope(), FontScope::Global);
+        assert_eq!(settings.notes_font_size_for(Some(20)), 18);
+    }
+
+    #[test]
+    fn the_old_resume_toggle_loads_as_last_document() {
+        let filename = txt_file::temp_path("resume.json");
+        txt_file::write_to_file(&filename, r#"{"resume_last_document": true}"#).unwrap();
+        let settings = Settings::load(&filename);
+        let _ = std::fs::remove_file(&filename);
+        assert_eq!(settings.get_startup(), Startup::LastDocument);
+    }
+
+    #[test]
+    fn the_old_labels_toggle_turned_off_loads_as_number() {
+        let filename = txt_file::temp_path("labels.json");
+        txt_file::write_to_file(&filename, r#"{"show_page_labels": false}"#).unwrap();
+        let settings = Settings::load(&filename);
+        let _ = std::fs::remove_file(&filename);
+        assert_eq!(settings.get_page_indicator(), PageIndicator::Number);
+        assert_eq!(Settings::default().get_page_indicator(), PageIndicator::Both);
+    }
+
+    #[test]
+    fn startup_names_round_trip() {
+        for name in Startup::NAMES {
+            assert_eq!(Startup::from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(Startup::default(), Startup::OpeningPage);
+    }
+
+    #[test]
+    fn a_document_s_own_colors_win_over_the_global_mode() {
+        let mut settings = Settings::default();
+        settings.set_page_colors(PageColors::Grayscale);
+        assert_eq!(settings.page_colors_for(Some(PageColors::HighContrast)), PageColors::HighContrast);
+        assert_eq!(settings.page_colors_for(None), PageColors::Grayscale);
+    }
+
+    #[test]
+    fn changes_go_to_the_document_s_own_colors_where_it_has_them() {
+        let mut settings = Settings::default();
+        let own = settings.change_page_colors(Some(PageColors::Normal), PageColors::HighContrast);
+        assert_eq!(own, Some(PageColors::HighContrast));
+        assert_eq!(settings.get_page_colors(), PageColors::Normal);
+    }
+
+    #[test]
+    fn changes_go_to_the_global_mode_otherwise() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.change_page_colors(None, PageColors::Grayscale), None);
+        assert_eq!(settings.get_page_colors(), PageColors::Grayscale);
+    }
+
+    #[test]
+    fn the_render_limit_stays_within_its_bounds() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.get_render_side(), render::MAX_RENDER_SIDE as u32);
+        settings.set_render_side(10);
+        assert_eq!(settings.get_render_side(), render::MIN_RENDER_SIDE);
+        settings.set_render_side(u32::MAX);
+        assert_eq!(settings.get_render_side(), render::MAX_RENDER_SIDE as u32);
+    }
+
+    #[test]
+    fn a_hand_edited_render_limit_is_clamped_on_use() {
+        let settings: Settings = serde_json::from_str(r#"{"render_side": 1000000}"#).unwrap();
+        assert_eq!(settings.get_render_side(), render::MAX_RENDER_SIDE as u32);
+    }
+
+    #[test]
+    fn an_unknown_choice_keeps_the_other_settings() {
+        let filename = txt_file::temp_path("unknown.json");
+        let data = r#"{"theme": "Solarized", "speech_rate": 220, "keybindings": {"overrides": {"next-page": "N"}}}"#;
+        txt_file::write_to_file(&filename, data).unwrap();
+        let settings = Settings::load(&filename);
+        let backup = txt_file::read_file(&format!("{}.bak", filename));
+        let _ = std::fs::remove_file(&filename);
+        let _ = std::fs::remove_file(format!("{}.bak", filename));
+        assert_eq!(settings.get_theme(), Theme::default());
+        assert_eq!(settings.get_speech_rate(), 220);
+        assert_eq!(settings.get_keybindings().get_binding("next-page"), "N");
+        assert_eq!(backup.unwrap(), data);
+    }
+}
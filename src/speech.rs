@@ -0,0 +1,452 @@
+use crate::interface::FileManager;
+use crate::ocr::OcrCache;
+use crate::pages;
+use crate::platform;
+use crate::settings::{store_setting, Settings};
+use crate::text::{self, TextBox};
+use crate::{App, BackendPDF};
+use pdfium_render::prelude::*;
+use slint::ComponentHandle;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::process::Child;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Words per minute the system voice may be set to
+pub const MIN_RATE: u32 = 80;
+pub const MAX_RATE: u32 = 400;
+
+// A sentence of the page, with the first and last of its character boxes so it can be highlighted
+// while it is read
+pub struct Sentence {
+    pub text: String,
+    pub first: u32,
+    pub last: u32,
+}
+
+// Splits the page's characters into sentences. The boxes hold no line breaks, so a box starting
+// below the previous one counts as a space
+pub fn sentences(boxes: &[TextBox]) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut first = 0;
+    for (index, text_box) in boxes.iter().enumerate() {
+        if let Some(previous) = index.checked_sub(1).map(|previous| &boxes[previous]) {
+            if (text_box.y - previous.y).abs() > previous.height / 2.0 && !current.ends_with(' ') {
+                current.push(' ');
+            }
+        }
+        if current.trim().is_empty() {
+            current.clear();
+            first = index as u32;
+        }
+        current.push_str(&text_box.text);
+        if text_box.text.ends_with(['.', '!', '?']) {
+            push_sentence(&mut sentences, &mut current, first, index as u32);
+        }
+    }
+    push_sentence(&mut sentences, &mut current, first, boxes.len().saturating_sub(1) as u32);
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<Sentence>, current: &mut String, first: u32, last: u32) {
+    let text = current.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().any(char::is_alphanumeric) {
+        sentences.push(Sentence { text, first, last });
+    }
+    current.clear();
+}
+
+// Sentences of one page of a document, None if the page can't be loaded. A page without
+// extractable text is read from fallback instead, e.g. its OCR text, which has no boxes to highlight
+// while reading. Without either it gives no sentences
+pub fn page_sentences(path: &str, page: u32, fallback: Option<&str>) -> Option<Vec<Sentence>> {
+    pages::with_document(path, |document| document_page_sentences(document, page, fallback))
+        .ok()
+        .flatten()
+}
+
+// page_sentences of a document already loaded
+fn document_page_sentences(document: &PdfDocument, page: u32, fallback: Option<&str>) -> Option<Vec<Sentence>> {
+    let page = pages::get_page(document, page).ok()?;
+    let boxes = text::text_boxes(&page);
+    if boxes.is_empty() {
+        if let Some(fallback) = fallback {
+            return Some(text_sentences(fallback));
+        }
+    }
+    Some(sentences(&boxes))
+}
+
+// Sentences of plain text, all pointing at no character box
+fn text_sentences(text: &str) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        current.push(if c.is_whitespace() { ' ' } else { c });
+        if matches!(c, '.' | '!' | '?') {
+            push_sentence(&mut sentences, &mut current, u32::MAX, u32::MAX);
+        }
+    }
+    push_sentence(&mut sentences, &mut current, u32::MAX, u32::MAX);
+    sentences
+}
+
+// The next page with text to read and its sentences, None if the document has no more
+type NextPage = Option<(u32, Vec<Sentence>)>;
+
+// Where reading aloud is: the page and sentence, and the voice process reading it. Pausing stops the
+// voice and keeps the sentence, so playing again starts that sentence over
+#[derive(Default)]
+pub struct ReadAloud {
+    pub path: String,
+    pub page: u32,
+    sentences: Vec<Sentence>,
+    current: usize,
+    voice: Option<Child>,
+    pub playing: bool,
+    // while the page is done and the next page with text is looked for, where it will be sent
+    next_page: Option<Receiver<NextPage>>,
+}
+
+impl ReadAloud {
+    pub fn load_page(&mut self, path: &str, page: u32, sentences: Vec<Sentence>) {
+        self.silence();
+        self.path = path.to_string();
+        self.page = page;
+        self.sentences = sentences;
+        self.current = 0;
+        self.next_page = None;
+    }
+
+    pub fn is_on(&self, path: &str, page: u32) -> bool {
+        self.path == path && self.page == page && !self.sentences.is_empty()
+    }
+
+    pub fn current(&self) -> Option<&Sentence> {
+        self.sentences.get(self.current)
+    }
+
+    pub fn speak_current(&mut self, rate: u32) -> Result<(), String> {
+        self.silence();
+        let Some(sentence) = self.sentences.get(self.current) else {
+            return Ok(());
+        };
+        self.voice = Some(platform::speak(sentence.text.as_str(), rate)?);
+        Ok(())
+    }
+
+    // Whether the voice got through the current sentence since the last call
+    pub fn sentence_done(&mut self) -> bool {
+        match self.voice.as_mut().map(|voice| voice.try_wait()) {
+            Some(Ok(Some(_))) | Some(Err(_)) => {
+                self.voice = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Moves to the next sentence, current is None once the page is done
+    pub fn advance(&mut self) {
+        self.current += 1;
+    }
+
+    pub fn silence(&mut self) {
+        if let Some(mut voice) = self.voice.take() {
+            let _ = voice.kill();
+            let _ = voice.wait();
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.silence();
+        self.playing = false;
+        self.sentences.clear();
+        self.current = 0;
+        self.next_page = None;
+    }
+
+    pub fn is_seeking(&self) -> bool {
+        self.next_page.is_some()
+    }
+}
+
+// Don't leave the voice talking after PDFer closes
+impl Drop for ReadAloud {
+    fn drop(&mut self) {
+        self.silence();
+    }
+}
+
+// Registers the callbacks that read the current page aloud
+pub fn bind(
+    app: &App,
+    settings: &Arc<Mutex<Settings>>,
+    file_manager: &Arc<Mutex<FileManager>>,
+    ocr_cache: &Arc<Mutex<OcrCache>>,
+    schedule_save: &Rc<dyn Fn()>,
+) {
+    // Reading aloud runs one sentence at a time through the system voice. The timer watches for the
+    // voice to finish a sentence and moves on, it only runs while playing
+    let read_aloud = Rc::new(RefCell::new(ReadAloud::default()));
+    let read_timer = Rc::new(slint::Timer::default());
+
+    /*  CALLBACK:
+        Starts reading the current page aloud, or carries on from the sentence it was paused at. Reading
+        continues onto the following pages, skipping those without text, until the document ends
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_read_aloud_play({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        let ocr_cache = ocr_cache.clone();
+        let read_aloud = read_aloud.clone();
+        let read_timer = read_timer.clone();
+        let schedule_save = schedule_save.clone();
+        move || {
+            let app = app_weak.unwrap();
+            let (path, page, modified) = {
+                let mut file_manager = cloned_file_manager.lock().unwrap();
+                let page = file_manager.get_cur_file_info().get_cur_page();
+                let modified = file_manager.get_cur_file_info().get_modified();
+                (file_manager.get_cur_path().unwrap(), page, modified)
+            };
+            let rate = cloned_settings.lock().unwrap().get_speech_rate();
+            let mut reader = read_aloud.borrow_mut();
+            if !reader.is_on(&path, page) {
+                let sentences = sentences_to_read(&ocr_cache, &path, modified, page);
+                reader.load_page(&path, page, sentences);
+            }
+            reader.playing = true;
+            app.global::<BackendPDF>().set_read_aloud_state("playing".into());
+            app.global::<BackendPDF>().set_read_aloud_status("".into());
+            if !continue_reading(&app, &cloned_file_manager, &ocr_cache, &mut reader, rate, &*schedule_save) {
+                return;
+            }
+
+            let app_weak = app_weak.clone();
+            let cloned_file_manager = cloned_file_manager.clone();
+            let cloned_settings = cloned_settings.clone();
+            let ocr_cache = ocr_cache.clone();
+            let read_aloud = read_aloud.clone();
+            let schedule_save = schedule_save.clone();
+            read_timer.start(slint::TimerMode::Repeated, Duration::from_millis(150), move || {
+                let Some(app) = app_weak.upgrade() else {
+                    return;
+                };
+                let mut reader = read_aloud.borrow_mut();
+                if !reader.playing {
+                    return;
+                }
+                // while the next page with text is looked for there's no voice to wait for
+                if !reader.is_seeking() {
+                    if !reader.sentence_done() {
+                        return;
+                    }
+                    reader.advance();
+                }
+                let rate = cloned_settings.lock().unwrap().get_speech_rate();
+                continue_reading(&app, &cloned_file_manager, &ocr_cache, &mut reader, rate, &*schedule_save);
+            });
+        }
+    });
+
+    /*  CALLBACK:
+        Pauses reading aloud, playing again restarts the sentence it was in
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_read_aloud_pause({
+        let app_weak = app.as_weak();
+        let read_aloud = read_aloud.clone();
+        let read_timer = read_timer.clone();
+        move || {
+            read_timer.stop();
+            let mut reader = read_aloud.borrow_mut();
+            reader.silence();
+            reader.playing = false;
+            app_weak.unwrap().global::<BackendPDF>().set_read_aloud_state("paused".into());
+        }
+    });
+
+    /*  CALLBACK:
+        Stops reading aloud, playing again starts at the top of the current page
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_read_aloud_stop({
+        let app_weak = app.as_weak();
+        let read_aloud = read_aloud.clone();
+        let read_timer = read_timer.clone();
+        move || {
+            read_timer.stop();
+            stop_reading(&app_weak.unwrap().global::<BackendPDF>(), &mut read_aloud.borrow_mut());
+        }
+    });
+
+    /*  CALLBACK:
+        Stops reading aloud once the document or page shown is no longer the one being read, after
+        opening another file or turning the page by hand. Called on every refresh. The pages reading
+        aloud turns itself refresh while it's busy with them, and are left alone
+
+        # Arguments
+        N/A
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_read_aloud_follow({
+        let app_weak = app.as_weak();
+        let cloned_file_manager = file_manager.clone();
+        let read_aloud = read_aloud.clone();
+        let read_timer = read_timer.clone();
+        move || {
+            let Ok(mut reader) = read_aloud.try_borrow_mut() else {
+                return;
+            };
+            if reader.current().is_none() || reads_shown_page(&cloned_file_manager, &reader) {
+                return;
+            }
+            read_timer.stop();
+            stop_reading(&app_weak.unwrap().global::<BackendPDF>(), &mut reader);
+        }
+    });
+
+    /*  CALLBACK:
+        Stores the reading speed, used from the next sentence on
+
+        # Arguments
+        * 'rate' - words per minute, clamped to what the system voices manage
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_speech_rate(store_setting(settings, |settings, rate: i32| {
+        settings.set_speech_rate(rate.max(0) as u32)
+    }));
+}
+
+// Sentences to read aloud on a page, from its OCR text when it has no text of its own
+fn sentences_to_read(ocr_cache: &Mutex<OcrCache>, path: &str, modified: u64, page: u32) -> Vec<Sentence> {
+    let recognized = ocr_cache.lock().unwrap().page_text(path, modified, page).map(str::to_string);
+    page_sentences(path, page, recognized.as_deref()).unwrap_or_default()
+}
+
+// Looks for the first page from first on with sentences to read, loading the document once on a
+// thread of its own so a run of pages without text, e.g. a scan never recognized, doesn't hold up
+// the window
+fn seek_page_with_text(
+    ocr_cache: &Mutex<OcrCache>,
+    path: &str,
+    modified: u64,
+    first: u32,
+    count: u32,
+) -> Receiver<NextPage> {
+    let recognized: HashMap<u32, String> = ocr_cache.lock().unwrap().document_text(path, modified);
+    let path = path.to_string();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let found = pages::with_document(&path, |document| {
+            (first..count).find_map(|page| {
+                let fallback = recognized.get(&page).map(String::as_str);
+                document_page_sentences(document, page, fallback)
+                    .filter(|sentences| !sentences.is_empty())
+                    .map(|sentences| (page, sentences))
+            })
+        });
+        let found = found.ok().flatten();
+        let _ = sender.send(found);
+    });
+    receiver
+}
+
+// Keeps reading aloud going: speaks the current sentence and highlights it, or when the page is done
+// looks for the next page with text and turns to it once found. At the end of the document, or if no
+// voice could be started, reading stops and false is returned
+fn continue_reading(
+    app: &App,
+    file_manager: &Mutex<FileManager>,
+    ocr_cache: &Mutex<OcrCache>,
+    reader: &mut ReadAloud,
+    rate: u32,
+    schedule_save: &dyn Fn(),
+) -> bool {
+    let backend = app.global::<BackendPDF>();
+    // another document was opened or the page turned by hand since the last sentence
+    if !reads_shown_page(file_manager, reader) {
+        stop_reading(&backend, reader);
+        return false;
+    }
+    if reader.current().is_none() {
+        if !reader.is_seeking() {
+            let (modified, count) = {
+                let mut file_manager = file_manager.lock().unwrap();
+                let file = file_manager.get_cur_file_info();
+                (file.get_modified(), file.get_page_count())
+            };
+            reader.next_page = Some(seek_page_with_text(ocr_cache, &reader.path, modified, reader.page + 1, count));
+        }
+        let found = match reader.next_page.as_ref().map(Receiver::try_recv) {
+            // the timer comes back until the search is done
+            Some(Err(TryRecvError::Empty)) => return true,
+            Some(Ok(found)) => found,
+            _ => None,
+        };
+        let Some((next, sentences)) = found else {
+            stop_reading(&backend, reader);
+            return false;
+        };
+        // only the page reached is turned to, the ones without text in between are never shown
+        file_manager.lock().unwrap().get_cur_file_info().set_cur_page(next);
+        schedule_save();
+        let path = reader.path.clone();
+        reader.load_page(&path, next, sentences);
+        backend.set_scroll_y(0.0);
+        backend.invoke_refresh();
+    }
+
+    let sentence = reader.current().unwrap();
+    backend.set_reading_page(reader.page as i32);
+    backend.set_reading_first(sentence.first as i32);
+    backend.set_reading_last(sentence.last as i32);
+    if let Err(e) = reader.speak_current(rate) {
+        eprintln!("{}", e);
+        stop_reading(&backend, reader);
+        backend.set_read_aloud_status(e.into());
+        return false;
+    }
+    true
+}
+
+// Whether the document and page shown are the ones being read
+fn reads_shown_page(file_manager: &Mutex<FileManager>, reader: &ReadAloud) -> bool {
+    let mut file_manager = file_manager.lock().unwrap();
+    file_manager.get_cur_path().is_ok_and(|path| path == reader.path)
+        && file_manager.get_cur_file_info().get_cur_page() == reader.page
+}
+
+fn stop_reading(backend: &BackendPDF, reader: &mut ReadAloud) {
+    reader.stop();
+    backend.set_read_aloud_state("stopped".into());
+    backend.set_reading_first(-1);
+    backend.set_reading_last(-1);
+}
@@ -0,0 +1,259 @@
+use crate::options::named_options;
+use crate::render;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use slint::Model;
+
+// Pages sampled, spread over the document, to tell a scan from a document with a text layer
+const SCAN_SAMPLE_PAGES: u16 = 5;
+// Fewer extractable characters than this per sampled page on average reads as a scan. Scans often
+// carry a stray page number or stamp as text, so the odd character doesn't count as a text layer
+const SCAN_MIN_CHARS: usize = 20;
+
+// A character of the page and its box, in pixels of the image render_page produces
+pub struct TextBox {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// Character boxes for a page, scaled like the rendered image so an overlay lines up with it. PDF space
+// starts at the bottom left and images at the top left, so y is flipped
+pub fn text_boxes(page: &PdfPage) -> Vec<TextBox> {
+    let page_width = page.width().value;
+    let page_height = page.height().value;
+    let (width, height) = render::page_render_size(page);
+    let scale_x = width as f32 / page_width;
+    let scale_y = height as f32 / page_height;
+
+    let Ok(text) = page.text() else {
+        return Vec::new();
+    };
+    text.chars()
+        .iter()
+        .filter_map(|ch| {
+            let c = ch.unicode_char()?;
+            if c.is_control() {
+                return None;
+            }
+            let bounds = ch.loose_bounds().ok()?;
+            Some(TextBox {
+                text: c.to_string(),
+                x: bounds.left().value * scale_x,
+                y: (page_height - bounds.top().value) * scale_y,
+                width: bounds.width().value * scale_x,
+                height: bounds.height().value * scale_y,
+            })
+        })
+        .collect()
+}
+
+// First and last index of the character boxes whose centre is inside the rectangle, None if there are none
+pub fn selected_range(boxes: &slint::ModelRc<crate::TextBox>, x0: f32, y0: f32, x1: f32, y1: f32) -> Option<(u32, u32)> {
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+    let indices: Vec<u32> = boxes
+        .iter()
+        .filter(|text_box| {
+            let x = text_box.x + text_box.w / 2.0;
+            let y = text_box.y + text_box.h / 2.0;
+            x >= left && x <= right && y >= top && y <= bottom
+        })
+        .map(|text_box| text_box.index as u32)
+        .collect();
+    Some((*indices.iter().min()?, *indices.iter().max()?))
+}
+
+// All text of the page in reading order, normalized for the document's script
+pub fn page_text(page: &PdfPage, script: TextScript) -> String {
+    match page.text() {
+        Ok(text) => normalize(&text.all(), script),
+        Err(_) => String::new(),
+    }
+}
+
+// Whether the document looks like scanned images without a text layer, judged from how much text a
+// few pages spread over it have. None if it can't be opened, Some(false) for a document without pages
+pub fn detect_scanned(path: &str) -> Option<bool> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None).ok()?;
+    let characters: Vec<usize> = sampled_pages(document.pages().len())
+        .filter_map(|index| document.pages().get(index).ok())
+        .map(|page| match page.text() {
+            Ok(text) => text.all().chars().filter(|c| !c.is_whitespace()).count(),
+            Err(_) => 0,
+        })
+        .collect();
+    Some(looks_scanned(&characters))
+}
+
+// Indices of the pages detect_scanned samples in a document of count pages. Worked out in u32, the
+// product overflows a u16 past 13107 pages
+fn sampled_pages(count: u16) -> impl Iterator<Item = u16> {
+    let samples = count.min(SCAN_SAMPLE_PAGES);
+    (0..samples).map(move |sample| (u32::from(sample) * u32::from(count) / u32::from(samples)) as u16)
+}
+
+// Whether sampled pages with these numbers of extractable characters are a scan. A document without
+// pages has nothing to sample and isn't one
+fn looks_scanned(characters: &[usize]) -> bool {
+    !characters.is_empty() && characters.iter().sum::<usize>() < SCAN_MIN_CHARS * characters.len()
+}
+
+// Writing system a document is in, which decides how its extracted text is cleaned up for reading
+// and searching. Auto picks one from the text itself
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum TextScript {
+        #[default]
+        Auto => "Auto",
+        Latin => "Latin",
+        // Chinese, Japanese and Korean
+        Cjk => "CJK",
+        // Arabic, Hebrew and other right-to-left scripts
+        Rtl => "Right to left",
+    }
+}
+
+impl TextScript {
+    // The script the text is mostly written in, for Auto. Anything without a clear majority of CJK or
+    // right-to-left letters is treated as Latin, which leaves it as extracted
+    fn detect(text: &str) -> Self {
+        let (mut cjk, mut rtl, mut letters) = (0, 0, 0);
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            letters += 1;
+            if is_cjk(c) {
+                cjk += 1;
+            } else if is_rtl(c) {
+                rtl += 1;
+            }
+        }
+        if cjk * 2 > letters {
+            TextScript::Cjk
+        } else if rtl * 2 > letters {
+            TextScript::Rtl
+        } else {
+            TextScript::Latin
+        }
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' // hiragana, katakana
+        | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' // ideographs
+        | '\u{1100}'..='\u{11ff}' | '\u{ac00}'..='\u{d7af}' // hangul
+        | '\u{3000}'..='\u{303f}' | '\u{ff00}'..='\u{ffef}') // CJK punctuation and full width forms
+}
+
+// Chinese and Japanese run their words together, Korean puts spaces between them like Latin text
+fn is_unspaced(c: char) -> bool {
+    is_cjk(c) && !matches!(c, '\u{1100}'..='\u{11ff}' | '\u{ac00}'..='\u{d7af}')
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08ff}' | '\u{fb1d}'..='\u{fdff}' | '\u{fe70}'..='\u{feff}')
+}
+
+// Cleans up extracted text so it reads and matches the way it was written. CJK has no spaces between
+// words, so the spaces and line breaks pdfium puts between Chinese and Japanese characters are dropped
+// and full width letters and digits become their ASCII forms. Right-to-left text loses the direction marks and the
+// tatweel stretching its words, which would otherwise split matches. Latin text is left as it is
+pub fn normalize(text: &str, script: TextScript) -> String {
+    let script = match script {
+        TextScript::Auto => TextScript::detect(text),
+        script => script,
+    };
+    match script {
+        TextScript::Auto | TextScript::Latin => text.to_string(),
+        TextScript::Cjk => {
+            let chars: Vec<char> = text
+                .chars()
+                .map(|c| match c {
+                    '\u{3000}' => ' ',
+                    '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+                    c => c,
+                })
+                .collect();
+            let mut normalized = String::with_capacity(text.len());
+            for (i, &c) in chars.iter().enumerate() {
+                if c.is_whitespace() {
+                    let before = chars[..i].iter().rev().find(|c| !c.is_whitespace());
+                    let after = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                    if before.is_some_and(|&c| is_unspaced(c)) && after.is_some_and(|&c| is_unspaced(c)) {
+                        continue;
+                    }
+                }
+                normalized.push(c);
+            }
+            normalized
+        }
+        TextScript::Rtl => text
+            .chars()
+            .filter(|c| !matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' | '\u{0640}'))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Japanese as pdfium extracts it, with spaces and a line break between runs of characters, and
+    // full width letters. The space before the letters stays, it separates two scripts
+    const JAPANESE: &str = "東京 の 天気 は\n晴れ です ＰＤＦ１";
+
+    #[test]
+    fn the_cjk_hint_joins_what_extraction_split() {
+        assert_eq!(normalize(JAPANESE, TextScript::Cjk), "東京の天気は晴れです PDF1");
+        // without it a search for 天気は晴れ wouldn't match
+        assert_eq!(normalize(JAPANESE, TextScript::Latin), JAPANESE);
+    }
+
+    #[test]
+    fn auto_detects_the_script() {
+        assert_eq!(normalize(JAPANESE, TextScript::Auto), normalize(JAPANESE, TextScript::Cjk));
+        assert_eq!(normalize("plain text", TextScript::Auto), "plain text");
+    }
+
+    #[test]
+    fn korean_keeps_its_spaces() {
+        assert_eq!(normalize("서울 날씨", TextScript::Cjk), "서울 날씨");
+    }
+
+    #[test]
+    fn the_rtl_hint_drops_direction_marks_and_tatweel() {
+        assert_eq!(normalize("\u{200f}مرحـــبا", TextScript::Rtl), "مرحبا");
+        assert_eq!(normalize("\u{200f}مرحـــبا", TextScript::Auto), "مرحبا");
+    }
+
+    #[test]
+    fn samples_spread_over_the_document() {
+        assert_eq!(sampled_pages(100).collect::<Vec<_>>(), [0, 20, 40, 60, 80]);
+        assert_eq!(sampled_pages(3).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(sampled_pages(0).count(), 0);
+        // no overflow for the longest document pdfium can address
+        assert_eq!(sampled_pages(u16::MAX).last(), Some(52428));
+    }
+
+    #[test]
+    fn scanned_pages_have_next_to_no_text() {
+        // a scan with a stamped page number on some pages
+        assert!(looks_scanned(&[0, 2, 0, 3, 1]));
+    }
+
+    #[test]
+    fn digital_pages_have_a_text_layer() {
+        assert!(!looks_scanned(&[1800, 2100, 1650, 1900, 2000]));
+        // a blank cover and an image plate don't make it a scan
+        assert!(!looks_scanned(&[0, 1800, 0, 1900, 2000]));
+    }
+
+    #[test]
+    fn documents_without_pages_are_not_scans() {
+        assert!(!looks_scanned(&[]));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::interface::FileManager;
+use crate::options::named_options;
+use crate::pages;
+use crate::render;
+use crate::settings::{self, store_named_setting, store_setting, Settings};
+use crate::{App, BackendPDF, MinimapStrip};
+use image::RgbaImage;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use slint::{ComponentHandle, Image, VecModel};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// Covers are cached here, next to database.json
+pub const THUMBNAIL_DIR: &str = "thumbnails";
+// Pixel budget of a cover, plenty for a recents tile
+const THUMBNAIL_PIXELS: f32 = 60_000.0;
+// Huge documents get one strip per run of pages, so the mini-map never renders more than this
+const MAX_STRIPS: u32 = 200;
+
+// How big the mini-map strips are. Small only tells text from figures and blank pages, larger sizes
+// take more of the window for a readable preview
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum ThumbnailSize {
+        #[default]
+        Small => "Small",
+        Medium => "Medium",
+        Large => "Large",
+    }
+}
+
+impl ThumbnailSize {
+    // Pixel budget of a strip. Strips are cached per budget, so each size has its own cache files
+    fn pixels(&self) -> f32 {
+        match self {
+            ThumbnailSize::Small => 1_500.0,
+            ThumbnailSize::Medium => 6_000.0,
+            ThumbnailSize::Large => 16_000.0,
+        }
+    }
+
+    // Width of the thumbnail column in logical pixels, before the UI scale
+    pub fn column_width(&self) -> f32 {
+        match self {
+            ThumbnailSize::Small => 50.0,
+            ThumbnailSize::Medium => 90.0,
+            ThumbnailSize::Large => 140.0,
+        }
+    }
+}
+
+// What the mouse wheel does over the thumbnail column. Scroll moves the strips like any other list,
+// Turn Pages leaves them where they are and steps through the document instead
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum ThumbnailWheel {
+        #[default]
+        Scroll => "Scroll Strips",
+        TurnPages => "Turn Pages",
+    }
+}
+
+// A mini-map strip, the image of the first of the pages it stands for
+pub struct Strip {
+    pub image: Image,
+    pub first: u32,
+    pub last: u32,
+}
+
+// Cache file for a page at a pixel budget. The modified time is part of the name so an edited PDF
+// gets new images
+fn cache_path(path: &str, modified: u64, page: u32, pixels: f32) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    page.hash(&mut hasher);
+    (pixels as u32).hash(&mut hasher);
+    PathBuf::from(THUMBNAIL_DIR).join(format!("{:016x}.png", hasher.finish()))
+}
+
+fn render_and_cache(page: &PdfPage, pixels: f32, cache_file: &PathBuf) -> Result<RgbaImage, PdfiumError> {
+    let (width, height) = render::render_target(page.width().value, page.height().value, pixels);
+    let thumbnail = page
+        .render_with_config(&PdfRenderConfig::new().set_target_size(width, height))?
+        .as_image()
+        .into_rgba8();
+
+    if let Err(e) = fs::create_dir_all(THUMBNAIL_DIR) {
+        eprintln!("Error creating thumbnail cache: {}", e);
+    } else if let Err(e) = thumbnail.save(cache_file) {
+        eprintln!("Error caching thumbnail: {}", e);
+    }
+    Ok(thumbnail)
+}
+
+// First page of the document as a small image, read from the disk cache or rendered and cached.
+// None if the document can't be read
+pub fn cover(path: &str, modified: u64) -> Option<Image> {
+    let cache_file = cache_path(path, modified, 0, THUMBNAIL_PIXELS);
+    if let Ok(cached) = image::open(&cache_file) {
+        return Some(render::to_slint_image(&cached.into_rgba8()));
+    }
+
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None).ok()?;
+    let page = document.pages().get(0).ok()?;
+    let thumbnail = render_and_cache(&page, THUMBNAIL_PIXELS, &cache_file).ok()?;
+    Some(render::to_slint_image(&thumbnail))
+}
+
+// First page of each strip of a document with count pages. Up to MAX_STRIPS pages get a strip each,
+// past that each strip covers an equal run of pages
+fn strip_starts(count: u32) -> (impl Iterator<Item = u32>, u32) {
+    let per_strip = count.div_ceil(MAX_STRIPS).max(1);
+    ((0..count).step_by(per_strip as usize), per_strip)
+}
+
+// Strips of the whole document for the mini-map at the given size, through the same disk cache as
+// the covers. A page that doesn't render gets a placeholder strip and is listed in the second value,
+// zero based
+pub fn minimap(path: &str, modified: u64, size: ThumbnailSize) -> (Vec<Strip>, Vec<u32>) {
+    let pdfium = Pdfium::default();
+    let Ok(document) = pdfium.load_pdf_from_file(path, None) else {
+        return (Vec::new(), Vec::new());
+    };
+    let count = pages::page_count(&document);
+    let (starts, per_strip) = strip_starts(count);
+    let pixels = size.pixels();
+    let mut failed = Vec::new();
+
+    let strips = starts
+        .map(|first| {
+            let cache_file = cache_path(path, modified, first, pixels);
+            let image = match image::open(&cache_file) {
+                Ok(cached) => cached.into_rgba8(),
+                Err(_) => pages::get_page(&document, first)
+                    .and_then(|page| render_and_cache(&page, pixels, &cache_file))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error rendering page {} for the mini-map: {}", first + 1, e);
+                        failed.push(first);
+                        let (width, height) = render::render_target(612.0, 792.0, pixels);
+                        render::error_placeholder(width as u32, height as u32)
+                    }),
+            };
+            Strip {
+                image: render::to_slint_image(&image),
+                first,
+                last: (first + per_strip - 1).min(count - 1),
+            }
+        })
+        .collect();
+    (strips, failed)
+}
+
+// Deletes the cached strips of a document at a size no longer used, so changing the size doesn't
+// leave a second set of images behind for every document viewed
+pub fn forget_minimap(path: &str, modified: u64, count: u32, size: ThumbnailSize) {
+    let (starts, _) = strip_starts(count);
+    for first in starts {
+        let cache_file = cache_path(path, modified, first, size.pixels());
+        if cache_file.exists() {
+            if let Err(e) = fs::remove_file(&cache_file) {
+                eprintln!("Error removing cached thumbnail: {}", e);
+            }
+        }
+    }
+}
+
+// Registers the thumbnail column's callbacks: the mini-map strips, their size, what the wheel does over
+// them and whether the column is shown
+pub fn bind(app: &App, settings: &Arc<Mutex<Settings>>, file_manager: &Arc<Mutex<FileManager>>) {
+    /*  CALLBACK:
+        Returns the mini-map of the open document, strips of its pages from the thumbnail cache
+
+        # Arguments
+        * 'size' - thumbnail size name: Small, Medium or Large
+
+        # Return
+        * A Slint vector type of strips with the pages each stands for, flagged if the page didn't render
+    */
+    app.global::<BackendPDF>().on_get_minimap({
+        let cloned_file_manager = file_manager.clone();
+        move |size| {
+            let size = ThumbnailSize::from_name(size.as_str()).unwrap_or_default();
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            let file_path = file_manager.get_cur_path().unwrap();
+            let modified = file_manager.get_cur_file_info().get_modified();
+            let (strips, failed) = minimap(file_path.as_str(), modified, size);
+            let strips: Vec<MinimapStrip> = strips
+                .into_iter()
+                .map(|strip| MinimapStrip {
+                    image: strip.image,
+                    first_page: strip.first as i32,
+                    last_page: strip.last as i32,
+                    failed: failed.contains(&strip.first),
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(strips))
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the thumbnail sizes for the thumbnail size picker
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of size names
+    */
+    app.global::<BackendPDF>().on_get_thumbnail_sizes(|| {
+        let names: Vec<slint::SharedString> =
+            ThumbnailSize::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Returns how wide the thumbnail column is at a thumbnail size
+
+        # Arguments
+        * 'size' - thumbnail size name: Small, Medium or Large
+
+        # Return
+        * The width in logical pixels, before the UI scale
+    */
+    app.global::<BackendPDF>().on_get_thumbnail_width(|size| {
+        ThumbnailSize::from_name(size.as_str()).unwrap_or_default().column_width()
+    });
+
+    /*  CALLBACK:
+        Sets and saves the thumbnail size. The open document's strips at the old size are dropped from
+        the cache, the mini-map fetches them again at the new size
+
+        # Arguments
+        * 'name' - thumbnail size name: Small, Medium or Large
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_thumbnail_size({
+        let cloned_file_manager = file_manager.clone();
+        let cloned_settings = settings.clone();
+        move |name| {
+            let Some(size) = ThumbnailSize::from_name(name.as_str()) else {
+                eprintln!("Unknown thumbnail size: {}", name);
+                return;
+            };
+            let mut settings = cloned_settings.lock().unwrap();
+            let previous = settings.get_thumbnail_size();
+            if previous == size {
+                return;
+            }
+            settings.set_thumbnail_size(size);
+            settings.save(settings::SETTINGS_FILE);
+            drop(settings);
+
+            let mut file_manager = cloned_file_manager.lock().unwrap();
+            if let Ok(file_path) = file_manager.get_cur_path() {
+                let modified = file_manager.get_cur_file_info().get_modified();
+                let count = file_manager.get_cur_file_info().get_page_count();
+                forget_minimap(file_path.as_str(), modified, count, previous);
+            }
+        }
+    });
+
+    /*  CALLBACK:
+        Returns the names of what the wheel can do over the thumbnail column
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of wheel behavior names
+    */
+    app.global::<BackendPDF>().on_get_thumbnail_wheels(|| {
+        let names: Vec<slint::SharedString> =
+            ThumbnailWheel::NAMES.iter().map(|&name| name.into()).collect();
+        slint::ModelRc::new(VecModel::from(names))
+    });
+
+    /*  CALLBACK:
+        Sets and saves what the wheel does over the thumbnail column
+
+        # Arguments
+        * 'name' - wheel behavior name: Scroll Strips or Turn Pages
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_thumbnail_wheel(store_named_setting(
+        settings,
+        "thumbnail wheel behavior",
+        ThumbnailWheel::from_name,
+        Settings::set_thumbnail_wheel,
+    ));
+
+    /*  CALLBACK:
+        Stores whether the thumbnail column is shown next to the page
+
+        # Arguments
+        * 'shown' - true to show it
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_thumbnails_shown(store_setting(
+        settings,
+        Settings::set_thumbnails_shown,
+    ));
+}
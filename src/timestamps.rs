@@ -0,0 +1,94 @@
+use crate::options::named_options;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A relative time is only shown for the past week, older ones are given as a date either way
+const RELATIVE_LIMIT: u64 = 7 * 24 * 60 * 60;
+
+// How the time a recent file was last read is shown
+named_options! {
+    #[derive(Default, Clone, Copy, PartialEq, Debug)]
+    #[derive(Serialize, Deserialize)]
+    pub enum TimestampFormat {
+        #[default]
+        Relative => "Relative",
+        Absolute => "Date",
+    }
+}
+
+// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// "2 hours ago" or "Dec 8, 2024" for a time in seconds since the Unix epoch, dates in the local
+// timezone. Empty for 0, which files read before the time was recorded have
+pub fn format(timestamp: u64, now: u64, format: TimestampFormat) -> String {
+    if timestamp == 0 {
+        return String::new();
+    }
+    let elapsed = now.saturating_sub(timestamp);
+    if format == TimestampFormat::Relative && elapsed < RELATIVE_LIMIT {
+        return relative(elapsed);
+    }
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.format("%b %-d, %Y").to_string(),
+        None => String::new(),
+    }
+}
+
+fn relative(elapsed: u64) -> String {
+    let (count, unit) = match elapsed {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (elapsed / 60, "minute"),
+        3600..=86399 => (elapsed / 3600, "hour"),
+        _ => (elapsed / 86400, "day"),
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_733_650_000;
+
+    #[test]
+    fn relative_times_fall_into_buckets() {
+        assert_eq!(relative(0), "just now");
+        assert_eq!(relative(59), "just now");
+        assert_eq!(relative(60), "1 minute ago");
+        assert_eq!(relative(45 * 60), "45 minutes ago");
+        assert_eq!(relative(3600), "1 hour ago");
+        assert_eq!(relative(23 * 3600 + 3599), "23 hours ago");
+        assert_eq!(relative(86400), "1 day ago");
+        assert_eq!(relative(6 * 86400), "6 days ago");
+    }
+
+    #[test]
+    fn older_times_are_dates() {
+        let formatted = format(NOW - RELATIVE_LIMIT, NOW, TimestampFormat::Relative);
+        assert!(!formatted.ends_with("ago"));
+        assert_eq!(formatted, format(NOW - RELATIVE_LIMIT, NOW, TimestampFormat::Absolute));
+    }
+
+    #[test]
+    fn dates_are_in_the_local_timezone() {
+        let expected = Local.timestamp_opt(NOW as i64, 0).unwrap().format("%b %-d, %Y").to_string();
+        assert_eq!(format(NOW, NOW, TimestampFormat::Absolute), expected);
+    }
+
+    #[test]
+    fn unrecorded_and_future_times() {
+        assert_eq!(format(0, NOW, TimestampFormat::Relative), "");
+        // a clock set back since shows as just now rather than a negative time
+        assert_eq!(format(NOW + 100, NOW, TimestampFormat::Relative), "just now");
+    }
+}
@@ -0,0 +1,183 @@
+use crate::interface::FileManager;
+use crate::pages::PageBoundary;
+use crate::settings::{store_setting, Settings};
+use crate::{App, BackendPDF, TocItem};
+use pdfium_render::prelude::*;
+use slint::{ComponentHandle, VecModel};
+use std::sync::{Arc, Mutex};
+
+// Bookmarks nested deeper than this are left out, as are any past MAX_ENTRIES, which also guards
+// against a malformed outline that loops back on itself
+const MAX_DEPTH: u32 = 8;
+const MAX_ENTRIES: usize = 2000;
+
+// A line of the document's table of contents, from its bookmarks
+#[derive(Clone)]
+pub struct TocEntry {
+    pub title: String,
+    // 0-based target page, None for a bookmark that doesn't point into the document
+    pub page: Option<u32>,
+    pub depth: u32,
+}
+
+// The bookmarks of the document in reading order, each with its nesting depth. Empty for a document
+// without an outline
+pub fn contents(path: &str) -> Result<Vec<TocEntry>, PdfiumError> {
+    let pdfium = Pdfium::default();
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let mut entries = Vec::new();
+    add_siblings(document.bookmarks().root(), 0, &mut entries);
+    Ok(entries)
+}
+
+fn add_siblings(first: Option<PdfBookmark>, depth: u32, entries: &mut Vec<TocEntry>) {
+    let mut next = first;
+    while let Some(bookmark) = next {
+        if entries.len() >= MAX_ENTRIES {
+            return;
+        }
+        entries.push(TocEntry {
+            title: bookmark.title().unwrap_or_default().trim().to_string(),
+            page: target_page(&bookmark),
+            depth,
+        });
+        if depth + 1 < MAX_DEPTH {
+            add_siblings(bookmark.first_child(), depth + 1, entries);
+        }
+        next = bookmark.next_sibling();
+    }
+}
+
+// Most bookmarks carry their destination, others only a go-to action holding it
+fn target_page(bookmark: &PdfBookmark) -> Option<u32> {
+    let page = match bookmark.destination() {
+        Some(destination) => destination.page_index().ok(),
+        None => bookmark
+            .action()?
+            .as_local_destination_action()?
+            .destination()
+            .ok()?
+            .page_index()
+            .ok(),
+    };
+    page.map(u32::from)
+}
+
+// The pages bookmarks point to, in page order and each once
+pub fn bookmarked_pages(entries: &[TocEntry]) -> Vec<u32> {
+    let mut pages: Vec<u32> = entries.iter().filter_map(|entry| entry.page).collect();
+    pages.sort_unstable();
+    pages.dedup();
+    pages
+}
+
+// The nearest bookmarked page after the current one, or before it, or None to stay. Past the last
+// bookmark, or before the first, the boundary decides like it does for page turns
+pub fn step_bookmark(pages: &[u32], current: u32, forward: bool, boundary: PageBoundary) -> Option<u32> {
+    let next = if forward {
+        pages.iter().copied().find(|&page| page > current)
+    } else {
+        pages.iter().rev().copied().find(|&page| page < current)
+    };
+    match next {
+        Some(page) => Some(page),
+        None if boundary == PageBoundary::Wrap => {
+            let wrapped = if forward { pages.first() } else { pages.last() };
+            wrapped.copied().filter(|&page| page != current)
+        }
+        None => None,
+    }
+}
+
+// Registers the callbacks of the table of contents next to the page
+pub fn bind(app: &App, settings: &Arc<Mutex<Settings>>, file_manager: &Arc<Mutex<FileManager>>) {
+    /*  CALLBACK:
+        Returns the table of contents of the current PDF, from its bookmarks
+
+        # Arguments
+        N/A
+
+        # Return
+        * A Slint vector type of entries in reading order, page -1 for one without a target page
+    */
+    app.global::<BackendPDF>().on_get_toc({
+        let cloned_file_manager = file_manager.clone();
+        move || {
+            let file_path = cloned_file_manager.lock().unwrap().get_cur_path().unwrap_or_default();
+            let entries = match contents(file_path.as_str()) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("Error reading the contents of {}: {}", file_path, e);
+                    Vec::new()
+                }
+            };
+            let entries: Vec<TocItem> = entries
+                .into_iter()
+                .map(|entry| TocItem {
+                    title: entry.title.into(),
+                    page: entry.page.map_or(-1, |page| page as i32),
+                    depth: entry.depth as i32,
+                })
+                .collect();
+            slint::ModelRc::new(VecModel::from(entries))
+        }
+    });
+
+    /*  CALLBACK:
+        Stores whether the table of contents is shown next to the page
+
+        # Arguments
+        * 'shown' - true to show it
+
+        # Return
+        N/A
+    */
+    app.global::<BackendPDF>().on_set_toc_shown(store_setting(settings, Settings::set_toc_shown));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(page: Option<u32>) -> TocEntry {
+        TocEntry {
+            title: String::new(),
+            page,
+            depth: 0,
+        }
+    }
+
+    // bookmarks on pages 3, 10 and 25, zero based
+    const BOOKMARKED: [u32; 3] = [2, 9, 24];
+
+    #[test]
+    fn bookmarked_pages_are_sorted_once_each() {
+        let entries = [entry(Some(9)), entry(Some(2)), entry(None), entry(Some(9)), entry(Some(24))];
+        assert_eq!(bookmarked_pages(&entries), BOOKMARKED);
+    }
+
+    #[test]
+    fn the_nearest_bookmark_either_way() {
+        assert_eq!(step_bookmark(&BOOKMARKED, 5, true, PageBoundary::Stop), Some(9));
+        assert_eq!(step_bookmark(&BOOKMARKED, 5, false, PageBoundary::Stop), Some(2));
+        // on a bookmarked page the next one is the one after it
+        assert_eq!(step_bookmark(&BOOKMARKED, 9, true, PageBoundary::Stop), Some(24));
+    }
+
+    #[test]
+    fn stop_stays_past_the_last_and_before_the_first() {
+        assert_eq!(step_bookmark(&BOOKMARKED, 24, true, PageBoundary::Stop), None);
+        assert_eq!(step_bookmark(&BOOKMARKED, 30, true, PageBoundary::Notify), None);
+        assert_eq!(step_bookmark(&BOOKMARKED, 2, false, PageBoundary::Stop), None);
+        assert_eq!(step_bookmark(&BOOKMARKED, 0, false, PageBoundary::Notify), None);
+    }
+
+    #[test]
+    fn wrap_goes_round_to_the_other_end() {
+        assert_eq!(step_bookmark(&BOOKMARKED, 24, true, PageBoundary::Wrap), Some(2));
+        assert_eq!(step_bookmark(&BOOKMARKED, 0, false, PageBoundary::Wrap), Some(24));
+        // a single bookmark already shown has nowhere to wrap to
+        assert_eq!(step_bookmark(&[4], 4, true, PageBoundary::Wrap), None);
+        assert_eq!(step_bookmark(&[4], 7, true, PageBoundary::Wrap), Some(4));
+    }
+}
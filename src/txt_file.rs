@@ -1,13 +1,21 @@
+use crate::settings::NotesFormat;
 use native_dialog::FileDialog;
 use std::fs::File;
-use std::io::Write;
-use std::io::Result;
+use std::io::{ErrorKind, Write};
+use std::io::{Error, Result};
 use std::fs;
 
-pub fn open_file_txt() -> String {
-    if let Some(file_path) = FileDialog::new()
-    .set_location("~/Desktop")
-    .add_filter("Text file", &["txt"])
+// Bytes looked at for a null byte when telling text from binary files
+const BINARY_SNIFF_BYTES: usize = 8192;
+pub const NOT_TEXT: &str = "This file doesn't appear to be text";
+
+// The scratchpad, a note belonging to no document, next to database.json
+pub const SCRATCH_FILE: &str = "scratch.txt";
+
+// Asks for a notes file to open, listing the given format's files first. Files of the other format
+// can still be picked. "err" if the user cancelled
+pub fn open_file_txt(format: NotesFormat) -> String {
+    if let Some(file_path) = notes_dialog(format)
     .show_open_single_file()
     .unwrap() {
         match file_path.into_os_string().into_string(){
@@ -16,7 +24,25 @@ pub fn open_file_txt() -> String {
         }
     }
     return "err".to_string();
-}    
+}
+
+// Asks where to save notes that have no file yet, suggesting the name in the given format. None if
+// the user cancelled
+pub fn save_notes_dialog(suggested_name: &str, format: NotesFormat) -> Option<String> {
+    match notes_dialog(format).set_filename(suggested_name).show_save_single_file() {
+        Ok(Some(path)) => path.to_str().map(|path| path.to_string()),
+        _ => None,
+    }
+}
+
+// A file dialog listing both notes formats, the given one first so the dialog starts on it
+fn notes_dialog(format: NotesFormat) -> FileDialog<'static> {
+    let dialog = FileDialog::new().set_location("~/Desktop");
+    match format {
+        NotesFormat::Text => dialog.add_filter("Text file", &["txt"]).add_filter("Markdown", &["md"]),
+        NotesFormat::Markdown => dialog.add_filter("Markdown", &["md"]).add_filter("Text file", &["txt"]),
+    }
+}
 
 pub fn write_to_file(filename: &str, text: &str) -> Result<()> {
     let mut file = File::create(filename)?; // Opens a file in write-only mode
@@ -26,4 +52,75 @@ pub fn write_to_file(filename: &str, text: &str) -> Result<()> {
 
 pub fn read_file(filename: &str) -> Result<String> {
     fs::read_to_string(filename)
-}
\ No newline at end of file
+}
+
+// Whether the bytes look like a binary file rather than text, by a null byte near the start, which
+// text in UTF-8 never has
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(BINARY_SNIFF_BYTES).any(|&byte| byte == 0)
+}
+
+// Text of a file the user opens as notes. Unlike read_file, a binary file is refused rather than
+// shown as garbage, and invalid UTF-8 is an error
+pub fn read_text_file(filename: &str) -> Result<String> {
+    let bytes = fs::read(filename)?;
+    if looks_binary(&bytes) {
+        return Err(Error::new(ErrorKind::InvalidData, NOT_TEXT));
+    }
+    String::from_utf8(bytes).map_err(|_| Error::new(ErrorKind::InvalidData, NOT_TEXT))
+}
+
+// A path in the temp directory no other test process uses, for the tests of every module that
+// reads or writes files
+#[cfg(test)]
+pub fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("pdfer-{}-{}", std::process::id(), name));
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str, bytes: &[u8]) -> String {
+        let path = temp_path(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    // the start of a PNG, which has a null byte in its header
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+    #[test]
+    fn binary_files_are_refused_as_notes() {
+        let path = fixture("binary.md", PNG);
+        let error = read_text_file(&path).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), NOT_TEXT);
+    }
+
+    #[test]
+    fn text_files_open_as_notes() {
+        let path = fixture("notes.md", "# Chapter 1\nnaïve café\n".as_bytes());
+        let text = read_text_file(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(text.unwrap(), "# Chapter 1\nnaïve café\n");
+    }
+
+    #[test]
+    fn invalid_utf8_is_refused_too() {
+        let path = fixture("latin1.txt", b"caf\xe9");
+        let result = read_text_file(&path);
+        let _ = fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn only_the_start_is_sniffed() {
+        let mut bytes = vec![b'a'; BINARY_SNIFF_BYTES];
+        bytes.push(0);
+        assert!(!looks_binary(&bytes));
+        assert!(looks_binary(PNG));
+    }
+}